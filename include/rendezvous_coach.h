@@ -0,0 +1,61 @@
+/*
+ * C ABI of the planner of rendezvous-coach, built with the `ffi` feature as a static and a shared
+ * library, e.g. `cargo rustc --release --lib --no-default-features --features ffi --crate-type
+ * staticlib --crate-type cdylib`.
+ *
+ * Times are seconds since the Unix epoch. What is returned is owned by the caller, and given back
+ * to the matching rc_*_free function.
+ */
+
+#ifndef RENDEZVOUS_COACH_H
+#define RENDEZVOUS_COACH_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A rendezvous and the trip to it. */
+typedef struct RcPlan RcPlan;
+
+/* A notification planned. */
+typedef struct {
+    /* When it is due */
+    int64_t time;
+    /* Time remaining before departure then */
+    uint64_t remaining_secs;
+    /* 0 (low) to 3 (critical) */
+    uint8_t urgency;
+    /* What the coach says, in UTF-8 */
+    char *message;
+} RcNotification;
+
+/* The notifications planned, in time order. */
+typedef struct {
+    RcNotification *items;
+    size_t len;
+} RcSchedule;
+
+/* A plan for the rendezvous at rendezvous_time, trip_secs away, or NULL when the time or the
+ * departure is out of range. */
+RcPlan *rc_plan_new(int64_t rendezvous_time, uint64_t trip_secs);
+void rc_plan_free(RcPlan *plan);
+
+/* The departure time of plan. */
+int64_t rc_plan_departure(const RcPlan *plan);
+
+/* The notifications planned from now to departure, empty when now is out of range. */
+RcSchedule rc_plan_schedule(const RcPlan *plan, int64_t now);
+void rc_schedule_free(RcSchedule schedule);
+
+/* What the coach says with remaining_secs left before departure, e.g. "Mancano 5 minuti". */
+char *rc_remaining_message(uint64_t remaining_secs);
+void rc_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
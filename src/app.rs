@@ -0,0 +1,15 @@
+//! Terminal interface of a [`Session`](crate::session::Session), independent of the command line:
+//! the state driven by the keys and the mouse, the widget rendering it, the tabs running several
+//! sessions at once and the front-end of a session running elsewhere.
+
+#[cfg(feature = "control")]
+mod attached;
+mod render;
+mod state;
+mod tabs;
+
+#[cfg(feature = "control")]
+pub use attached::attach;
+pub use render::{GaugeDirection, GaugeStyle};
+pub use state::{AppState, Prompt, PromptKind};
+pub use tabs::Tabs;
@@ -0,0 +1,169 @@
+//! Terminal front-end of a session running elsewhere, e.g. detached, through its control socket.
+
+use std::path::{Path, PathBuf};
+
+use error_stack::ResultExt;
+use ratatui::{
+    Terminal, TerminalOptions, Viewport,
+    backend::Backend,
+    buffer::Buffer,
+    crossterm::event,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+use tracing::{debug, warn};
+
+use crate::error::{AppError, AppResult, Suggestion};
+use crate::feature::control::{default_socket_path, request};
+use crate::feature::tui::Theme;
+use crate::plan::Urgency;
+use crate::time::{TimeSpan, Timestamp};
+
+/// Follows the session answering on `socket` in the terminal, until it is over or detached from.
+pub fn attach(socket: Option<&Path>) -> AppResult<()> {
+    let socket = socket.map_or_else(default_socket_path, Path::to_path_buf);
+    let status = request(&socket, "status")
+        .change_context(AppError::Session)
+        .attach_opaque(Suggestion(
+            "start a session with --detach, or --control-socket",
+        ))?;
+    let mut attached = Attached {
+        socket,
+        status,
+        exit: false,
+    };
+    let mut terminal = ratatui::init_with_options(TerminalOptions {
+        viewport: Viewport::Inline(3),
+    });
+    let result = attached.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// Terminal front-end of a session running elsewhere, e.g. detached, through its control socket.
+struct Attached {
+    socket: PathBuf,
+    /// Last status of the session
+    status: serde_json::Value,
+    exit: bool,
+}
+
+impl Attached {
+    fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> AppResult<()> {
+        while !self.exit {
+            terminal
+                .draw(|frame| frame.render_widget(&*self, frame.area()))
+                .change_context(AppError::Terminal)
+                .attach("cannot render frame")?;
+
+            let event_available = event::poll(Timestamp::until_next_second())
+                .change_context(AppError::Terminal)
+                .attach("cannot read event")?;
+            let mut command = "status";
+            if event_available
+                && let event::Event::Key(key_event) = event::read()
+                    .change_context(AppError::Terminal)
+                    .attach("cannot read event")?
+                && key_event.kind == event::KeyEventKind::Press
+            {
+                match self.command(key_event.code) {
+                    Some(key_command) => command = key_command,
+                    None => continue,
+                }
+            }
+            match request(&self.socket, command) {
+                Ok(status) if status.get("error").is_none() => self.status = status,
+                Ok(status) => warn!("{command} refused: {}", status["error"]),
+                // Quit, or over
+                Err(report) => {
+                    debug!("the session is over: {report:?}");
+                    self.exit = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Command sent to the session for the key pressed, if any. Detaching (d, or Esc) leaves
+    /// the session running, quitting (q) abandons it.
+    fn command(&mut self, code: event::KeyCode) -> Option<&'static str> {
+        match code {
+            event::KeyCode::Char('m') if self.status["muted"] == true => Some("unmute"),
+            event::KeyCode::Char('m') => Some("mute"),
+            event::KeyCode::Char('s') => Some("speak-now"),
+            event::KeyCode::Char('+') => Some("push"),
+            event::KeyCode::Char('q') => Some("quit"),
+            event::KeyCode::Char('d') | event::KeyCode::Esc => {
+                self.exit = true;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// The remaining time to departure, colored by urgency, and whether speech is off.
+    fn countdown(&self) -> Line<'static> {
+        let remaining =
+            TimeSpan::of_seconds(self.status["remaining_seconds"].as_u64().unwrap_or(0));
+        let urgency = Urgency::for_remaining(&remaining);
+        let departure = self.status["departure_time"]
+            .as_str()
+            .and_then(|time| Timestamp::parse(time).ok())
+            .map(|time| format!(" (🚗 {})", time.to_hh_mm()))
+            .unwrap_or_default();
+        let mut line = Line::from(Span::styled(
+            format!("⏰ {remaining}{departure}"),
+            Style::default().fg(Theme::DEFAULT.gauge.get(urgency)),
+        ));
+        if self.status["muted"] == true || self.status["snoozed_until"].is_string() {
+            line.push_span(" 🔇");
+        }
+        if self.status["paused"] == true {
+            line.push_span(" ⏸");
+        }
+        line
+    }
+}
+
+impl Widget for &Attached {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [title, countdown, message] = Layout::vertical([Constraint::Length(1); 3]).areas(area);
+        Line::from(vec![
+            Span::styled(
+                "🚗 Attached",
+                Style::default().fg(Theme::DEFAULT.title).bold(),
+            ),
+            Span::raw(" | (m) Mute (s) Speak now (+) Push (d) Detach (q) Quit"),
+        ])
+        .render(title, buf);
+        self.countdown().render(countdown, buf);
+        if let Some(last) = self.status["emitted"].get(0)
+            && let Some(text) = last["message"].as_str()
+        {
+            Line::from(format!("➡ {text}")).render(message, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attached_keys_send_the_commands() {
+        let mut attached = Attached {
+            socket: default_socket_path(),
+            status: serde_json::json!({ "remaining_seconds": 272, "muted": true }),
+            exit: false,
+        };
+
+        assert_eq!(Some("unmute"), attached.command(event::KeyCode::Char('m')));
+        assert_eq!(Some("push"), attached.command(event::KeyCode::Char('+')));
+        assert!(!attached.exit);
+        assert_eq!(None, attached.command(event::KeyCode::Char('d')));
+        assert!(attached.exit);
+        assert_eq!("⏰ 00:04:32 🔇", attached.countdown().to_string());
+    }
+}
@@ -0,0 +1,1038 @@
+//! Rendering of a session in the terminal interface, inline, in full screen or on a single line.
+
+use clap::ValueEnum;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols,
+    text::{Line, Span},
+    widgets::{Block, Gauge, LineGauge, List, ListItem, Widget},
+};
+use tracing::Level;
+
+use super::state::{AppState, PromptKind};
+use crate::feature::coach::Coach;
+use crate::feature::logs::RecentLogs;
+use crate::feature::tts::{HealthMonitor, SpeechActivity};
+use crate::feature::tui::{BIG_HEIGHT, BigText, Markers, urgency_marker, wrap};
+use crate::plan::{Notification, Urgency};
+use crate::time::{TimeSpan, Timestamp};
+
+/// Widget showing the progress towards departure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GaugeStyle {
+    /// Thick line after the remaining time
+    Thick,
+    /// Bar of full blocks, with the remaining time over it
+    Block,
+    /// Line of braille dots after the remaining time
+    Braille,
+    /// Just the remaining time and its percentage
+    Percentage,
+}
+
+/// How the gauge moves as departure approaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GaugeDirection {
+    /// Emptying
+    Drain,
+    /// Filling up
+    Fill,
+}
+
+/// Line of --gauge-style braille.
+const BRAILLE: symbols::line::Set = symbols::line::Set {
+    horizontal: "⣿",
+    ..symbols::line::NORMAL
+};
+/// Below this size, the terminal interface shrinks to a single line.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 4;
+/// From this width, panels are shown side by side.
+const WIDE: u16 = 100;
+/// Lines a message wraps to at most, the last one cut with an ellipsis.
+const MESSAGE_LINES: usize = 3;
+
+fn is_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Key of a hint of the title line, e.g. `m` in ` | (m) Mute`.
+pub(super) fn hint_key(hint: &str) -> Option<char> {
+    let (_, rest) = hint.split_once('(')?;
+    let mut chars = rest.chars();
+    let key = chars.next()?;
+    (chars.next() == Some(')')).then_some(key)
+}
+impl<C: Coach> AppState<C> {
+    /// The key bound to the action of `key`, itself unless bound to another one.
+    fn key(&self, key: char) -> char {
+        self.keys
+            .iter()
+            .find(|(_, action)| **action == key)
+            .map_or(key, |(bound, _)| *bound)
+    }
+
+    /// The plan at a glance: rendezvous, trip, buffer (time given to get ready), departure and
+    /// elapsed time.
+    fn plan_summary(&self, now: &Timestamp) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "Rendezvous",
+                self.session.engine.plan.rendezvous_time.to_hh_mm(),
+            ),
+            ("Trip", self.session.engine.plan.trip_duration.to_string()),
+            ("Buffer", self.session.engine.total_time().to_string()),
+            ("Departure", self.session.engine.departure_time.to_hh_mm()),
+            ("Elapsed", self.session.engine.elapsed_time(now).to_string()),
+        ]
+    }
+
+    /// Compact plan summary, for the title line.
+    fn summary_line(&self, now: &Timestamp) -> Line<'static> {
+        let mut line = Line::default();
+        for (i, (label, value)) in self.plan_summary(now).into_iter().enumerate() {
+            if i > 0 {
+                line.push_span(Span::raw(" · "));
+            }
+            let style = if label == "Departure" {
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::ITALIC)
+            } else {
+                Style::default()
+            };
+            line.push_span(Span::raw(format!("{label} ")).add_modifier(Modifier::BOLD));
+            line.push_span(Span::styled(value, style));
+        }
+        line
+    }
+
+    /// Plan summary panel, for --fullscreen.
+    fn summary_panel(&self, now: &Timestamp) -> List<'static> {
+        let items: Vec<ListItem> = self
+            .plan_summary(now)
+            .into_iter()
+            .map(|(label, value)| {
+                let style = if label == "Departure" {
+                    Style::default()
+                        .fg(self.theme.title)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{label:<11}")),
+                    Span::styled(value, style),
+                ]))
+            })
+            .collect();
+        List::new(items)
+    }
+
+    pub(super) fn title(&self, now: &Timestamp) -> Line<'static> {
+        if let Some(prompt) = &self.prompt {
+            let mut line = Line::from(vec![Span::styled(
+                prompt.question(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )]);
+            if prompt.kind == PromptKind::Quit {
+                line.push_span(Span::raw(" (y) Yes | (any other key) No"));
+            } else {
+                line.push_span(Span::raw(format!(" {}▏", prompt.input)));
+                line.push_span(Span::raw(" | (Enter) Confirm | (Esc) Cancel"));
+            }
+            if let Some(error) = &prompt.error {
+                line.push_span(Span::styled(
+                    format!(" | {} {error}", self.glyphs.warning),
+                    Style::default().fg(self.theme.error),
+                ));
+            }
+            return line;
+        }
+        // The clock, to relate the times to now
+        let mut title = Line::from(vec![
+            Span::raw(format!("{} ", self.glyphs.clock)),
+            Span::styled(now.to_hh_mm_ss(), Style::default().fg(self.theme.time)),
+            Span::raw(" | "),
+        ]);
+        // The summary has a panel of its own in full screen
+        if self.fullscreen {
+            title.push_span(Span::styled(
+                format!("{} Rendezvous coach", self.glyphs.car),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        } else {
+            title.extend(self.summary_line(now));
+        }
+        // Hints of the keys, as bound
+        let hint = |key, action| format!(" | ({}) {action}", self.key(key));
+        title.extend([
+            Span::raw(if self.session.engine.muted {
+                format!(" | {}{}", self.glyphs.muted, hint('m', "Unmute"))
+            } else {
+                hint('m', "Mute")
+            }),
+            Span::raw(if self.session.engine.paused {
+                format!(" | {}{}", self.glyphs.paused, hint('p', "Resume"))
+            } else {
+                hint('p', "Pause")
+            }),
+            Span::raw(hint('+', "Later")),
+            Span::raw(hint('t', "Trip")),
+            Span::raw(hint('e', "Rendezvous")),
+            Span::raw(hint('r', "Repeat")),
+            Span::raw(hint('n', "Next")),
+            Span::raw(if self.show_logs {
+                hint('l', "History")
+            } else {
+                hint('l', "Log")
+            }),
+            Span::raw(if self.count_up {
+                hint('c', "Count down")
+            } else {
+                hint('c', "Count up")
+            }),
+            Span::raw(hint('q', "Quit")),
+        ]);
+        if let Some(until) = self
+            .session
+            .engine
+            .snoozed_until
+            .filter(|_| !self.session.engine.muted)
+            && until > *now
+        {
+            title.push_span(Span::raw(format!(
+                " | {} Snoozed until {until}",
+                self.glyphs.snoozed
+            )));
+        }
+        if let Some(warning) = &self.speech_warning {
+            title.push_span(Span::styled(
+                format!(" | {} {warning}", self.glyphs.warning),
+                Style::default().fg(self.theme.warning),
+            ));
+        }
+        if let Some(health) = self.speaker_health.as_ref().map(HealthMonitor::current) {
+            if let Some(failure) = health.failure {
+                title.push_span(Span::styled(
+                    format!(" | {} Speech failing: {failure}", self.glyphs.warning),
+                    Style::default().fg(self.theme.error),
+                ));
+            } else if health.restarts > 0 {
+                title.push_span(Span::styled(
+                    format!(" | Speech restarted {}×", health.restarts),
+                    Style::default().fg(self.theme.warning),
+                ));
+            }
+        }
+        title
+    }
+
+    /// Remaining time, or how late it is with --overdue (e.g. `−00:02:13`), and its style.
+    fn counter(&self, now: &Timestamp) -> (String, Style) {
+        if let Some(overdue_time) = self.session.engine.overdue_time(now) {
+            return (
+                format!("−{overdue_time}"),
+                Style::default().fg(self.theme.overdue),
+            );
+        }
+        let remaining_time = self.session.engine.remaining_time(now);
+        let urgency = Urgency::for_remaining(&remaining_time);
+        let style = self
+            .alarm_style(&remaining_time)
+            .fg(self.theme.countdown.get(urgency));
+        (remaining_time.to_string(), style)
+    }
+
+    /// Status bar: the speak-now action, flashing while speech is in progress to tell that it
+    /// works, the time to the next notification, the speech backend and the sinks, with their
+    /// failures.
+    fn footer(&self, now: &Timestamp) -> Line<'static> {
+        let mut footer = if self
+            .speech_activity
+            .as_ref()
+            .is_some_and(SpeechActivity::is_speaking)
+        {
+            Line::from(Span::styled(
+                format!(" {} Speaking… ", self.glyphs.speaking),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::REVERSED | Modifier::SLOW_BLINK),
+            ))
+        } else {
+            Line::from(format!(" ({}) Speak now ", self.key('s')))
+        };
+        // Dropped while paused, rather than to come
+        if let Some(next) = self
+            .session
+            .engine
+            .notifications
+            .pending
+            .next()
+            .filter(|_| !self.session.engine.paused)
+        {
+            footer.push_span(Span::raw(format!(
+                "| {} Next in {} ",
+                self.glyphs.hourglass,
+                next.time.time_span_from(now)
+            )));
+        }
+        if let Some(backend) = &self.speech_backend {
+            footer.push_span(Span::raw(format!("| {} {backend} ", self.glyphs.voice)));
+        }
+        if self.session.engine.muted {
+            footer.push_span(Span::raw(format!("| {} muted ", self.glyphs.muted)));
+        }
+        for (name, failures) in self.session.sinks.failures() {
+            if failures == 0 {
+                footer.push_span(Span::raw(format!("| {name} {} ", self.glyphs.ok)));
+            } else {
+                footer.push_span(Span::styled(
+                    format!("| {name} {} {failures} failed ", self.glyphs.failed),
+                    Style::default().fg(self.theme.error),
+                ));
+            }
+        }
+        footer
+    }
+
+    pub(super) fn gauge_label(&self, now: &Timestamp) -> Line<'static> {
+        let remaining_time = self.session.engine.remaining_time(now);
+        let urgency = Urgency::for_remaining(&remaining_time);
+        let (counter, style) = self.counter(now);
+        let (title, icon, time) = if self.session.engine.overdue_time(now).is_some() {
+            ("Overdue", self.glyphs.alarm, counter)
+        } else if self.count_up {
+            (
+                "Elapsed time",
+                self.glyphs.stopwatch,
+                self.session.engine.elapsed_time(now).to_string(),
+            )
+        } else {
+            ("Remaining time", self.glyphs.alarm, counter)
+        };
+        let mut label = Line::from(vec![
+            Span::raw(title).add_modifier(Modifier::BOLD),
+            Span::raw(format!(" {icon} ")),
+            Span::styled(time, style),
+        ]);
+        if self.high_contrast {
+            label.push_span(Span::raw(format!(" {}", urgency_marker(urgency))));
+        }
+        label
+    }
+
+    fn gauge_fill_style(&self, now: &Timestamp) -> Style {
+        if self.session.engine.overdue_time(now).is_some() {
+            return Style::default().fg(self.theme.overdue);
+        }
+        let remaining_time = self.session.engine.remaining_time(now);
+        let urgency = Urgency::for_remaining(&remaining_time);
+        self.alarm_style(&remaining_time)
+            .fg(self.theme.gauge.get(urgency))
+    }
+
+    fn line_gauge(&self, now: &Timestamp, line_set: symbols::line::Set) -> LineGauge<'static> {
+        LineGauge::default()
+            .filled_style(self.gauge_fill_style(now))
+            .line_set(line_set)
+            .label(self.gauge_label(now))
+            .ratio(self.frame_ratio(now))
+    }
+
+    /// Where the pending notifications fall along the gauge, starting at `offset`.
+    fn timeline(&self, now: &Timestamp, offset: u16) -> Markers {
+        self.session
+            .engine
+            .notifications
+            .pending
+            .iter()
+            .fold(Markers::new(offset), |markers, n| {
+                let ratio = self.gauge_ratio(&n.time);
+                markers.mark(ratio, Style::default().fg(self.theme.gauge.get(n.urgency)))
+            })
+    }
+
+    /// Renders the gauge, with the timeline on the line below when there is room.
+    fn render_progress(&self, now: &Timestamp, area: Rect, buf: &mut Buffer) {
+        let [gauge_area, timeline_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(area);
+        // The line of the gauge starts one column after its label
+        let line_offset = self.gauge_label(now).width() as u16 + 1;
+        match self.gauge_style {
+            GaugeStyle::Thick => {
+                self.line_gauge(now, symbols::line::THICK)
+                    .render(gauge_area, buf);
+                self.timeline(now, line_offset).render(timeline_area, buf);
+            }
+            GaugeStyle::Braille => {
+                self.line_gauge(now, BRAILLE).render(gauge_area, buf);
+                self.timeline(now, line_offset).render(timeline_area, buf);
+            }
+            GaugeStyle::Block => {
+                Gauge::default()
+                    .gauge_style(self.gauge_fill_style(now))
+                    .label(self.gauge_label(now).to_string())
+                    .ratio(self.frame_ratio(now))
+                    .use_unicode(true)
+                    .render(gauge_area, buf);
+                self.timeline(now, 0).render(timeline_area, buf);
+            }
+            GaugeStyle::Percentage => {
+                let mut label = self.gauge_label(now);
+                label.push_span(Span::styled(
+                    format!(" {:.0}%", self.frame_ratio(now) * 100.0),
+                    self.gauge_fill_style(now),
+                ));
+                label.render(gauge_area, buf);
+            }
+        }
+    }
+
+    /// The time and message of `n`, wrapped to `width` columns under its start.
+    fn notification_line(&self, n: &Notification, style: Style, width: u16) -> ListItem<'static> {
+        let mut spans = vec![
+            Span::styled(format!("{}", n.time), Style::default().fg(self.theme.time)),
+            Span::raw(format!(" {} ", self.glyphs.arrow)),
+        ];
+        if self.high_contrast {
+            spans.push(Span::raw(format!("[{}] ", urgency_marker(n.urgency))));
+        }
+        let indent: usize = spans.iter().map(Span::width).sum();
+        let style = style.fg(self.theme.urgency(n.urgency));
+        let mut lines = wrap(
+            &n.message,
+            usize::from(width).saturating_sub(indent),
+            MESSAGE_LINES,
+        )
+        .into_iter();
+        spans.push(Span::styled(lines.next().unwrap_or_default(), style));
+        let mut text = vec![Line::from(spans)];
+        text.extend(lines.map(|line| {
+            Line::from(vec![
+                Span::raw(" ".repeat(indent)),
+                Span::styled(line, style),
+            ])
+        }));
+        ListItem::new(text)
+    }
+
+    /// Notifications emitted, latest first, `width` columns wide.
+    fn history(&self, width: u16) -> List<'static> {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let items: Vec<ListItem> = self
+            .session
+            .engine
+            .notifications
+            .emitted
+            .iter()
+            .skip(self.history_scroll)
+            .map(|n| self.notification_line(n, bold, width))
+            .collect();
+        List::new(items)
+    }
+
+    /// The last log events, latest first, colored by level.
+    fn log(&self) -> List<'static> {
+        let lines = self
+            .logs
+            .as_ref()
+            .map(RecentLogs::lines)
+            .unwrap_or_default();
+        let items: Vec<ListItem> = lines
+            .into_iter()
+            .skip(self.history_scroll)
+            .map(|line| {
+                let color = match line.level {
+                    Level::ERROR => self.theme.error,
+                    Level::WARN => self.theme.warning,
+                    _ => Color::Reset,
+                };
+                ListItem::new(Line::styled(line.text, Style::default().fg(color)))
+            })
+            .collect();
+        List::new(items)
+    }
+
+    /// Notifications still to come, next first, `width` columns wide.
+    fn schedule(&self, width: u16) -> List<'static> {
+        let items: Vec<ListItem> = self
+            .session
+            .engine
+            .notifications
+            .pending
+            .iter()
+            .map(|n| self.notification_line(n, Style::default(), width))
+            .collect();
+        List::new(items)
+    }
+
+    /// Lines of the inline viewport, in a terminal `width` columns wide.
+    pub fn inline_height(&self, width: u16) -> u16 {
+        if self.minimal {
+            return 1;
+        }
+        // Room for the longest message to wrap, within the margin
+        let wrapped = self
+            .session
+            .engine
+            .notifications
+            .pending
+            .iter()
+            .map(|n| {
+                self.notification_line(n, Style::default(), width.saturating_sub(2))
+                    .height()
+            })
+            .max()
+            .unwrap_or(1);
+        // 1 (departure time) +
+        // 1 (remaining w/ line gauge) +
+        // (big countdown) +
+        // (max number of messages, and the lines of a wrapped one)
+        let countdown = if self.big_countdown { BIG_HEIGHT } else { 0 };
+        2 + countdown + self.session.engine.notifications.max_emitted as u16 + wrapped as u16 - 1
+    }
+
+    fn render_inline(&self, area: Rect, buf: &mut Buffer) {
+        let now = Timestamp::now().unwrap();
+        // Bordered in the margin left around the content
+        let block = match self.alarm_border(&now) {
+            Some(style) => Block::bordered().border_style(style),
+            None => Block::new(),
+        };
+        block
+            .title(self.title(&now).centered())
+            .title_bottom(self.footer(&now).centered())
+            .render(area, buf);
+
+        // Giving up the large digits and the schedule first when the terminal gets small
+        let big_countdown = self.big_countdown && area.height >= BIG_HEIGHT + 5;
+        let countdown = if big_countdown { BIG_HEIGHT } else { 0 };
+        let vertical = Layout::vertical([
+            Constraint::Length(countdown),
+            Constraint::Length(2),
+            Constraint::Length(self.session.engine.notifications.max_emitted as u16),
+        ])
+        .margin(1);
+        let [countdown_area, progress_area, main] = vertical.areas(area);
+
+        if big_countdown {
+            self.countdown(&now).render(countdown_area, buf);
+        }
+        self.render_progress(&now, progress_area, buf);
+        if self.is_alarming(&now) {
+            // The viewport cannot grow, so the history makes room for the large digits
+            self.countdown(&now).render(main, buf);
+        } else if self.show_logs {
+            self.log().render(main, buf);
+        } else if self.show_schedule && area.width >= WIDE {
+            let [history_area, schedule_area] =
+                Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .areas(main);
+            self.history(history_area.width).render(history_area, buf);
+            self.schedule(schedule_area.width)
+                .block(Block::new().title(" Next ".bold()))
+                .render(schedule_area, buf);
+        } else {
+            self.history(main.width).render(main, buf);
+        }
+    }
+
+    /// Whether the interface fits on a single line in `area`, with --minimal or when there is
+    /// no room for more.
+    pub(super) fn is_minimal(&self, area: Rect) -> bool {
+        self.minimal || is_small(area)
+    }
+
+    /// Single line with the remaining time and the last message.
+    fn render_minimal(&self, area: Rect, buf: &mut Buffer) {
+        let now = Timestamp::now().unwrap();
+        let urgency = Urgency::for_remaining(&self.session.engine.remaining_time(&now));
+        let (counter, style) = self.counter(&now);
+        let mut line = Line::from(vec![
+            Span::raw(format!("{} ", self.glyphs.alarm)),
+            Span::styled(counter, style.add_modifier(Modifier::BOLD)),
+        ]);
+        if self.high_contrast {
+            line.push_span(Span::raw(format!(" {}", urgency_marker(urgency))));
+        }
+        if let Some(n) = self.session.engine.notifications.emitted.front() {
+            line.push_span(Span::raw(format!(" {} ", self.glyphs.arrow)));
+            line.push_span(Span::styled(
+                n.message.clone(),
+                Style::default().fg(self.theme.urgency(n.urgency)),
+            ));
+        }
+        line.render(area, buf);
+    }
+
+    /// In the last minute before departure, with --visual-alarm.
+    fn is_alarming(&self, now: &Timestamp) -> bool {
+        let remaining_time = self.session.engine.remaining_time(now);
+        self.visual_alarm && !remaining_time.is_zero() && remaining_time <= TimeSpan::of_minutes(1)
+    }
+
+    /// Style of the blinking border of the visual alarm.
+    fn alarm_border(&self, now: &Timestamp) -> Option<Style> {
+        self.is_alarming(now).then(|| {
+            Style::default()
+                .fg(self.theme.countdown.critical)
+                .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK)
+        })
+    }
+
+    /// Inverts the colors of `area` every other second of the visual alarm.
+    fn flash(&self, now: &Timestamp, area: Rect, buf: &mut Buffer) {
+        if self.is_alarming(now) && self.session.engine.remaining_time(now).total_secs() % 2 == 0 {
+            buf.set_style(area, Style::default().add_modifier(Modifier::REVERSED));
+        }
+    }
+
+    /// Blinking in the last minute, with --blink.
+    fn alarm_style(&self, remaining_time: &TimeSpan) -> Style {
+        if self.blink && !remaining_time.is_zero() && *remaining_time <= TimeSpan::of_minutes(1) {
+            Style::default().add_modifier(Modifier::SLOW_BLINK)
+        } else {
+            Style::default()
+        }
+    }
+
+    fn countdown(&self, now: &Timestamp) -> BigText {
+        let (counter, style) = self.counter(now);
+        BigText::new(counter).style(style.add_modifier(Modifier::BOLD))
+    }
+
+    /// Layout of --fullscreen: the plan summary next to the countdown in large, with the
+    /// schedule and the history side by side.
+    ///
+    /// The panels are given up as the terminal gets small, down to the countdown on a line and
+    /// the history.
+    fn render_fullscreen(&self, area: Rect, buf: &mut Buffer) {
+        let now = Timestamp::now().unwrap();
+        let mut block = Block::bordered()
+            .title(self.title(&now).centered())
+            .title_bottom(self.footer(&now).centered());
+        if let Some(style) = self.alarm_border(&now) {
+            block = block.border_style(style);
+        }
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // Room left for at least 3 messages below the large digits
+        let alarming = self.is_alarming(&now);
+        let tall = alarming || inner.height >= BIG_HEIGHT + 2 + 2 + 5 + 2;
+        let [top, progress_area, panels] = Layout::vertical([
+            Constraint::Length(if tall { BIG_HEIGHT + 2 } else { 1 }),
+            Constraint::Length(2),
+            Constraint::Min(0),
+        ])
+        .margin(1)
+        .areas(inner);
+        if tall && inner.width >= WIDE && !alarming {
+            let [summary_area, countdown_area] =
+                Layout::horizontal([Constraint::Length(24), Constraint::Min(0)]).areas(top);
+            self.summary_panel(&now)
+                .block(Block::bordered().title(" Plan "))
+                .render(summary_area, buf);
+            self.countdown(&now).render(countdown_area, buf);
+        } else {
+            self.countdown(&now).render(top, buf);
+        }
+        self.render_progress(&now, progress_area, buf);
+
+        let history_area = if self.show_schedule && inner.width >= WIDE {
+            let [schedule_area, history_area] =
+                Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                    .areas(panels);
+            // Within the borders
+            self.schedule(schedule_area.width.saturating_sub(2))
+                .block(Block::bordered().title(" Schedule "))
+                .render(schedule_area, buf);
+            history_area
+        } else {
+            panels
+        };
+        let history = if self.show_logs {
+            self.log().block(Block::bordered().title(" Log "))
+        } else {
+            self.history(history_area.width.saturating_sub(2))
+                .block(Block::bordered().title(" History "))
+        };
+        history.render(history_area, buf);
+    }
+}
+
+impl<C: Coach> Widget for &AppState<C> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.is_minimal(area) {
+            self.render_minimal(area, buf);
+        } else if self.fullscreen {
+            self.render_fullscreen(area, buf);
+        } else {
+            self.render_inline(area, buf);
+        }
+        if let Ok(now) = Timestamp::now() {
+            self.flash(&now, area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event;
+
+    use super::*;
+    use crate::feature::coach::DefaultItCoach;
+    use crate::feature::sink::{JsonLinesSink, SinkFilter};
+    use crate::feature::tui::Glyphs;
+    use crate::plan::Plan;
+
+    #[test]
+    fn app_state_high_contrast_marks_the_urgency() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.high_contrast = true;
+        state.session.engine.notifications.emit(Notification {
+            time: Timestamp::new(2025, 10, 24, 17, 42, 00).unwrap(),
+            message: "Mancano 3 minuti".to_owned(),
+            urgency: Urgency::High,
+        });
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 200, 10));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("[● HIGH] Mancano 3 minuti"));
+        assert!(screen.contains("‼ GO"));
+    }
+
+    #[test]
+    fn app_state_n_key_shows_the_next_notifications() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        let next = state
+            .session
+            .engine
+            .notifications
+            .pending
+            .next()
+            .unwrap()
+            .message
+            .clone();
+        let screen = |state: &AppState<DefaultItCoach>| {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 200, 8));
+            state.render(buf.area, &mut buf);
+            buf.content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        assert!(!screen(&state).contains(&next));
+
+        state.handle_key(event::KeyCode::Char('n'));
+
+        assert!(screen(&state).contains(&next));
+    }
+
+    #[test]
+    fn app_state_l_key_shows_the_log() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        let logs = RecentLogs::new(10);
+        let subscriber = tracing_subscriber::registry().with(logs.clone());
+        tracing::subscriber::with_default(subscriber, || warn!("cannot deliver to mqtt"));
+        state.logs = Some(logs);
+        let screen = |state: &AppState<DefaultItCoach>| {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 200, 8));
+            state.render(buf.area, &mut buf);
+            buf.content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        assert!(!screen(&state).contains("cannot deliver to mqtt"));
+
+        state.handle_key(event::KeyCode::Char('l'));
+
+        assert!(screen(&state).contains("cannot deliver to mqtt"));
+    }
+
+    #[test]
+    fn app_state_fullscreen_shows_the_schedule() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.fullscreen = true;
+        state.show_schedule = true;
+        let next = state
+            .session
+            .engine
+            .notifications
+            .pending
+            .next()
+            .unwrap()
+            .message
+            .clone();
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 200, 20));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("Schedule"));
+        assert!(screen.contains("History"));
+        assert!(screen.contains(&next));
+    }
+
+    #[test]
+    fn app_state_shows_the_plan_summary() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 250, 8));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("Rendezvous 18:00 · Trip 00:15:00"));
+        assert!(screen.contains("Departure 17:45 · Elapsed "));
+        assert!(screen.contains(" | Rendezvous 18:00"));
+    }
+
+    #[test]
+    fn app_state_shrinks_to_a_line_in_a_small_terminal() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.session.engine.notifications.emit(Notification {
+            time: Timestamp::now().unwrap(),
+            message: "Mancano 45 minuti".to_owned(),
+            urgency: Urgency::Low,
+        });
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 60, 2));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("➡ Mancano 45 minuti"));
+        assert!(!screen.contains("Remaining time"));
+    }
+
+    #[test]
+    fn app_state_minimal_is_a_single_line() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 10).unwrap();
+        state.minimal = true;
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 200, state.inline_height(200)));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert_eq!(1, state.inline_height(200));
+        assert!(screen.starts_with("⏰"));
+        assert!(screen.contains(" 00:4"));
+    }
+
+    #[test]
+    fn app_state_ascii_glyphs_replace_the_emoji() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 10).unwrap();
+        state.glyphs = Glyphs::ASCII;
+        state.fullscreen = true;
+        state.session.engine.muted = true;
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 200, 30));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("[>] Rendezvous coach"));
+        assert!(screen.contains("(muted) (m) Unmute"));
+        assert!(screen.contains("Remaining time (!) 00:"));
+        assert!(!screen.chars().any(|c| "🚗⏰🕐🔇".contains(c)));
+    }
+
+    #[test]
+    fn app_state_footer_shows_the_speak_now_action() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.area = Rect::new(0, 0, 200, 8);
+
+        let mut buf = Buffer::empty(state.area);
+        (&state).render(buf.area, &mut buf);
+        let footer: String = (0..buf.area.width).map(|x| buf[(x, 7)].symbol()).collect();
+        state.handle_mouse(mouse(
+            event::MouseEventKind::Down(event::MouseButton::Left),
+            100,
+            7,
+        ));
+
+        assert!(footer.contains("(s) Speak now"));
+        assert!(state.session.engine.update_requested);
+    }
+
+    #[test]
+    fn app_state_footer_shows_the_speech_and_the_sinks() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.speech_backend = Some("sherpa".to_owned());
+        state.session.engine.muted = true;
+        let json = JsonLinesSink::new(Vec::new());
+        state
+            .session
+            .sinks
+            .add("json", SinkFilter::ALL, Box::new(json));
+
+        let now = Timestamp::now().unwrap();
+        let next = state
+            .session
+            .engine
+            .notifications
+            .pending
+            .next()
+            .unwrap()
+            .time;
+
+        let footer = state.footer(&now).to_string();
+
+        assert_eq!(
+            format!(
+                " (s) Speak now | ⏳ Next in {} | 🗣 sherpa | 🔇 muted | json ✓ ",
+                next.time_span_from(&now)
+            ),
+            footer
+        );
+        state.session.engine.paused = true;
+        assert!(!state.footer(&now).to_string().contains("Next in"));
+    }
+
+    #[test]
+    fn app_state_c_key_counts_the_elapsed_time_up() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        let now = Timestamp::now().unwrap() + TimeSpan::of_minutes(15);
+
+        state.handle_key(event::KeyCode::Char('c'));
+
+        assert!(state.count_up);
+        assert!(
+            state
+                .gauge_label(&now)
+                .to_string()
+                .starts_with("Elapsed time ⏱ 00:15")
+        );
+        assert!((state.gauge_ratio(&now) - 1.0 / 3.0).abs() < 0.01);
+    }
+
+    fn mouse(kind: event::MouseEventKind, column: u16, row: u16) -> event::MouseEvent {
+        event::MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: event::KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn app_state_mouse_clicks_the_hints_and_scrolls_the_history() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        for i in 1..=3 {
+            state.session.engine.notifications.emit(Notification {
+                time: Timestamp::now().unwrap(),
+                message: format!("{i}"),
+                urgency: Urgency::Low,
+            });
+        }
+        state.area = Rect::new(0, 0, 250, 8);
+        let mut buf = Buffer::empty(state.area);
+        (&state).render(buf.area, &mut buf);
+        let mute = (0..buf.area.width)
+            .find(|&x| buf[(x, 0)].symbol() == "(" && buf[(x + 1, 0)].symbol() == "m")
+            .unwrap();
+
+        let click = event::MouseEventKind::Down(event::MouseButton::Left);
+        state.handle_mouse(mouse(click, mute, 0));
+        state.handle_mouse(mouse(event::MouseEventKind::ScrollDown, 10, 5));
+
+        assert!(state.session.engine.muted);
+        assert_eq!(1, state.history_scroll);
+        for _ in 0..5 {
+            state.handle_mouse(mouse(event::MouseEventKind::ScrollDown, 10, 5));
+        }
+        assert_eq!(2, state.history_scroll);
+    }
+
+    #[test]
+    fn app_state_visual_alarm_in_the_last_minute() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::new(0, 15, 30),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 10).unwrap();
+        let now = Timestamp::now().unwrap();
+        assert!(!state.is_alarming(&now));
+
+        state.visual_alarm = true;
+        let mut buf = Buffer::empty(Rect::new(0, 0, 200, 12));
+        (&state).render(buf.area, &mut buf);
+
+        assert!(state.is_alarming(&now));
+        assert!(!state.is_alarming(&(now + TimeSpan::of_minutes(1))));
+        assert_eq!("┌", buf[(0, 0)].symbol());
+    }
+
+    #[test]
+    fn app_state_gauge_style_and_direction() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.gauge_style = GaugeStyle::Percentage;
+        state.gauge_direction = GaugeDirection::Fill;
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 200, 8));
+        (&state).render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains(" 0%"));
+        state.handle_key(event::KeyCode::Char('c'));
+        assert!(state.gauge_ratio(&Timestamp::now().unwrap()) > 0.99);
+    }
+}
@@ -0,0 +1,603 @@
+//! State of a session of the terminal interface: the session counting down, and what is shown and
+//! how, driven by the keys and the mouse.
+
+use std::collections::HashMap;
+
+use ratatui::{Frame, crossterm::event, layout::Rect};
+use tracing::warn;
+
+use super::render::{GaugeDirection, GaugeStyle, hint_key};
+use crate::error::AppResult;
+use crate::feature::coach::Coach;
+use crate::feature::logs::RecentLogs;
+use crate::feature::tts::{HealthMonitor, SpeechActivity};
+use crate::feature::tui::{Glyphs, Theme};
+use crate::plan::Plan;
+use crate::session::Session;
+use crate::time::{TimeSpan, Timestamp};
+
+/// What a prompt asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    /// New trip duration
+    Trip,
+    /// New rendezvous time
+    Rendezvous,
+    /// Confirmation before abandoning the session
+    Quit,
+}
+
+/// Answer being typed to a prompt of the terminal interface.
+#[derive(Debug)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+    /// Why the last answer was rejected
+    pub error: Option<String>,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind) -> Self {
+        Self {
+            kind,
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn question(&self) -> &'static str {
+        match self.kind {
+            PromptKind::Trip => "New trip duration (HH:MM):",
+            PromptKind::Rendezvous => "New rendezvous time (HH:MM, or YYYY-MM-DD HH:MM):",
+            PromptKind::Quit => "Abandon the rendezvous?",
+        }
+    }
+}
+
+pub struct AppState<C> {
+    /// The countdown, with where its notifications go
+    pub session: Session<C>,
+    /// Why messages are not spoken, when speech is not available
+    pub speech_warning: Option<String>,
+    pub speaker_health: Option<HealthMonitor>,
+    /// Whether the speaker is speaking, for the footer
+    pub speech_activity: Option<SpeechActivity>,
+    /// Name of the speech backend in use, for the footer
+    pub speech_backend: Option<String>,
+    /// The q key asking for a confirmation before abandoning the session
+    pub confirm_quit: bool,
+    /// Prompt being answered, taking the keys
+    pub prompt: Option<Prompt>,
+    /// Rendered taking the whole terminal
+    pub fullscreen: bool,
+    /// Rendered on a single line
+    pub minimal: bool,
+    /// Remaining time shown in large digits in the inline viewport
+    pub big_countdown: bool,
+    /// The pending notifications shown beside the history
+    pub show_schedule: bool,
+    /// The last log events, shown in place of the history
+    pub logs: Option<RecentLogs>,
+    pub show_logs: bool,
+    /// The gauge counting the elapsed time up, instead of the remaining time down
+    pub count_up: bool,
+    pub gauge_style: GaugeStyle,
+    pub gauge_direction: GaugeDirection,
+    /// Part of the current second gone by when drawing, for the gauge to move within it
+    pub frame_fraction: f64,
+    pub theme: Theme,
+    pub glyphs: Glyphs,
+    /// Keys bound to the actions, to the keys of the actions by default
+    pub keys: HashMap<char, char>,
+    /// Urgency shown by symbols and text besides colors
+    pub high_contrast: bool,
+    /// Remaining time blinking in the last minute
+    pub blink: bool,
+    /// Flashing interface in the last minute
+    pub visual_alarm: bool,
+    /// Emitted notifications scrolled past, latest first
+    pub history_scroll: usize,
+    /// Where the last frame was rendered, for the mouse
+    pub area: Rect,
+}
+
+impl<C: Coach> AppState<C> {
+    pub fn new(plan: &Plan, coach: C, max_messages: usize) -> AppResult<Self> {
+        Ok(Self::with_session(Session::new(plan, coach, max_messages)?))
+    }
+
+    /// The terminal interface of `session`.
+    pub fn with_session(session: Session<C>) -> Self {
+        Self {
+            session,
+            speech_warning: None,
+            speaker_health: None,
+            speech_activity: None,
+            speech_backend: None,
+            confirm_quit: false,
+            prompt: None,
+            fullscreen: false,
+            minimal: false,
+            big_countdown: false,
+            show_schedule: false,
+            logs: None,
+            show_logs: false,
+            count_up: false,
+            gauge_style: GaugeStyle::Thick,
+            gauge_direction: GaugeDirection::Drain,
+            frame_fraction: 0.0,
+            theme: Theme::DEFAULT,
+            glyphs: Glyphs::EMOJI,
+            keys: HashMap::new(),
+            high_contrast: false,
+            blink: false,
+            visual_alarm: false,
+            history_scroll: 0,
+            area: Rect::default(),
+        }
+    }
+
+    /// Changes the plan as answered to the prompt, or asks again when the answer is invalid.
+    fn answer_prompt(&mut self) {
+        let Some(mut prompt) = self.prompt.take() else {
+            return;
+        };
+        let input = prompt.input.trim();
+        let plan = match prompt.kind {
+            PromptKind::Trip => TimeSpan::parse(input)
+                .map(|trip_duration| Plan {
+                    trip_duration,
+                    ..self.session.engine.plan.clone()
+                })
+                .map_err(|_| format!("invalid trip duration `{input}`")),
+            PromptKind::Rendezvous => Timestamp::parse(input)
+                .or_else(|_| Timestamp::parse_today_time(input))
+                .map(|rendezvous_time| Plan {
+                    rendezvous_time,
+                    ..self.session.engine.plan.clone()
+                })
+                .map_err(|_| format!("invalid rendezvous time `{input}`")),
+            PromptKind::Quit => unreachable!("answered by a single key"),
+        };
+        match plan.and_then(|plan| {
+            let now = Timestamp::now().map_err(|e| e.to_string())?;
+            self.session
+                .engine
+                .replan(plan, &now)
+                .map_err(|e| e.to_string())
+        }) {
+            Ok(()) => {}
+            Err(error) => {
+                prompt.error = Some(error);
+                self.prompt = Some(prompt);
+            }
+        }
+    }
+
+    /// Filled part of the gauge: the remaining time when draining, or the elapsed time when
+    /// filling up, the other way round when counting up.
+    pub(super) fn gauge_ratio(&self, now: &Timestamp) -> f64 {
+        if self.session.engine.overdue_time(now).is_some() {
+            // Over: full, whatever the direction
+            1.0
+        } else if (self.gauge_direction == GaugeDirection::Drain) != self.count_up {
+            self.session.engine.remaining_ratio(now)
+        } else {
+            1.0 - self.session.engine.remaining_ratio(now)
+        }
+    }
+
+    /// [`Self::gauge_ratio`] at the frame being drawn, moving on towards the next second.
+    pub(super) fn frame_ratio(&self, now: &Timestamp) -> f64 {
+        let ratio = self.gauge_ratio(now);
+        let next = self.gauge_ratio(&(*now + TimeSpan::of_seconds(1)));
+        ratio + (next - ratio) * self.frame_fraction
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        self.area = area;
+        frame.render_widget(&*self, area);
+    }
+
+    pub fn handle_event(&mut self, event: event::Event) {
+        match event {
+            event::Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press => {
+                self.handle_key(key_event.code);
+            }
+            event::Event::Mouse(mouse_event) => self.handle_mouse(mouse_event),
+            // Laid out again for the new size right away, by the next frame
+            event::Event::Resize(_, _) => (),
+            _ => (),
+        }
+    }
+
+    pub fn handle_mouse(&mut self, mouse_event: event::MouseEvent) {
+        match mouse_event.kind {
+            event::MouseEventKind::ScrollDown => {
+                let oldest = self
+                    .session
+                    .engine
+                    .notifications
+                    .emitted
+                    .len()
+                    .saturating_sub(1);
+                self.history_scroll = (self.history_scroll + 1).min(oldest);
+            }
+            event::MouseEventKind::ScrollUp => {
+                self.history_scroll = self.history_scroll.saturating_sub(1);
+            }
+            event::MouseEventKind::Down(event::MouseButton::Left)
+                if self.prompt.is_none() && !self.is_minimal(self.area) =>
+            {
+                if mouse_event.row == self.area.y
+                    && let Ok(now) = Timestamp::now()
+                {
+                    self.click_title(mouse_event.column, &now);
+                } else if mouse_event.row == self.area.bottom() - 1 {
+                    // The footer, with the speak-now action alone
+                    self.session.engine.update_requested = true;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Presses the key of the hint clicked on the title line, or ends the snooze when clicking
+    /// its indicator.
+    fn click_title(&mut self, column: u16, now: &Timestamp) {
+        let title = self.title(now);
+        // Centered, inside the border in full screen
+        let border = u16::from(self.fullscreen);
+        let free = self
+            .area
+            .width
+            .saturating_sub(2 * border)
+            .saturating_sub(title.width() as u16);
+        let mut start = self.area.x + border + free / 2;
+        for span in &title.spans {
+            let end = start + span.width() as u16;
+            if (start..end).contains(&column) {
+                if span.content.contains(self.glyphs.snoozed) {
+                    self.session.engine.snoozed_until = None;
+                } else if let Some(key) = hint_key(&span.content) {
+                    self.handle_key(event::KeyCode::Char(key));
+                }
+                return;
+            }
+            start = end;
+        }
+    }
+
+    pub fn handle_key(&mut self, code: event::KeyCode) {
+        if let Some(prompt) = &mut self.prompt {
+            if prompt.kind == PromptKind::Quit {
+                // Anything but a yes keeps the session going
+                self.session.exit = matches!(
+                    code,
+                    event::KeyCode::Char('y' | 'Y') | event::KeyCode::Enter
+                );
+                self.prompt = None;
+                return;
+            }
+            match code {
+                event::KeyCode::Char(c) => prompt.input.push(c),
+                event::KeyCode::Backspace => {
+                    prompt.input.pop();
+                }
+                event::KeyCode::Esc => self.prompt = None,
+                event::KeyCode::Enter => self.answer_prompt(),
+                _ => (),
+            }
+            return;
+        }
+        let code = match code {
+            event::KeyCode::Char(key) => {
+                event::KeyCode::Char(self.keys.get(&key).copied().unwrap_or(key))
+            }
+            code => code,
+        };
+        match code {
+            event::KeyCode::Char('q') if self.confirm_quit => {
+                self.prompt = Some(Prompt::new(PromptKind::Quit));
+            }
+            event::KeyCode::Char('q') => self.session.exit = true,
+            event::KeyCode::Char('m') => self.session.engine.muted = !self.session.engine.muted,
+            event::KeyCode::Char('s') => self.session.engine.update_requested = true,
+            event::KeyCode::Char('r') => self.session.engine.replay_requested = true,
+            event::KeyCode::Char('+') => match Timestamp::now() {
+                Ok(now) => self.session.push(self.session.push_increment, &now),
+                Err(report) => warn!("cannot push the rendezvous: {report:?}"),
+            },
+            event::KeyCode::Char('n') => self.show_schedule = !self.show_schedule,
+            event::KeyCode::Char('l') => self.show_logs = !self.show_logs,
+            event::KeyCode::Char('c') => self.count_up = !self.count_up,
+            event::KeyCode::Char('t') => self.prompt = Some(Prompt::new(PromptKind::Trip)),
+            event::KeyCode::Char('e') => self.prompt = Some(Prompt::new(PromptKind::Rendezvous)),
+            event::KeyCode::Char('p') => {
+                self.session.engine.paused = !self.session.engine.paused;
+                // Catching up on what was missed
+                self.session.engine.update_requested = !self.session.engine.paused;
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::engine::Engine;
+    use crate::engine::tests::{FakeClock, RecordingSpeaker};
+    use crate::feature::coach::DefaultItCoach;
+    use crate::plan::{Notification, Urgency};
+
+    /// The terminal interface of a session of `plan` started at `start`.
+    fn started_at(plan: &Plan, start: Timestamp) -> AppState<DefaultItCoach> {
+        let engine = Engine::new(plan, DefaultItCoach, 5, start).unwrap();
+        AppState::with_session(Session::with_engine(engine))
+    }
+
+    #[test]
+    fn app_state_just_created_is_a_clean_slate() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+
+        assert!(!state.session.exit);
+        assert!(!state.session.engine.muted);
+        assert!(state.session.engine.notifications.emitted.is_empty());
+    }
+
+    #[test]
+    fn app_state_m_key_toggles_mute() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+
+        state.handle_key(event::KeyCode::Char('m'));
+        assert!(state.session.engine.muted);
+
+        state.handle_key(event::KeyCode::Char('m'));
+        assert!(!state.session.engine.muted);
+    }
+
+    #[test]
+    fn app_state_frame_ratio_moves_within_the_second() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_minutes(15),
+            trip_duration: TimeSpan::of_minutes(5),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        let now = Timestamp::now().unwrap();
+        let next = now + TimeSpan::of_seconds(1);
+
+        state.frame_fraction = 0.5;
+
+        let expected = (state.gauge_ratio(&now) + state.gauge_ratio(&next)) / 2.0;
+        assert!((state.frame_ratio(&now) - expected).abs() < 1e-9);
+        assert!(state.frame_ratio(&now) < state.gauge_ratio(&now));
+    }
+
+    #[test]
+    fn app_state_q_key_asks_before_abandoning_with_a_farewell() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        state.confirm_quit = true;
+
+        state.handle_key(event::KeyCode::Char('q'));
+        state.handle_key(event::KeyCode::Char('n'));
+        assert!(!state.session.exit);
+        assert!(state.prompt.is_none());
+
+        state.handle_key(event::KeyCode::Char('q'));
+        state.handle_key(event::KeyCode::Char('y'));
+        assert!(state.session.exit);
+
+        let mut speaker = RecordingSpeaker::default();
+        state
+            .session
+            .finish(&mut speaker, &Timestamp::now().unwrap());
+        assert!(speaker.spoken[0].starts_with("Appuntamento abbandonato a 4"));
+    }
+
+    #[test]
+    fn app_state_overdue_counts_past_departure() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let now = Timestamp::new(2025, 10, 24, 17, 47, 13).unwrap();
+        let mut state = started_at(&plan, now);
+        assert_eq!(None, state.session.engine.overdue_time(&now));
+
+        state.session.engine.overdue = true;
+        let label = state.gauge_label(&now).to_string();
+        let mut speaker = RecordingSpeaker::default();
+        state
+            .session
+            .tick(&FakeClock(Cell::new(now)), &mut speaker)
+            .unwrap();
+
+        assert_eq!("Overdue ⏰ −00:02:13", label);
+        assert_eq!(1.0, state.gauge_ratio(&now));
+        assert!(!state.session.exit);
+    }
+
+    /// Answers `answer` to the prompt opened by `key`.
+    fn type_answer<C: Coach>(state: &mut AppState<C>, key: char, answer: &str) {
+        state.handle_key(event::KeyCode::Char(key));
+        for c in answer.chars() {
+            state.handle_key(event::KeyCode::Char(c));
+        }
+        state.handle_key(event::KeyCode::Enter);
+    }
+
+    #[test]
+    fn app_state_p_key_pauses_and_resumes_with_the_remaining_time() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        // Started past departure, with nothing left to announce but the departure
+        let clock = FakeClock(Cell::new(Timestamp::new(2025, 10, 24, 17, 50, 00).unwrap()));
+        let mut state = started_at(&plan, clock.0.get());
+        let mut speaker = RecordingSpeaker::default();
+
+        state.handle_key(event::KeyCode::Char('p'));
+        assert!(state.session.engine.paused);
+        state.session.tick(&clock, &mut speaker).unwrap();
+        assert!(speaker.spoken.is_empty());
+
+        clock.advance(TimeSpan::of_seconds(1));
+        state.handle_key(event::KeyCode::Char('p'));
+        assert!(!state.session.engine.paused);
+        state.session.tick(&clock, &mut speaker).unwrap();
+        assert_eq!(vec!["Ora di partire!"], speaker.spoken);
+    }
+
+    #[test]
+    fn app_state_plus_key_pushes_the_rendezvous() {
+        let now = Timestamp::now().unwrap();
+        let plan = Plan {
+            rendezvous_time: now + TimeSpan::of_minutes(30),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        let mut speaker = RecordingSpeaker::default();
+
+        state.handle_key(event::KeyCode::Char('+'));
+        state
+            .session
+            .tick(&FakeClock(Cell::new(now)), &mut speaker)
+            .unwrap();
+
+        let departure_time = now + TimeSpan::of_minutes(20);
+        assert_eq!(departure_time, state.session.engine.departure_time);
+        assert_eq!(
+            Some(departure_time),
+            state
+                .session
+                .engine
+                .notifications
+                .pending
+                .iter()
+                .last()
+                .map(|n| n.time)
+        );
+        assert_eq!(
+            format!("Nuovo orario di partenza: {}", departure_time.to_hh_mm()),
+            speaker.spoken[0]
+        );
+    }
+
+    #[test]
+    fn app_state_t_key_changes_the_trip_duration() {
+        let now = Timestamp::now().unwrap();
+        let plan = Plan {
+            rendezvous_time: now + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        type_answer(&mut state, 't', "bike");
+        assert!(state.prompt.as_ref().is_some_and(|p| p.error.is_some()));
+        state.handle_key(event::KeyCode::Esc);
+        type_answer(&mut state, 't', "00:25");
+
+        assert!(state.prompt.is_none());
+        assert_eq!(
+            TimeSpan::of_minutes(25),
+            state.session.engine.plan.trip_duration
+        );
+        assert_eq!(
+            now + TimeSpan::of_minutes(35),
+            state.session.engine.departure_time
+        );
+        assert!(state.session.engine.announcement.is_some());
+    }
+
+    #[test]
+    fn app_state_e_key_changes_the_rendezvous_time() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+        let rendezvous_time = plan.rendezvous_time + TimeSpan::of_minutes(30);
+
+        type_answer(&mut state, 'e', &rendezvous_time.to_rfc3339());
+
+        assert!(state.prompt.is_none());
+        assert_eq!(rendezvous_time, state.session.engine.plan.rendezvous_time);
+        assert_eq!(
+            rendezvous_time - TimeSpan::of_minutes(15),
+            state.session.engine.departure_time
+        );
+        assert_eq!(
+            Some(rendezvous_time - TimeSpan::of_minutes(15)),
+            state
+                .session
+                .engine
+                .notifications
+                .pending
+                .iter()
+                .last()
+                .map(|n| n.time)
+        );
+    }
+
+    #[test]
+    fn app_state_s_key_speaks_the_remaining_time_even_when_muted() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(Timestamp::new(2025, 10, 24, 17, 50, 00).unwrap()));
+        let mut state = started_at(&plan, clock.0.get());
+        let mut speaker = RecordingSpeaker::default();
+
+        state.handle_key(event::KeyCode::Char('m'));
+        state.handle_key(event::KeyCode::Char('s'));
+        state.session.tick(&clock, &mut speaker).unwrap();
+        clock.advance(TimeSpan::of_seconds(1));
+        state.session.tick(&clock, &mut speaker).unwrap();
+
+        assert_eq!(vec!["Ora di partire!"], speaker.spoken);
+    }
+
+    #[test]
+    fn app_state_r_key_repeats_the_last_announcement() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(Timestamp::new(2025, 10, 24, 17, 50, 00).unwrap()));
+        let mut state = started_at(&plan, clock.0.get());
+        let mut speaker = RecordingSpeaker::default();
+
+        state.handle_key(event::KeyCode::Char('r'));
+        state.session.tick(&clock, &mut speaker).unwrap();
+        assert!(speaker.spoken.is_empty());
+
+        state.session.engine.last_announced = Some(Notification {
+            time: Timestamp::new(2025, 10, 24, 17, 40, 00).unwrap(),
+            message: "Mancano 5 minuti".to_owned(),
+            urgency: Urgency::Medium,
+        });
+        state.handle_key(event::KeyCode::Char('r'));
+        clock.advance(TimeSpan::of_seconds(1));
+        state.session.tick(&clock, &mut speaker).unwrap();
+        clock.advance(TimeSpan::of_seconds(1));
+        state.session.tick(&clock, &mut speaker).unwrap();
+
+        assert_eq!(vec!["Mancano 5 minuti"], speaker.spoken);
+    }
+}
@@ -0,0 +1,402 @@
+//! Sessions counting down at the same time in the terminal, one shown at a time in tabs.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use error_stack::ResultExt;
+use ratatui::{
+    Frame, Terminal, TerminalOptions, Viewport,
+    backend::Backend,
+    crossterm::{event, execute},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets,
+};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::warn;
+
+use super::state::AppState;
+use crate::engine::SystemClock;
+use crate::error::{AppError, AppResult};
+use crate::feature::coach::Coach;
+use crate::feature::tts::{Announcer, QueuedSpeaker};
+use crate::time::{TimeSpan, Timestamp};
+
+/// Sessions counting down at the same time, shown one at a time in tabs.
+pub struct Tabs<C> {
+    pub apps: Vec<AppState<C>>,
+    active: usize,
+    /// Frames drawn per second
+    fps: u32,
+    /// Mouse captured, to capture again on resume
+    pub mouse: bool,
+    /// Set by Ctrl-Z or SIGTSTP, suspending the process
+    suspended: Arc<AtomicBool>,
+}
+
+impl<C: Coach> Tabs<C> {
+    pub fn new(apps: Vec<AppState<C>>, fps: u32) -> Self {
+        Self {
+            apps,
+            active: 0,
+            fps,
+            mouse: false,
+            suspended: Arc::default(),
+        }
+    }
+
+    /// Lines of the inline viewport, with the tab bar when there are several sessions.
+    fn inline_height(&self, width: u16) -> u16 {
+        let height = self.apps.iter().map(|app| app.inline_height(width)).max();
+        height.unwrap_or_default() + u16::from(self.apps.len() > 1)
+    }
+
+    /// Takes the terminal, inline or in full screen as the first session asks, and runs the
+    /// sessions on `runtime` until they are over or interrupted by a signal, giving the terminal
+    /// back even on a panic. The last messages are spoken before returning.
+    pub fn run_in_terminal(
+        &mut self,
+        runtime: &Runtime,
+        mut speaker: QueuedSpeaker,
+    ) -> AppResult<()> {
+        // Only known to wrap the messages ahead, the viewport cannot grow later
+        let width = ratatui::crossterm::terminal::size().map_or(u16::MAX, |(width, _)| width);
+        let mut terminal = if self.apps[0].fullscreen {
+            ratatui::init()
+        } else {
+            ratatui::init_with_options(TerminalOptions {
+                viewport: Viewport::Inline(self.inline_height(width)),
+            })
+        };
+        if self.mouse {
+            execute!(std::io::stdout(), event::EnableMouseCapture)
+                .change_context(AppError::Terminal)
+                .attach("cannot capture the mouse")?;
+            release_mouse_on_panic();
+        }
+        // Suspending as on Ctrl-Z, once the terminal is given back
+        #[cfg(unix)]
+        signal_hook::flag::register(signal_hook::consts::SIGTSTP, self.suspended.clone())
+            .change_context(AppError::Terminal)
+            .attach("cannot handle the signals")?;
+        let interrupted = Arc::new(AtomicBool::new(false));
+        for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+            signal_hook::flag::register(signal, interrupted.clone())
+                .change_context(AppError::Other)
+                .attach("cannot handle the signals")?;
+        }
+        for app in &mut self.apps {
+            app.session.interrupted = interrupted.clone();
+        }
+
+        // The terminal is restored by the panic hook of ratatui, before the message
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            runtime.block_on(self.run(&mut terminal, &mut speaker))
+        }));
+
+        if self.mouse
+            && let Err(e) = execute!(std::io::stdout(), event::DisableMouseCapture)
+        {
+            warn!("cannot release the mouse: {e}");
+        }
+        ratatui::restore();
+        let result = match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(report)) => {
+                self.abandon(&mut speaker);
+                Err(report)
+            }
+            Err(panic) => {
+                self.abandon(&mut speaker);
+                speaker.finish();
+                std::panic::resume_unwind(panic)
+            }
+        };
+        speaker.finish();
+        result
+    }
+
+    /// Counts the sessions down, ticking when one has something to do, and handles the events of
+    /// the terminal and the requests to the control server as they come, drawing a frame after
+    /// each and at every --fps in between.
+    async fn run<B: Backend, S: Announcer>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        speaker: &mut S,
+    ) -> AppResult<()> {
+        let mut events = terminal_events();
+        let mut frames = tokio::time::interval(Duration::from_secs(1) / self.fps);
+        frames.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let tick = tokio::time::sleep(Duration::ZERO);
+        tokio::pin!(tick);
+        let mut ticking = true;
+        loop {
+            tokio::select! {
+                () = &mut tick, if ticking => {
+                    let mut waits = Vec::with_capacity(self.apps.len());
+                    for app in &mut self.apps {
+                        let now = app.session.tick(&SystemClock, speaker)?;
+                        if app.session.engine.remaining_time(&now) == TimeSpan::ZERO
+                            && !app.session.engine.overdue
+                        {
+                            app.session.exit = true;
+                        }
+                        waits.extend(app.session.until_next_tick(&now));
+                    }
+                    // The frames keep the countdown on screen up to date in between
+                    match waits.into_iter().min() {
+                        Some(wait) => tick.as_mut().reset(Instant::now() + wait),
+                        None => ticking = false,
+                    }
+                }
+                _ = frames.tick() => {}
+                Some(event) = events.recv() => {
+                    self.handle_event(event);
+                    // Acted upon by a tick right away, e.g. speaking now
+                    tick.as_mut().reset(Instant::now());
+                    ticking = true;
+                }
+                request = self.apps[0].session.next_request() => {
+                    self.apps[0].session.answer(request)?;
+                    tick.as_mut().reset(Instant::now());
+                    ticking = true;
+                }
+            }
+            if self.suspended.swap(false, Ordering::Relaxed) {
+                self.suspend(terminal)?;
+                tick.as_mut().reset(Instant::now());
+                ticking = true;
+                continue;
+            }
+            let now = Timestamp::now().change_context(AppError::Other)?;
+            for app in &mut self.apps {
+                app.session.exit |= app.session.interrupted.load(Ordering::Relaxed);
+                if app.session.exit {
+                    app.session.finish(speaker, &now);
+                }
+            }
+            self.apps.retain(|app| !app.session.exit);
+            if self.apps.is_empty() {
+                break;
+            }
+            self.active = self.active.min(self.apps.len() - 1);
+
+            let fraction = 1.0 - Timestamp::until_next_second().as_secs_f64();
+            for app in &mut self.apps {
+                app.frame_fraction = fraction;
+            }
+            terminal
+                .draw(|frame| self.draw(frame))
+                .change_context(AppError::Terminal)
+                .attach("cannot render frame")?;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let mut area = frame.area();
+        if self.apps.len() > 1 {
+            let [bar, rest] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+            frame.render_widget(self.bar(), bar);
+            area = rest;
+        }
+        self.apps[self.active].draw(frame, area);
+    }
+
+    /// Tab bar, with the rendezvous and departure time of each session.
+    fn bar(&self) -> widgets::Tabs<'static> {
+        let titles = self.apps.iter().enumerate().map(|(i, app)| {
+            format!(
+                "{} {} ({} {})",
+                i + 1,
+                app.session.engine.plan.rendezvous_time.to_hh_mm(),
+                app.glyphs.car,
+                app.session.engine.departure_time.to_hh_mm()
+            )
+        });
+        widgets::Tabs::new(titles)
+            .select(self.active)
+            .highlight_style(
+                Style::default()
+                    .fg(self.apps[self.active].theme.title)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            )
+            .divider(" | ")
+    }
+
+    /// Gives the terminal back and stops the process, as the shell expects on Ctrl-Z, then takes
+    /// the terminal again and catches up with the time gone by once continued.
+    fn suspend<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> AppResult<()> {
+        if self.mouse
+            && let Err(e) = execute!(std::io::stdout(), event::DisableMouseCapture)
+        {
+            warn!("cannot release the mouse: {e}");
+        }
+        ratatui::restore();
+        #[cfg(unix)]
+        signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)
+            .change_context(AppError::Terminal)
+            .attach("cannot suspend")?;
+
+        ratatui::crossterm::terminal::enable_raw_mode()
+            .change_context(AppError::Terminal)
+            .attach("cannot resume")?;
+        if self.apps[self.active].fullscreen {
+            execute!(
+                std::io::stdout(),
+                ratatui::crossterm::terminal::EnterAlternateScreen
+            )
+            .change_context(AppError::Terminal)
+            .attach("cannot resume")?;
+        }
+        if self.mouse {
+            execute!(std::io::stdout(), event::EnableMouseCapture)
+                .change_context(AppError::Terminal)
+                .attach("cannot capture the mouse")?;
+        }
+        terminal
+            .clear()
+            .change_context(AppError::Terminal)
+            .attach("cannot resume")?;
+        let now = Timestamp::now().change_context(AppError::Other)?;
+        for app in &mut self.apps {
+            app.session.resync(&now);
+        }
+        Ok(())
+    }
+
+    /// Ends the sessions still running, e.g. after an error.
+    fn abandon<S: Announcer>(&mut self, speaker: &mut S) {
+        let Ok(now) = Timestamp::now() else {
+            return;
+        };
+        for app in &mut self.apps {
+            // Kept, to resume the session
+            app.session.state_file = None;
+            app.session.finish(speaker, &now);
+        }
+    }
+
+    /// Switches sessions with Tab and Shift+Tab, abandons them all with Ctrl-C and suspends
+    /// with Ctrl-Z (not signals in raw mode), passing the other events to the one shown.
+    fn handle_event(&mut self, event: event::Event) {
+        let app = &mut self.apps[self.active];
+        match event {
+            event::Event::Key(key_event)
+                if key_event.kind == event::KeyEventKind::Press
+                    && key_event.code == event::KeyCode::Char('z')
+                    && key_event.modifiers.contains(event::KeyModifiers::CONTROL) =>
+            {
+                self.suspended.store(true, Ordering::Relaxed);
+            }
+            event::Event::Key(key_event)
+                if key_event.kind == event::KeyEventKind::Press
+                    && key_event.code == event::KeyCode::Char('c')
+                    && key_event.modifiers.contains(event::KeyModifiers::CONTROL) =>
+            {
+                for app in &mut self.apps {
+                    app.session.exit = true;
+                }
+            }
+            event::Event::Key(key_event)
+                if key_event.kind == event::KeyEventKind::Press && app.prompt.is_none() =>
+            {
+                match key_event.code {
+                    event::KeyCode::Tab => self.active = (self.active + 1) % self.apps.len(),
+                    event::KeyCode::BackTab => {
+                        self.active = (self.active + self.apps.len() - 1) % self.apps.len();
+                    }
+                    _ => app.handle_event(event),
+                }
+            }
+            _ => app.handle_event(event),
+        }
+    }
+}
+
+/// Events of the terminal, read from a thread as reading blocks.
+fn terminal_events() -> UnboundedReceiver<event::Event> {
+    let (sender, events) = unbounded_channel();
+    std::thread::spawn(move || {
+        loop {
+            match event::read() {
+                // Until the sessions are over
+                Ok(event) if sender.send(event).is_err() => break,
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("cannot read event: {e}");
+                    break;
+                }
+            }
+        }
+    });
+    events
+}
+
+/// Releases the mouse before the panic message, along with the rest of the terminal.
+fn release_mouse_on_panic() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(std::io::stdout(), event::DisableMouseCapture);
+        hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feature::coach::DefaultItCoach;
+    use crate::plan::Plan;
+
+    #[test]
+    fn tabs_switch_between_the_sessions() {
+        let in_hours = |hours| Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(hours),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let apps = vec![
+            AppState::new(&in_hours(1), DefaultItCoach, 5).unwrap(),
+            AppState::new(&in_hours(2), DefaultItCoach, 5).unwrap(),
+        ];
+        let mut tabs = Tabs::new(apps, 1);
+        let press = |code| event::Event::Key(event::KeyEvent::from(code));
+
+        tabs.handle_event(press(event::KeyCode::Tab));
+        tabs.handle_event(press(event::KeyCode::Char('m')));
+
+        assert_eq!(1, tabs.active);
+        assert!(!tabs.apps[0].session.engine.muted);
+        assert!(tabs.apps[1].session.engine.muted);
+        tabs.handle_event(press(event::KeyCode::BackTab));
+        assert_eq!(0, tabs.active);
+        assert_eq!(1, tabs.inline_height(200) - tabs.apps[0].inline_height(200));
+    }
+
+    #[test]
+    fn tabs_ctrl_c_abandons_all_the_sessions() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let apps = vec![
+            AppState::new(&plan, DefaultItCoach, 5).unwrap(),
+            AppState::new(&plan, DefaultItCoach, 5).unwrap(),
+        ];
+        let mut tabs = Tabs::new(apps, 1);
+        tabs.apps[0].confirm_quit = true;
+
+        tabs.handle_event(event::Event::Key(event::KeyEvent::new(
+            event::KeyCode::Char('c'),
+            event::KeyModifiers::CONTROL,
+        )));
+
+        assert!(tabs.apps.iter().all(|app| app.session.exit));
+        assert!(!tabs.apps[0].count_up);
+        assert!(tabs.apps[0].prompt.is_none());
+    }
+}
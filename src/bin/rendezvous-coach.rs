@@ -1,300 +1,1630 @@
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::process::ExitCode;
 use std::time::Duration;
 
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use error_stack::ResultExt;
-use ratatui::{
-    Frame, Terminal, TerminalOptions, Viewport,
-    backend::Backend,
-    buffer::Buffer,
-    crossterm::event,
-    layout::{Constraint, Layout, Rect},
-    style::{Color, Modifier, Style, Stylize},
-    symbols,
-    text::{Line, Span},
-    widgets::{Block, LineGauge, List, ListItem, Widget},
+#[cfg(feature = "tui")]
+use ratatui::style::Color;
+#[cfg(feature = "tui")]
+use rendezvous_coach::app::{AppState, GaugeDirection, GaugeStyle, Tabs};
+use rendezvous_coach::error::{self, AppError, AppResult, Suggestion};
+use rendezvous_coach::feature::coach::{Coach, DefaultItCoach, PluginCoach};
+use rendezvous_coach::feature::config::{Config, Layers, option_value};
+#[cfg(feature = "control")]
+use rendezvous_coach::feature::control::{ControlServer, default_socket_path, request};
+use rendezvous_coach::feature::daemon::Daemon;
+#[cfg(feature = "history")]
+use rendezvous_coach::feature::history::{History, Statistics};
+use rendezvous_coach::feature::logs::RecentLogs;
+use rendezvous_coach::feature::preview::{PreviewFormat, preview, schedule_table};
+use rendezvous_coach::feature::profile::Profiles;
+#[cfg(feature = "sinks-net")]
+use rendezvous_coach::feature::sink::{DeadLetters, NetworkSinkOptions, SmtpServer};
+use rendezvous_coach::feature::sink::{SessionLog, SinkFilter, SinkOptions, StatusFormat};
+use rendezvous_coach::feature::state::{SessionState, StateFile};
+use rendezvous_coach::feature::systemd::{Schedule, Units, user_unit_dir};
+use rendezvous_coach::feature::tts::{
+    BellSpeaker, Ducking, DuckingSpeaker, PacedSpeaker, PreprocessingSpeaker, Preprocessor,
+    PrintSpeaker, Pronunciation, QueuedSpeaker, RampedSpeaker, Recorder, ResilientSpeaker,
+    SapiSpeaker, SaySpeaker, SilentSpeaker, Speaker, SpeakerError, SpeechOptions, SpeechProfile,
+    UrgentSpeaker, VolumeRamp, select_voice,
 };
-use rendezvous_coach::error::{AppError, AppResult};
-use rendezvous_coach::feature::coach::{Coach, DefaultItCoach};
-use rendezvous_coach::feature::tts::{Speaker, TTSSpeaker};
-use rendezvous_coach::init;
-use rendezvous_coach::plan::{Notification, Plan};
+#[cfg(feature = "tts")]
+use rendezvous_coach::feature::tts::{
+    Chime, ChimeSpeaker, CloudSpeaker, ComposedSpeaker, Composition, SoundCue, SoundCueSpeaker,
+    TTSSpeaker, prepare_all,
+};
+#[cfg(feature = "tui")]
+use rendezvous_coach::feature::tui::{Glyphs, Theme};
+use rendezvous_coach::init::{self, LogOptions};
+use rendezvous_coach::plan::Plan;
+#[cfg(feature = "control")]
+use rendezvous_coach::plan::Urgency;
+use rendezvous_coach::session::{self, Session};
 use rendezvous_coach::time::*;
+#[cfg(feature = "control")]
+use tracing::debug;
+use tracing::level_filters::LevelFilter;
+use tracing::{info, warn};
 
-#[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[derive(Parser, Clone)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Cli {
-    /// Rendezvous time
-    #[arg(short, long, value_name = "HH:MM")]
-    rendezvous: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Keys bound to the actions of the terminal interface in the configuration file, to their
+    /// own keys (still checked without it)
+    #[arg(skip)]
+    #[cfg_attr(not(feature = "tui"), allow(dead_code))]
+    keys: HashMap<char, char>,
+    /// Rendezvous time (today, or on another day as "YYYY-MM-DD HH:MM")
+    #[arg(short, long, value_name = "HH:MM", required = true)]
+    rendezvous: Option<String>,
     /// Trip duration
-    #[arg(short, long, value_name = "HH:MM")]
-    trip: String,
-    /// TTS model directory (default: auto-downloaded to ~/.local/share/rendezvous-coach/models/)
+    #[arg(short, long, value_name = "HH:MM", required = true)]
+    trip: Option<String>,
+    /// Another rendezvous to count down to at the same time, in a tab of its own (repeatable)
+    #[cfg(feature = "tui")]
+    #[arg(long, value_name = "HH:MM,HH:MM", value_parser = parse_plan)]
+    also: Vec<Plan>,
+    /// What is shown while counting down (default: the terminal interface, when built in)
+    #[arg(long, value_enum)]
+    output: Option<OutputFormat>,
+    /// Nothing shown, only speaking and delivering to the sinks, e.g. under cron or systemd
+    /// without a terminal (always, without the terminal interface)
+    #[cfg_attr(
+        feature = "tui",
+        arg(long, conflicts_with_all = ["output", "fullscreen", "minimal", "mouse"])
+    )]
+    #[cfg_attr(not(feature = "tui"), arg(long, conflicts_with = "output"))]
+    #[cfg_attr(not(feature = "tui"), allow(dead_code))]
+    headless: bool,
+    /// Go on in the background like --headless, answering on the control socket, and return
+    /// right away; follow the session with the attach command
+    #[cfg(feature = "control")]
+    #[cfg_attr(
+        feature = "tui",
+        arg(
+            long,
+            conflicts_with_all = ["headless", "output", "fullscreen", "minimal", "mouse"]
+        )
+    )]
+    #[cfg_attr(
+        not(feature = "tui"),
+        arg(long, conflicts_with_all = ["headless", "output"])
+    )]
+    detach: bool,
+    /// How much later the + key (or the push command) moves the rendezvous
+    #[arg(long, value_name = "HH:MM", default_value = "00:05", value_parser = parse_time_span)]
+    push_increment: TimeSpan,
+    /// Keep running past departure, counting how late it is, instead of exiting
+    #[arg(long)]
+    overdue: bool,
+    /// Ask for a confirmation when q is pressed, before abandoning the session
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    confirm_quit: bool,
+    /// Plain ASCII symbols instead of the emoji (e.g. -> for ➡), for the fonts and terminals
+    /// showing them as double-width boxes
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    ascii: bool,
+    /// Show just a line with the remaining time and the last message, e.g. in a tiny tmux pane
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with = "fullscreen")]
+    minimal: bool,
+    /// Take the whole terminal, with the countdown in large, the schedule and the history
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    fullscreen: bool,
+    /// Colors of the terminal interface: default, ocean, light or mono
+    #[cfg(feature = "tui")]
+    #[arg(long, value_name = "NAME", default_value = "default", value_parser = parse_theme)]
+    theme: Theme,
+    /// Color of an element of the theme: title, time, warning, error, overdue, the messages of an
+    /// urgency (low, medium, high, critical), or the gauge or countdown for all the urgencies or
+    /// one (e.g. gauge.high), as a name, #RRGGBB or 0-255 (e.g. gauge.medium=yellow; repeatable)
+    #[cfg(feature = "tui")]
+    #[arg(long = "theme-color", value_name = "ELEMENT=COLOR", value_parser = parse_theme_color)]
+    theme_colors: Vec<(String, Color)>,
+    /// No colors in the terminal interface (also with the NO_COLOR environment variable)
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    no_color: bool,
+    /// Tell the urgency of the messages and of the remaining time by symbols and text too, not
+    /// by color alone
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    high_contrast: bool,
+    /// Make the remaining time blink in the last minute
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    blink: bool,
+    /// In the last minute, flash the interface in inverted colors, with a blinking border and
+    /// the remaining time in large, to make the departure unmistakable even muted
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    visual_alarm: bool,
+    /// Click the key hints of the title line and scroll the history with the mouse (keeping
+    /// the terminal from selecting text)
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    mouse: bool,
+    /// How the progress towards departure is shown
+    #[cfg(feature = "tui")]
+    #[arg(long, value_enum, default_value_t = GaugeStyle::Thick)]
+    gauge_style: GaugeStyle,
+    /// Whether the gauge empties or fills up as departure approaches (the c key switches it
+    /// while running)
+    #[cfg(feature = "tui")]
+    #[arg(long, value_enum, default_value_t = GaugeDirection::Drain)]
+    gauge_direction: GaugeDirection,
+    /// Frames drawn per second, above 1 for the gauge to move smoothly (the messages still go
+    /// by the second)
+    #[cfg(feature = "tui")]
+    #[arg(
+        long,
+        value_name = "FPS",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=30)
+    )]
+    fps: u32,
+    /// Show the remaining time in large digits, readable from across the room (always with
+    /// --fullscreen)
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    big_countdown: bool,
+    /// Status line of --output status or waybar, with {remaining}, {urgency} and {message}
+    #[arg(long, value_name = "TEMPLATE", default_value = "{remaining}")]
+    status_format: String,
+    /// Seconds between the lines of --output status or waybar
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    status_interval: u64,
+    /// Take the messages from the coach plugin started with COMMAND (e.g. "python3 coach.py"),
+    /// see the README for the protocol
+    #[arg(long, value_name = "COMMAND", global = true)]
+    coach_plugin: Option<String>,
+    /// Speech backend
+    #[arg(long, value_enum, global = true, default_value_t = SpeakerBackend::DEFAULT)]
+    speaker: SpeakerBackend,
+    /// Voice name (default: the first voice speaking --lang, see `voices`)
+    #[arg(long, value_name = "NAME")]
+    voice: Option<String>,
+    /// Language of the voice, as an ISO 639-1 code, e.g. en (default: the coach language)
+    #[arg(long, value_name = "LANG", value_parser = parse_language)]
+    lang: Option<String>,
+    /// Speech rate, relative to the normal one (e.g. 0.8 is 20% slower)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_factor)]
+    speech_rate: f32,
+    /// Voice pitch, relative to the normal one (e.g. 1.2 is 20% higher)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_factor)]
+    speech_pitch: f32,
+    /// Pronounce WORD as SPOKEN (repeatable, e.g. Dott=Dottore)
+    #[arg(long = "pronounce", value_name = "WORD=SPOKEN")]
+    pronunciations: Vec<Pronunciation>,
+    /// Vary the speech rate by message: faster routine updates, slower final call
+    #[arg(long)]
+    pace: bool,
+    /// Voice for the notifications in the last 5 minutes (default: --voice)
+    #[arg(long, value_name = "NAME")]
+    urgent_voice: Option<String>,
+    /// Speech rate for the notifications in the last 5 minutes (default: --speech-rate)
+    #[arg(long, value_name = "FACTOR", value_parser = parse_factor)]
+    urgent_speech_rate: Option<f32>,
+    /// Voice pitch for the notifications in the last 5 minutes (default: --speech-pitch)
+    #[arg(long, value_name = "FACTOR", value_parser = parse_factor)]
+    urgent_speech_pitch: Option<f32>,
+    /// Speech volume, from 0.0 (silent) to 1.0 (full volume)
+    #[arg(long, value_name = "LEVEL", default_value_t = 1.0, value_parser = parse_volume)]
+    volume: f32,
+    /// Volumes for low, medium, high and critical urgency notifications, relative to --volume
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH,CRITICAL")]
+    volume_ramp: Option<VolumeRamp>,
+    /// Play a chime before each message
+    #[cfg(feature = "tts")]
+    #[arg(long, conflicts_with = "compose")]
+    chime: bool,
+    /// Play this audio file as chime, instead of the built-in one (implies --chime)
+    #[cfg(feature = "tts")]
+    #[arg(long, value_name = "FILE")]
+    chime_file: Option<std::path::PathBuf>,
+    /// Outputs for low, medium, high and critical urgency notifications (e.g. speech,speech,chime+speech,chime+speech+bell)
+    #[cfg(feature = "tts")]
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH,CRITICAL")]
+    compose: Option<Composition>,
+    /// Play FILE when the remaining time drops to HH:MM (repeatable, e.g. 00:30=gentle.ogg)
+    #[cfg(feature = "tts")]
+    #[arg(long = "sound", value_name = "HH:MM=FILE")]
+    sounds: Vec<SoundCue>,
+    /// Play the --sound files instead of speaking the messages they go with
+    #[cfg(feature = "tts")]
+    #[arg(long, requires = "sounds")]
+    sounds_only: bool,
+    /// While speaking, pause the playing media players or lower their volume (Linux, via playerctl)
+    #[arg(long, value_name = "pause|LEVEL")]
+    duck: Option<Ducking>,
+    /// Show the remaining time in the terminal title
+    #[arg(long)]
+    terminal_title: bool,
+    /// Deliver to SINK (json, status, title, desktop, kdeconnect, webhook, mqtt, dbus, email,
+    /// websocket, plugin for all the --sink-plugin or plugin-N for the Nth) only the messages from
+    /// an urgency and/or within a time of departure (e.g. desktop=all, kdeconnect=00:15 or
+    /// webhook=medium,00:30; repeatable)
+    #[arg(long = "sink-filter", value_name = "SINK=FILTER", value_parser = parse_sink_filter)]
+    sink_filters: Vec<(String, SinkFilter)>,
+    /// Times a message is sent again when its delivery over the network (--webhook, --mqtt,
+    /// --email-to) fails, waiting twice as long each time from 1 second
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    retries: u32,
+    /// File where the messages that could not be delivered over the network are recorded
+    /// (default: dead-letters.jsonl in the data directory)
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "FILE")]
+    dead_letters: Option<std::path::PathBuf>,
+    /// Also show each message as a desktop notification
+    #[cfg(feature = "desktop")]
+    #[arg(long)]
+    desktop_notifications: bool,
+    /// Also ping the phone paired with KDE Connect as NAME for the messages of the last 5 minutes
+    #[arg(long, value_name = "NAME")]
+    kdeconnect: Option<String>,
+    /// Also POST each message as JSON to this URL
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+    /// Authorization header of the --webhook requests (e.g. "Bearer TOKEN")
+    #[cfg(feature = "sinks-net")]
+    #[arg(
+        long,
+        value_name = "VALUE",
+        env = "RENDEZVOUS_WEBHOOK_AUTH",
+        hide_env_values = true
+    )]
+    webhook_auth: Option<String>,
+    /// Also publish each message, and the remaining time, to the MQTT broker at HOST
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "HOST")]
+    mqtt: Option<String>,
+    /// Port of the --mqtt broker
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "PORT", default_value_t = 1883)]
+    mqtt_port: u16,
+    /// Topic the --mqtt messages are published under
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "TOPIC", default_value = "rendezvous-coach")]
+    mqtt_topic: String,
+    /// User name for the --mqtt broker
+    #[cfg(feature = "sinks-net")]
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "mqtt",
+        requires = "mqtt_password"
+    )]
+    mqtt_username: Option<String>,
+    /// Password for the --mqtt broker
+    #[cfg(feature = "sinks-net")]
+    #[arg(
+        long,
+        value_name = "PASSWORD",
+        env = "RENDEZVOUS_MQTT_PASSWORD",
+        hide_env_values = true
+    )]
+    mqtt_password: Option<String>,
+    /// Register the --mqtt topics as Home Assistant sensors, through MQTT discovery under PREFIX
+    #[cfg(feature = "sinks-net")]
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        num_args = 0..=1,
+        default_missing_value = "homeassistant",
+        requires = "mqtt"
+    )]
+    mqtt_discovery: Option<String>,
+    /// Also emit a D-Bus signal for each message, and expose the countdown on the session bus
+    #[cfg(feature = "dbus")]
+    #[arg(long)]
+    dbus: bool,
+    /// Accept commands (status, snooze 5m, mute, unmute, speak-now, quit) on a Unix socket
+    /// (default: rendezvous-coach.sock in the runtime directory)
+    #[cfg(feature = "control")]
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<Option<std::path::PathBuf>>,
+    /// Serve the status (GET /status) and accept the same commands (e.g. POST /snooze/5m) over
+    /// HTTP, at ADDRESS (e.g. 127.0.0.1:8080)
+    #[cfg(feature = "control")]
+    #[arg(long, value_name = "ADDRESS")]
+    http: Option<String>,
+    /// Serve a page showing the live countdown, and broadcast it over WebSocket (/ws), at ADDRESS
+    /// (e.g. 0.0.0.0:8081)
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "ADDRESS")]
+    websocket: Option<String>,
+    /// Also deliver each message to the sink plugin started with COMMAND (e.g. "./notify.sh"),
+    /// see the README for the protocol (repeatable)
+    #[arg(long = "sink-plugin", value_name = "COMMAND")]
+    sink_plugins: Vec<String>,
+    /// Also email a reminder to ADDRESS when the countdown reaches the --email-at times
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "ADDRESS", requires = "smtp_host")]
+    email_to: Option<String>,
+    /// Sender of the reminder emails (default: --email-to)
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "ADDRESS")]
+    email_from: Option<String>,
+    /// Remaining times to email a reminder at (repeatable)
+    #[cfg(feature = "sinks-net")]
+    #[arg(
+        long = "email-at",
+        value_name = "HH:MM",
+        default_values = ["01:00", "00:15"],
+        value_parser = parse_time_span
+    )]
+    email_at: Vec<TimeSpan>,
+    /// SMTP server the reminder emails are sent through, with STARTTLS
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "HOST")]
+    smtp_host: Option<String>,
+    /// Port of the SMTP server
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "PORT", default_value_t = 587)]
+    smtp_port: u16,
+    /// User name for the SMTP server
+    #[cfg(feature = "sinks-net")]
+    #[arg(long, value_name = "NAME", requires = "smtp_password")]
+    smtp_username: Option<String>,
+    /// Password for the SMTP server
+    #[cfg(feature = "sinks-net")]
+    #[arg(
+        long,
+        value_name = "PASSWORD",
+        env = "RENDEZVOUS_SMTP_PASSWORD",
+        hide_env_values = true
+    )]
+    smtp_password: Option<String>,
+    /// Print what would be spoken to stderr, with timestamps, instead of speaking
+    #[arg(long)]
+    tts_dry_run: bool,
+    /// Print the departure time and the table of the notifications planned, then exit
+    #[cfg_attr(feature = "control", arg(long, conflicts_with = "detach"))]
+    #[cfg_attr(not(feature = "control"), arg(long))]
+    dry_run: bool,
+    /// Append each message to FILE, with the time, remaining time and outcome on each channel
+    #[arg(long, value_name = "FILE")]
+    session_log: Option<std::path::PathBuf>,
+    /// Do not store the session in the history (history.sqlite3 in the data directory)
+    #[cfg(feature = "history")]
+    #[arg(long)]
+    no_history: bool,
+    /// Save the state of the session to FILE at every tick, for the resume command (default:
+    /// session.json in the data directory)
+    #[arg(long, value_name = "FILE")]
+    state_file: Option<std::path::PathBuf>,
+    /// Also save each spoken message as a WAV file, in a new subdirectory of DIR
     #[arg(long, value_name = "DIR")]
+    record: Option<std::path::PathBuf>,
+    /// Restart the speech backend when a message takes longer than this to be spoken
+    #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+    speech_timeout: u64,
+    /// Synthesize all the planned messages at startup, so that they are spoken without delay
+    #[cfg(feature = "tts")]
+    #[arg(long)]
+    presynthesize: bool,
+    /// TTS model directory (default: auto-downloaded to ~/.local/share/rendezvous-coach/models/)
+    #[cfg(feature = "tts")]
+    #[arg(long, value_name = "DIR", global = true)]
     model_path: Option<std::path::PathBuf>,
+    /// Least level of the events logged, e.g. info or debug (default: as told by RUST_LOG)
+    #[arg(long, value_name = "LEVEL", global = true)]
+    log_level: Option<LevelFilter>,
+    /// Write the log to FILE rather than to stderr, a new file each day with the date appended
+    #[arg(long, value_name = "FILE", global = true)]
+    log_file: Option<std::path::PathBuf>,
+    /// Log one JSON object per event and line, e.g. for a log collector
+    #[arg(long, global = true)]
+    log_json: bool,
+    /// API key of the cloud TTS backend
+    #[cfg(feature = "tts")]
+    #[arg(
+        long,
+        value_name = "KEY",
+        env = "GOOGLE_TTS_API_KEY",
+        hide_env_values = true,
+        global = true
+    )]
+    cloud_api_key: Option<String>,
 }
 
-#[derive(Debug)]
-struct Notifications {
-    pending: Vec<Notification>,
-    emitted: VecDeque<Notification>,
-    max_emitted: usize,
+fn parse_factor(input: &str) -> Result<f32, String> {
+    match input.parse::<f32>() {
+        Ok(factor) if factor > 0.0 => Ok(factor),
+        _ => Err("must be a positive number".to_owned()),
+    }
 }
 
-impl Notifications {
-    fn new(pending: Vec<Notification>, max_emitted: usize) -> Self {
-        Self {
-            pending,
-            emitted: VecDeque::with_capacity(max_emitted),
-            max_emitted,
-        }
-    }
+fn parse_time_span(input: &str) -> Result<TimeSpan, String> {
+    TimeSpan::parse(input).map_err(|_| "expected HH:MM[:SS]".to_owned())
+}
 
-    fn emit(&mut self, n: Notification) {
-        self.emitted.push_front(n);
-        self.emitted.truncate(self.max_emitted);
-    }
+#[cfg(feature = "tui")]
+fn parse_plan(input: &str) -> Result<Plan, String> {
+    let (rendezvous, trip) = input
+        .split_once(',')
+        .ok_or_else(|| "expected RENDEZVOUS,TRIP".to_owned())?;
+    Ok(Plan {
+        rendezvous_time: parse_rendezvous(rendezvous)?,
+        trip_duration: parse_time_span(trip)?,
+    })
 }
 
-#[derive(Debug)]
-struct AppState {
-    departure_time: Timestamp,
-    started: Timestamp,
-    notifications: Notifications,
-    exit: bool,
+/// A rendezvous today as `HH:MM`, or on another day as `YYYY-MM-DD HH:MM`.
+fn parse_rendezvous(input: &str) -> Result<Timestamp, String> {
+    Timestamp::parse(input)
+        .or_else(|_| Timestamp::parse_today_time(input))
+        .map_err(|_| format!("invalid rendezvous time `{input}`"))
 }
 
-impl AppState {
-    fn new<C: Coach>(plan: &Plan, coach: C, max_messages: usize) -> AppResult<Self> {
-        let now = Timestamp::now().change_context(AppError)?;
-        let pending = plan.notifications(&now, &coach).change_context(AppError)?;
-        let notifications = Notifications::new(pending, max_messages);
-        Ok(Self {
-            departure_time: plan.departure_time(),
-            started: Timestamp::now().change_context(AppError)?,
-            notifications,
-            exit: false,
-        })
+#[cfg(feature = "tui")]
+fn parse_theme(input: &str) -> Result<Theme, String> {
+    Theme::named(input).ok_or_else(|| {
+        format!(
+            "unknown theme `{input}`, must be one of {}",
+            Theme::NAMES.join(", ")
+        )
+    })
+}
+
+#[cfg(feature = "tui")]
+fn parse_theme_color(input: &str) -> Result<(String, Color), String> {
+    let (element, color) = input
+        .split_once('=')
+        .ok_or_else(|| "expected ELEMENT=COLOR".to_owned())?;
+    let color = color
+        .parse()
+        .map_err(|_| format!("invalid color `{color}`"))?;
+    Theme::default().set(element, color)?;
+    Ok((element.to_owned(), color))
+}
+
+#[cfg(feature = "history")]
+fn parse_since(input: &str) -> Result<Timestamp, String> {
+    Timestamp::parse(input)
+        .or_else(|_| Timestamp::parse(&format!("{input} 00:00")))
+        .map_err(|_| "expected YYYY-MM-DD [HH:MM]".to_owned())
+}
+
+/// Names of the sinks, as given to --sink-filter, `plugin` for all the --sink-plugin (each also
+/// as `plugin-N`, in order)
+const SINK_NAMES: [&str; 11] = [
+    "json",
+    "status",
+    "title",
+    "desktop",
+    "kdeconnect",
+    "webhook",
+    "mqtt",
+    "dbus",
+    "email",
+    "websocket",
+    "plugin",
+];
+
+fn parse_sink_filter(input: &str) -> Result<(String, SinkFilter), String> {
+    let (name, filter) = input
+        .split_once('=')
+        .ok_or_else(|| "expected SINK=FILTER".to_owned())?;
+    let plugin = name
+        .strip_prefix("plugin-")
+        .is_some_and(|n| n.parse::<usize>().is_ok_and(|n| n > 0));
+    if !SINK_NAMES.contains(&name) && !plugin {
+        return Err(format!(
+            "unknown sink `{name}`, must be one of {} or plugin-N",
+            SINK_NAMES.join(", ")
+        ));
     }
+    Ok((name.to_owned(), filter.parse()?))
+}
 
-    fn total_time(&self) -> TimeSpan {
-        self.departure_time.time_span_from(&self.started)
+fn parse_language(input: &str) -> Result<String, String> {
+    match input.len() {
+        2 | 3 if input.chars().all(|c| c.is_ascii_alphabetic()) => Ok(input.to_ascii_lowercase()),
+        _ => Err("expected an ISO 639-1 code, e.g. en".to_owned()),
     }
+}
+
+fn parse_volume(input: &str) -> Result<f32, String> {
+    match input.parse::<f32>() {
+        Ok(volume) if (0.0..=1.0).contains(&volume) => Ok(volume),
+        _ => Err("must be a number between 0.0 and 1.0".to_owned()),
+    }
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// List the voices available for the selected speech backend
+    Voices,
+    /// Print the countdown of the running session (see --control-socket) for the tmux status line
+    #[cfg(feature = "control")]
+    TmuxStatus {
+        /// Control socket of the session (default: rendezvous-coach.sock in the runtime directory)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<std::path::PathBuf>,
+    },
+    /// Follow a running session (e.g. started with --detach) in the terminal, through its control
+    /// socket; closing the terminal leaves the session running
+    #[cfg(all(feature = "control", feature = "tui"))]
+    Attach {
+        /// Control socket of the session (default: rendezvous-coach.sock in the runtime directory)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<std::path::PathBuf>,
+    },
+    /// Generate a systemd user service, and the timer starting it ahead of a recurring rendezvous
+    Systemd {
+        /// Name of the units (rendezvous-coach-NAME.service and .timer)
+        #[arg(long, value_name = "NAME")]
+        profile: String,
+        /// Rendezvous time
+        #[arg(short, long, value_name = "HH:MM", value_parser = parse_time_span)]
+        rendezvous: TimeSpan,
+        /// Trip duration
+        #[arg(short, long, value_name = "HH:MM", value_parser = parse_time_span)]
+        trip: TimeSpan,
+        /// Days of the rendezvous, in systemd calendar syntax (e.g. Mon..Fri, or Sat,Sun; default:
+        /// every day)
+        #[arg(long, value_name = "DAYS")]
+        days: Option<String>,
+        /// How long before departure the coach starts
+        #[arg(long, value_name = "HH:MM", default_value = "01:00", value_parser = parse_time_span)]
+        lead: TimeSpan,
+        /// Write the units to the systemd user directory, instead of printing them
+        #[arg(long)]
+        install: bool,
+        /// Other options of the coach, after -- (e.g. -- --voice NAME --webhook URL)
+        #[arg(last = true, value_name = "OPTIONS")]
+        options: Vec<String>,
+    },
+    /// Start a session without the terminal interface for each rendezvous in a schedule
+    Daemon {
+        /// Schedule file, or directory of schedule files, with one "YYYY-MM-DD HH:MM TRIP [LABEL]"
+        /// rendezvous per line; read again at every change
+        #[arg(long, value_name = "PATH")]
+        schedule: std::path::PathBuf,
+        /// How long before departure each session starts
+        #[arg(long, value_name = "HH:MM", default_value = "01:00", value_parser = parse_time_span)]
+        lead: TimeSpan,
+        /// Other options of the sessions, after -- (e.g. -- --webhook URL --speaker bell)
+        #[arg(last = true, value_name = "OPTIONS")]
+        options: Vec<String>,
+    },
+    /// Summarize the stored sessions: how often departure was reached, snoozes and lead times
+    #[cfg(feature = "history")]
+    Stats {
+        /// Only the sessions started from DATE (YYYY-MM-DD, or YYYY-MM-DD HH:MM)
+        #[arg(long, value_name = "DATE", value_parser = parse_since)]
+        since: Option<Timestamp>,
+    },
+    /// Print the completion script for a shell (e.g. to
+    /// ~/.local/share/bash-completion/completions/rendezvous-coach)
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print the man page, in roff (e.g. to ~/.local/share/man/man1/rendezvous-coach.1)
+    Man,
+    /// Print the notifications a session would emit from now, for other tools (e.g. scripts or
+    /// calendar importers)
+    Preview {
+        /// Rendezvous time (today, or on another day as "YYYY-MM-DD HH:MM")
+        #[arg(short, long, value_name = "HH:MM", value_parser = parse_rendezvous)]
+        rendezvous: Timestamp,
+        /// Trip duration
+        #[arg(short, long, value_name = "HH:MM", value_parser = parse_time_span)]
+        trip: TimeSpan,
+        #[arg(long, value_enum, default_value_t = PreviewFormat::Json)]
+        format: PreviewFormat,
+    },
+    /// Resume the last session interrupted (e.g. by a crash or a reboot) where it left off, with
+    /// other options after it
+    Resume {
+        /// Other options of the session (e.g. --speaker bell)
+        #[arg(
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "OPTIONS"
+        )]
+        options: Vec<String>,
+    },
+    /// Start the session of a profile, e.g. `run school`, with other options after it
+    Run {
+        /// Name of the profile
+        name: String,
+        /// Other options, in place of those of the profile (e.g. --trip 00:25)
+        #[arg(
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "OPTIONS"
+        )]
+        options: Vec<String>,
+    },
+    /// Work with the profiles, named plans started with the run command
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Work with the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Work with the stored sessions
+    #[cfg(feature = "history")]
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum ProfileCommand {
+    /// Store options as a profile, replacing the one of the same name (e.g. save school -r 08:00
+    /// -t 00:20 --speaker bell --webhook URL)
+    Save {
+        /// Name of the profile
+        name: String,
+        /// Options of the profile, as on the command line
+        #[arg(
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "OPTIONS"
+        )]
+        options: Vec<String>,
+    },
+    /// Print the names of the profiles
+    List,
+    /// Print the options of a profile, as a configuration file
+    Show {
+        /// Name of the profile
+        name: String,
+    },
+    /// Remove a profile
+    Delete {
+        /// Name of the profile
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum ConfigCommand {
+    /// Print where the configuration file is read from
+    Path,
+    /// Print the options in effect, as a configuration file, with where each comes from
+    Dump,
+}
+
+#[cfg(feature = "history")]
+#[derive(Subcommand, Clone)]
+enum HistoryCommand {
+    /// Print the stored sessions and their notifications, for analysis in other tools
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Only the sessions started from DATE (YYYY-MM-DD, or YYYY-MM-DD HH:MM)
+        #[arg(long, value_name = "DATE", value_parser = parse_since)]
+        since: Option<Timestamp>,
+    },
+}
+
+#[cfg(feature = "history")]
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// One row per notification, with the columns of its session
+    Csv,
+    /// An array of sessions, each with its notifications
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Interactive terminal interface
+    #[cfg(feature = "tui")]
+    Tui,
+    /// One line of JSON per event on the standard output, instead of the terminal interface
+    Json,
+    /// A status line on the standard output at every --status-interval, for polybar or i3blocks
+    Status,
+    /// The status line as JSON for a waybar custom module, with the urgency as class
+    Waybar,
+}
 
-    // fn elapsed_time(&self, now: &Timestamp) -> TimeSpan {
-    //     now.time_span_from(&self.started)
-    // }
+#[derive(Clone, Copy, ValueEnum)]
+enum SpeakerBackend {
+    /// Local neural TTS (sherpa-onnx)
+    #[cfg(feature = "tts")]
+    Sherpa,
+    /// macOS `say` command
+    Say,
+    /// Speech synthesizer of Windows (SAPI), through PowerShell
+    Sapi,
+    /// Google Cloud Text-to-Speech, with local caching
+    #[cfg(feature = "tts")]
+    Cloud,
+    /// Terminal bell only, rung more times as departure approaches (e.g. over SSH)
+    Bell,
+}
+
+impl SpeakerBackend {
+    /// The neural voice when built in, or else the speech synthesizer of the system, or the bell
+    /// without one.
+    #[cfg(feature = "tts")]
+    const DEFAULT: Self = Self::Sherpa;
+    #[cfg(all(not(feature = "tts"), target_os = "macos"))]
+    const DEFAULT: Self = Self::Say;
+    #[cfg(all(not(feature = "tts"), windows))]
+    const DEFAULT: Self = Self::Sapi;
+    #[cfg(all(not(feature = "tts"), not(target_os = "macos"), not(windows)))]
+    const DEFAULT: Self = Self::Bell;
+}
 
-    fn remaining_time(&self, now: &Timestamp) -> TimeSpan {
-        self.departure_time.time_span_from(now)
+fn preprocessor<C: Coach>(cli: &Cli, coach: &C) -> Preprocessor {
+    let mut preprocessor = Preprocessor::for_language(coach.language());
+    for Pronunciation { word, spoken } in &cli.pronunciations {
+        preprocessor.pronounce(word, spoken);
     }
+    preprocessor
+}
 
-    fn remaining_ratio(&self, now: &Timestamp) -> f64 {
-        let total_secs = self.total_time().total_secs() as f64;
-        let remaing_secs = self.remaining_time(now).total_secs() as f64;
-        remaing_secs / total_secs
+/// Adds the text preprocessing and the optional effects around the speech backend.
+fn wrap_speaker<C: Coach>(
+    cli: &Cli,
+    coach: &C,
+    speaker: Box<dyn Speaker + Send>,
+) -> Box<dyn Speaker + Send> {
+    let mut speaker: Box<dyn Speaker + Send> =
+        Box::new(PreprocessingSpeaker::new(speaker, preprocessor(cli, coach)));
+    let urgent = SpeechProfile {
+        voice: cli.urgent_voice.clone(),
+        rate: cli.urgent_speech_rate,
+        pitch: cli.urgent_speech_pitch,
+    };
+    if !urgent.is_empty() {
+        speaker = Box::new(UrgentSpeaker::new(speaker, urgent));
+    }
+    if cli.pace {
+        speaker = Box::new(PacedSpeaker::new(speaker));
     }
+    #[cfg(feature = "tts")]
+    if let Some(composition) = &cli.compose {
+        let chime = chime(cli).unwrap_or(Chime::Builtin);
+        let bell = BellSpeaker::new(std::io::stdout());
+        speaker = Box::new(ComposedSpeaker::new(
+            speaker,
+            bell,
+            chime,
+            composition.clone(),
+        ));
+    } else if let Some(chime) = chime(cli) {
+        speaker = Box::new(ChimeSpeaker::new(speaker, chime));
+    }
+    #[cfg(feature = "tts")]
+    if !cli.sounds.is_empty() {
+        speaker = Box::new(SoundCueSpeaker::new(
+            speaker,
+            cli.sounds.clone(),
+            cli.sounds_only,
+        ));
+    }
+    if let Some(ramp) = cli.volume_ramp {
+        speaker = Box::new(RampedSpeaker::new(speaker, ramp));
+    }
+    match cli.duck {
+        Some(ducking) => Box::new(DuckingSpeaker::new(speaker, ducking)),
+        None => speaker,
+    }
+}
 
-    fn tick<S: Speaker>(&mut self, speaker: &mut S) -> AppResult<Timestamp> {
-        let now = Timestamp::now().change_context(AppError)?;
-        if self.notifications.pending.is_empty() {
-            self.exit = true;
-        } else {
-            if let Some(n) = self.notifications.pending.pop_if(|n| n.time == now) {
-                self.notifications.emit(n.clone());
-                speaker.speak(&n.message).change_context(AppError)?;
+#[cfg(feature = "tts")]
+fn chime(cli: &Cli) -> Option<Chime> {
+    match &cli.chime_file {
+        Some(path) => Some(Chime::File(path.clone())),
+        None if cli.chime => Some(Chime::Builtin),
+        None => None,
+    }
+}
 
-                if let Some(next_notification) = self.notifications.pending.last() {
-                    let to_next = next_notification.time.time_span_from(&now);
-                    let msg = format!("Prossima notifica tra: {}", to_next);
-                    self.notifications.emit(Notification { message: msg, ..n });
-                }
+fn build_backend(cli: &Cli, recorder: Option<&Recorder>) -> AppResult<Box<dyn Speaker + Send>> {
+    let speaker: Box<dyn Speaker + Send> = match cli.speaker {
+        #[cfg(feature = "tts")]
+        SpeakerBackend::Sherpa => {
+            let mut speaker =
+                TTSSpeaker::new(cli.model_path.as_deref()).change_context(AppError::Speech)?;
+            if let Some(recorder) = recorder {
+                speaker = speaker.with_recorder(recorder.clone());
+            }
+            Box::new(speaker)
+        }
+        SpeakerBackend::Say => {
+            let mut speaker = SaySpeaker::new().change_context(AppError::Speech)?;
+            if let Some(recorder) = recorder {
+                speaker = speaker.with_recorder(recorder.clone());
+            }
+            Box::new(speaker)
+        }
+        SpeakerBackend::Sapi => {
+            let mut speaker = SapiSpeaker::new().change_context(AppError::Speech)?;
+            if let Some(recorder) = recorder {
+                speaker = speaker.with_recorder(recorder.clone());
+            }
+            Box::new(speaker)
+        }
+        #[cfg(feature = "tts")]
+        SpeakerBackend::Cloud => {
+            let api_key = cli
+                .cloud_api_key
+                .clone()
+                .ok_or(AppError::Speech)
+                .attach("the cloud TTS backend requires an API key")
+                .attach_opaque(Suggestion("pass --cloud-api-key or set GOOGLE_TTS_API_KEY"))?;
+            let mut speaker = CloudSpeaker::new(api_key).change_context(AppError::Speech)?;
+            if let Some(recorder) = recorder {
+                speaker = speaker.with_recorder(recorder.clone());
+            }
+            Box::new(speaker)
+        }
+        SpeakerBackend::Bell => Box::new(BellSpeaker::new(std::io::stdout())),
+    };
+    Ok(speaker)
+}
+
+/// Selects the requested voice, or the first one speaking `language`.
+fn choose_voice<S: Speaker + ?Sized>(
+    speaker: &mut S,
+    requested: Option<&str>,
+    language: &str,
+) -> AppResult<()> {
+    let voices = speaker.voices().change_context(AppError::Speech)?;
+    match select_voice(&voices, requested, language) {
+        Some(voice) => {
+            if !voice.speaks(language) {
+                warn!(
+                    "The voice `{}` speaks `{}`, not `{language}`",
+                    voice.name, voice.language
+                );
             }
+            speaker
+                .set_voice(&voice.name)
+                .change_context(AppError::Speech)
         }
-        Ok(now)
+        None => match requested {
+            Some(name) => Err(AppError::Speech)
+                .attach(format!("unknown voice: {name}"))
+                .attach_opaque(Suggestion("run `rendezvous-coach voices` to list them")),
+            None => {
+                warn!("No voice speaking `{language}` found, using the default one");
+                Ok(())
+            }
+        },
     }
+}
 
-    fn run<B: Backend, S: Speaker>(
-        &mut self,
-        terminal: &mut Terminal<B>,
-        speaker: &mut S,
-    ) -> AppResult<()> {
-        let tick_time = Duration::from_secs(1);
-        loop {
-            let now = self.tick(speaker)?;
+/// The language of the voice: `--lang`, or the coach language.
+fn voice_language<C: Coach>(cli: &Cli, coach: &C) -> String {
+    match &cli.lang {
+        Some(language) => {
+            if !language.eq_ignore_ascii_case(coach.language()) {
+                warn!(
+                    "The coach speaks `{}`, not `{language}` as the voice",
+                    coach.language()
+                );
+            }
+            language.clone()
+        }
+        None => coach.language().to_owned(),
+    }
+}
 
-            terminal
-                .draw(|frame| self.draw(frame))
-                .change_context(AppError)
-                .attach("cannot render frame")?;
+fn check_voice<S: Speaker + ?Sized>(speaker: &S, name: &str) -> AppResult<()> {
+    let voices = speaker.voices().change_context(AppError::Speech)?;
+    if voices.iter().any(|v| v.name.eq_ignore_ascii_case(name)) {
+        return Ok(());
+    }
+    Err(AppError::Speech)
+        .attach(format!("unknown voice: {name}"))
+        .attach_opaque(Suggestion("run `rendezvous-coach voices` to list them"))
+}
 
-            self.handle_events(tick_time)?;
+fn list_voices(cli: &Cli) -> AppResult<()> {
+    let speaker = build_backend(cli, None)?;
+    for voice in speaker.voices().change_context(AppError::Speech)? {
+        println!("{:<30} {}", voice.name, voice.language);
+    }
+    Ok(())
+}
 
-            if self.remaining_time(&now) == TimeSpan::ZERO {
-                self.exit = true;
+/// Prints the remaining time of the running session, styled by urgency, or nothing when no
+/// session is listening.
+#[cfg(feature = "control")]
+fn tmux_status(socket: Option<&std::path::Path>) -> AppResult<()> {
+    let socket = socket.map_or_else(default_socket_path, |s| s.to_path_buf());
+    match request(&socket, "status") {
+        Ok(status) => {
+            if let Some(line) = tmux_status_line(&status) {
+                println!("{line}");
             }
+        }
+        Err(report) => debug!("no session to show: {report:?}"),
+    }
+    Ok(())
+}
 
-            if self.exit {
-                break;
-            }
+/// Prints the systemd units for `schedule`, or installs them.
+fn systemd_units(schedule: &Schedule, install: bool, options: &[String]) -> AppResult<()> {
+    let program = std::env::current_exe().change_context(AppError::Other)?;
+    let units = Units::new(schedule, &program, options).change_context(AppError::Config)?;
+    if !install {
+        println!("# {}\n{}", units.service_name, units.service);
+        println!("# {}\n{}", units.timer_name, units.timer);
+        return Ok(());
+    }
+    let dir = user_unit_dir().change_context(AppError::Storage)?;
+    for path in units.install(&dir).change_context(AppError::Storage)? {
+        println!("Written {}", path.display());
+    }
+    println!(
+        "Enable with: systemctl --user daemon-reload && systemctl --user enable --now {}",
+        units.timer_name
+    );
+    Ok(())
+}
+
+#[cfg(feature = "history")]
+fn export_history(format: ExportFormat, since: Option<&Timestamp>) -> AppResult<()> {
+    let history = History::open(&History::default_path()).change_context(AppError::Storage)?;
+    let mut out = std::io::stdout().lock();
+    match format {
+        ExportFormat::Csv => history.export_csv(since, &mut out),
+        ExportFormat::Json => history.export_json(since, &mut out),
+    }
+    .change_context(AppError::Storage)
+}
+
+/// The remaining time in a few characters (e.g. `1h05` or `4:32`), styled for tmux.
+#[cfg(feature = "control")]
+fn tmux_status_line(status: &serde_json::Value) -> Option<String> {
+    let remaining = TimeSpan::of_seconds(status["remaining_seconds"].as_u64()?);
+    let style = match Urgency::for_remaining(&remaining) {
+        Urgency::Low => "default",
+        Urgency::Medium => "yellow",
+        Urgency::High => "red",
+        Urgency::Critical => "red,bold,blink",
+    };
+    let time = if remaining.hours() > 0 {
+        format!("{}h{:02}", remaining.hours(), remaining.minutes())
+    } else {
+        format!("{}:{:02}", remaining.minutes(), remaining.seconds())
+    };
+    let snoozed = if status["snoozed_until"].is_string() || status["muted"] == true {
+        " 🔇"
+    } else {
+        ""
+    };
+    Some(format!("#[fg={style}]🚗 {time}{snoozed}#[default]"))
+}
+
+/// The coach of the messages, the --coach-plugin if given.
+fn build_coach(cli: &Cli) -> AppResult<Box<dyn Coach>> {
+    match &cli.coach_plugin {
+        Some(command) => {
+            let coach = PluginCoach::spawn(command).change_context(AppError::Other)?;
+            Ok(Box::new(coach))
         }
-        Ok(())
+        None => Ok(Box::new(DefaultItCoach)),
     }
+}
 
-    fn draw(&self, frame: &mut Frame) {
-        frame.render_widget(self, frame.area());
+/// The sinks asked on the command line.
+fn sink_options(cli: &Cli) -> SinkOptions {
+    SinkOptions {
+        filters: cli.sink_filters.clone(),
+        json: matches!(cli.output, Some(OutputFormat::Json)),
+        status: match cli.output {
+            Some(OutputFormat::Status) => Some(StatusFormat::Plain(cli.status_format.clone())),
+            Some(OutputFormat::Waybar) => Some(StatusFormat::Waybar(cli.status_format.clone())),
+            _ => None,
+        },
+        status_interval: TimeSpan::of_seconds(cli.status_interval),
+        terminal_title: cli.terminal_title,
+        #[cfg(feature = "tui")]
+        interface: interface(cli),
+        #[cfg(not(feature = "tui"))]
+        interface: false,
+        #[cfg(feature = "desktop")]
+        desktop_notifications: cli.desktop_notifications,
+        kdeconnect: cli.kdeconnect.clone(),
+        #[cfg(feature = "dbus")]
+        dbus: cli.dbus,
+        plugins: cli.sink_plugins.clone(),
+        #[cfg(feature = "sinks-net")]
+        network: NetworkSinkOptions {
+            retries: cli.retries,
+            dead_letters: cli
+                .dead_letters
+                .clone()
+                .unwrap_or_else(DeadLetters::default_path),
+            webhook: cli.webhook.clone(),
+            webhook_auth: cli.webhook_auth.clone(),
+            mqtt: cli.mqtt.clone(),
+            mqtt_port: cli.mqtt_port,
+            mqtt_topic: cli.mqtt_topic.clone(),
+            mqtt_credentials: cli.mqtt_username.clone().zip(cli.mqtt_password.clone()),
+            mqtt_discovery: cli.mqtt_discovery.clone(),
+            email_to: cli.email_to.clone(),
+            email_from: cli.email_from.clone(),
+            email_at: cli.email_at.clone(),
+            smtp: cli.smtp_host.as_ref().map(|host| SmtpServer {
+                host: host.clone(),
+                port: cli.smtp_port,
+                credentials: cli.smtp_username.clone().zip(cli.smtp_password.clone()),
+            }),
+            websocket: cli.websocket.clone(),
+        },
     }
+}
 
-    fn handle_events(&mut self, poll_time: Duration) -> AppResult<()> {
-        let event_available = event::poll(poll_time)
-            .change_context(AppError)
-            .attach("cannot read event")?;
-        if event_available {
-            match event::read()
-                .change_context(AppError)
-                .attach("cannot read event")?
-            {
-                event::Event::Key(key_event) if key_event.kind == event::KeyEventKind::Press => {
-                    match key_event.code {
-                        event::KeyCode::Char('q') => self.exit = true,
-                        _ => (),
-                    }
-                    Ok(())
-                }
-                _ => Ok(()),
-            }
-        } else {
+/// Whether the session is shown in the terminal interface, rather than only spoken and delivered
+/// to the sinks.
+#[cfg(feature = "tui")]
+fn interface(cli: &Cli) -> bool {
+    !cli.headless && matches!(cli.output, None | Some(OutputFormat::Tui))
+}
+
+fn main() -> ExitCode {
+    init::error_reporting();
+    match start() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(report) => error::exit(report),
+    }
+}
+
+/// Runs the command of the command line.
+fn start() -> AppResult<()> {
+    let config_path = Config::path();
+    let config = Config::load(&config_path).change_context(AppError::Config)?;
+    let layers = Layers::new(Cli::command(), &config, std::env::args_os().collect());
+    let matches = layers.matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    cli.keys = config.key_bindings().change_context(AppError::Config)?;
+    let logs = init::tracing(&LogOptions {
+        level: cli.log_level,
+        file: cli.log_file.clone(),
+        json: cli.log_json,
+    });
+    match cli.command {
+        Some(Command::Config {
+            command: ConfigCommand::Path,
+        }) => {
+            println!("{}", config_path.display());
+            Ok(())
+        }
+        Some(Command::Config {
+            command: ConfigCommand::Dump,
+        }) => {
+            print!("{}", layers.dump(&matches));
+            Ok(())
+        }
+        Some(Command::Preview {
+            rendezvous,
+            trip,
+            format,
+        }) => {
+            let plan = Plan {
+                rendezvous_time: rendezvous,
+                trip_duration: trip,
+            };
+            let now = Timestamp::now().change_context(AppError::Other)?;
+            let coach = build_coach(&cli)?;
+            preview(&plan, &now, &coach, format, &mut std::io::stdout().lock())
+        }
+        Some(Command::Run { name, options }) => {
+            let profile = Profiles::beside(&config_path)
+                .load(&name)
+                .change_context(AppError::Config)?;
+            let config = config.overridden_by(profile);
+            let args = std::env::args_os()
+                .take(1)
+                .chain(options.into_iter().map(OsString::from))
+                .collect();
+            run(&layered_cli(&config, args)?, logs, None)
+        }
+        Some(Command::Resume { options }) => {
+            let state = StateFile::new(cli.state_file.unwrap_or_else(StateFile::default_path))
+                .load()
+                .change_context(AppError::Storage)
+                .attach_opaque(Suggestion("no session to resume, start one with -r and -t"))?;
+            let plan = [
+                "--rendezvous".to_owned(),
+                state.plan.rendezvous_time.to_rfc3339(),
+                "--trip".to_owned(),
+                state.plan.trip_duration.to_string(),
+            ];
+            let args = std::env::args_os()
+                .take(1)
+                .chain(plan.into_iter().chain(options).map(OsString::from))
+                .collect();
+            run(&layered_cli(&config, args)?, logs, Some(state))
+        }
+        Some(Command::Profile { ref command }) => profile(command, &Profiles::beside(&config_path)),
+        Some(Command::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_owned();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
             Ok(())
         }
+        Some(Command::Man) => clap_mangen::Man::new(Cli::command())
+            .render(&mut std::io::stdout())
+            .change_context(AppError::Other)
+            .attach("cannot print the man page"),
+        Some(Command::Voices) => list_voices(&cli),
+        #[cfg(feature = "control")]
+        Some(Command::TmuxStatus { ref socket }) => tmux_status(socket.as_deref()),
+        #[cfg(all(feature = "control", feature = "tui"))]
+        Some(Command::Attach { ref socket }) => rendezvous_coach::app::attach(socket.as_deref()),
+        Some(Command::Systemd {
+            ref profile,
+            rendezvous,
+            trip,
+            ref days,
+            lead,
+            install,
+            ref options,
+        }) => {
+            let schedule = Schedule {
+                profile: profile.clone(),
+                days: days.clone(),
+                rendezvous,
+                trip,
+                lead,
+            };
+            systemd_units(&schedule, install, options)
+        }
+        Some(Command::Daemon {
+            ref schedule,
+            lead,
+            ref options,
+        }) => {
+            let program = std::env::current_exe().change_context(AppError::Other)?;
+            Daemon::new(program, options.clone(), lead)
+                .watch(schedule)
+                .change_context(AppError::InvalidRendezvous)
+        }
+        #[cfg(feature = "history")]
+        Some(Command::Stats { since }) => {
+            let history =
+                History::open(&History::default_path()).change_context(AppError::Storage)?;
+            let sessions = history
+                .sessions(since.as_ref())
+                .change_context(AppError::Storage)?;
+            print!("{}", Statistics::of(&sessions));
+            Ok(())
+        }
+        #[cfg(feature = "history")]
+        Some(Command::History {
+            command: HistoryCommand::Export { format, since },
+        }) => export_history(format, since.as_ref()),
+        None => run(&cli, logs, None),
     }
 }
 
-impl Widget for &AppState {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let title = Line::from(vec![
-            Span::styled(
-                "Departure time",
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::raw(" 🚗 "),
-            Span::styled(
-                format!("{}", self.departure_time),
-                Style::default().fg(Color::Green),
-            )
-            .add_modifier(Modifier::ITALIC),
-            Span::raw(" | (q) Quit"),
-        ]);
-        let block = Block::new().title(title.centered());
-        block.render(area, buf);
-
-        let vertical = Layout::vertical([
-            Constraint::Length(2),
-            Constraint::Length(self.notifications.max_emitted as u16),
-        ])
-        .margin(1);
-        let [progress_area, main] = vertical.areas(area);
-
-        let now = Timestamp::now().unwrap();
-        let remaining_time = self.remaining_time(&now);
-        let ratio = self.remaining_ratio(&now);
-        let label = Line::from(vec![
-            Span::raw("Remaining time").add_modifier(Modifier::BOLD),
-            Span::raw(" ⏰ "),
-            Span::styled(
-                format!("{}", remaining_time),
-                Style::default().fg(Color::Red),
-            ),
-        ]);
-        let progress = LineGauge::default()
-            .filled_style(Style::default().fg(Color::Red))
-            .line_set(symbols::line::THICK)
-            .label(label)
-            .ratio(ratio);
-        progress.render(progress_area, buf);
-
-        // in progress downloads
-        let items: Vec<ListItem> = self
-            .notifications
-            .emitted
-            .iter()
-            .map(|n| {
-                ListItem::new(Line::from(vec![
-                    Span::styled(format!("{}", n.time), Style::default().fg(Color::Gray)),
-                    Span::raw(" ➡ "),
-                    Span::styled(
-                        format!("{}", n.message),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ),
-                ]))
-            })
-            .collect();
-        let list = List::new(items);
-        list.render(main, buf);
-    }
-}
-
-fn main() -> AppResult<()> {
-    init::error_reporting();
-    init::tracing();
+/// Parses the command line `args`, after the environment variables and `config`.
+fn layered_cli(config: &Config, args: Vec<OsString>) -> AppResult<Cli> {
+    let matches = Layers::new(Cli::command(), config, args).matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    cli.keys = config.key_bindings().change_context(AppError::Config)?;
+    Ok(cli)
+}
+
+/// Stores, lists, prints or removes the profiles.
+fn profile(command: &ProfileCommand, profiles: &Profiles) -> AppResult<()> {
+    match command {
+        ProfileCommand::Save { name, options } => {
+            // The rendezvous may be left for the run command
+            let command = Cli::command().mut_arg("rendezvous", |arg| arg.required(false));
+            let matches = command
+                .clone()
+                .try_get_matches_from(
+                    std::iter::once("rendezvous-coach").chain(options.iter().map(String::as_str)),
+                )
+                .unwrap_or_else(|e| e.exit());
+            let options = command
+                .get_arguments()
+                .filter(|arg| {
+                    matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+                })
+                .filter_map(|arg| Some((arg.get_long()?.to_owned(), option_value(arg, &matches)?)))
+                .collect();
+            let path = profiles
+                .save(name, &Config::new(options))
+                .change_context(AppError::Config)?;
+            println!(
+                "Saved {}, start it with: rendezvous-coach run {name}",
+                path.display()
+            );
+        }
+        ProfileCommand::List => {
+            for name in profiles.list().change_context(AppError::Config)? {
+                println!("{name}");
+            }
+        }
+        ProfileCommand::Show { name } => {
+            print!(
+                "{}",
+                profiles
+                    .load(name)
+                    .change_context(AppError::Config)?
+                    .to_toml()
+            );
+        }
+        ProfileCommand::Delete { name } => {
+            profiles.delete(name).change_context(AppError::Config)?
+        }
+    }
+    Ok(())
+}
+
+/// Sets up a session and its history, as asked on the command line.
+fn configure<C: Coach>(session: &mut Session<C>, cli: &Cli) {
+    session.push_increment = cli.push_increment;
+    session.engine.overdue = cli.overdue;
+    #[cfg(feature = "history")]
+    if !cli.no_history {
+        match History::open(&History::default_path()).and_then(|history| {
+            let id = history.start(&session.engine.plan, &session.engine.started)?;
+            Ok((history, id))
+        }) {
+            Ok(history) => session.history = Some(history),
+            Err(report) => warn!("Not storing the session in the history: {report:?}"),
+        }
+    }
+}
 
-    let cli = Cli::parse();
+/// Sets up the terminal interface of a session, as asked on the command line.
+#[cfg(feature = "tui")]
+fn configure_interface<C: Coach>(app: &mut AppState<C>, cli: &Cli) {
+    app.fullscreen = cli.fullscreen;
+    app.minimal = cli.minimal;
+    app.big_countdown = cli.big_countdown;
+    app.gauge_style = cli.gauge_style;
+    app.gauge_direction = cli.gauge_direction;
+    app.show_schedule = cli.fullscreen;
+    app.theme = cli.theme;
+    for (element, color) in &cli.theme_colors {
+        app.theme
+            .set(element, *color)
+            .expect("element checked by parse_theme_color");
+    }
+    if cli.no_color || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        app.theme = Theme::MONO;
+    }
+    if cli.ascii {
+        app.glyphs = Glyphs::ASCII;
+    }
+    app.keys = cli.keys.clone();
+    app.high_contrast = cli.high_contrast;
+    app.blink = cli.blink;
+    app.visual_alarm = cli.visual_alarm;
+    app.confirm_quit = cli.confirm_quit;
+}
+
+/// Synthesizes ahead the messages of `sessions`, not to wait for the model when they are due.
+#[cfg(feature = "tts")]
+fn presynthesize<'a, C: Coach + 'a>(
+    runtime: &tokio::runtime::Runtime,
+    speaker: &mut Box<dyn Speaker + Send>,
+    sessions: impl Iterator<Item = &'a Session<C>>,
+) -> AppResult<()> {
+    let messages: Vec<&str> = sessions
+        .flat_map(|session| &session.engine.notifications.pending)
+        .map(|n| n.message.as_str())
+        .collect();
+    runtime
+        .block_on(prepare_all(speaker, &messages))
+        .change_context(AppError::Speech)
+}
+
+/// Starts a session, or takes up the one `resumed`.
+#[cfg_attr(not(feature = "tui"), allow(unused_variables))]
+fn run(cli: &Cli, logs: RecentLogs, resumed: Option<SessionState>) -> AppResult<()> {
+    #[cfg(feature = "control")]
+    if cli.detach {
+        let socket = cli
+            .control_socket
+            .clone()
+            .flatten()
+            .unwrap_or_else(default_socket_path);
+        let process = session::detach(&socket, cli.control_socket.is_some())?;
+        println!(
+            "Session running in the background (process {process}), follow it with: rendezvous-coach attach --socket {}",
+            socket.display()
+        );
+        return Ok(());
+    }
+    let (Some(rendezvous), Some(trip)) = (&cli.rendezvous, &cli.trip) else {
+        unreachable!("rendezvous and trip are required without a subcommand");
+    };
     let plan = Plan {
-        rendezvous_time: Timestamp::parse_today_time(&cli.rendezvous).change_context(AppError)?,
-        trip_duration: TimeSpan::parse(&cli.trip).change_context(AppError)?,
+        rendezvous_time: Timestamp::parse(rendezvous)
+            .or_else(|_| Timestamp::parse_today_time(rendezvous))
+            .change_context(AppError::InvalidRendezvous)?,
+        trip_duration: TimeSpan::parse(trip).change_context(AppError::InvalidRendezvous)?,
     };
+    if cli.dry_run {
+        let now = Timestamp::now().change_context(AppError::Other)?;
+        let coach = build_coach(cli)?;
+        let plans = std::iter::once(&plan);
+        #[cfg(feature = "tui")]
+        let plans = plans.chain(&cli.also);
+        let tables = plans
+            .map(|plan| schedule_table(plan, &now, &coach))
+            .collect::<AppResult<Vec<_>>>()?;
+        print!("{}", tables.join("\n"));
+        return Ok(());
+    }
 
-    let coach = DefaultItCoach;
-    let mut speaker = TTSSpeaker::new(cli.model_path.as_deref()).change_context(AppError)?;
-
-    let mut app = AppState::new(&plan, coach, 10)?;
+    let coach = build_coach(cli)?;
+    // Sounds need audio too, so the fallback goes without them.
+    let mut speaker_health = None;
+    let (mut speaker, speech_warning) = if cli.tts_dry_run {
+        let printer = PrintSpeaker::new(std::io::stderr());
+        let dry_run: Box<dyn Speaker + Send> = Box::new(PreprocessingSpeaker::new(
+            printer,
+            preprocessor(cli, &coach),
+        ));
+        (dry_run, Some("TTS dry run".to_owned()))
+    } else {
+        let recorder = cli
+            .record
+            .as_deref()
+            .map(Recorder::new)
+            .transpose()
+            .change_context(AppError::Storage)?;
+        if let Some(recorder) = &recorder {
+            info!("Recording the messages to {}", recorder.dir().display());
+        }
+        match build_backend(cli, recorder.as_ref()) {
+            Ok(backend) => {
+                let factory_cli = cli.clone();
+                let backend = ResilientSpeaker::new(
+                    backend,
+                    move || {
+                        build_backend(&factory_cli, recorder.as_ref()).change_context(SpeakerError)
+                    },
+                    Duration::from_secs(cli.speech_timeout),
+                );
+                speaker_health = Some(backend.health());
+                let mut speaker = wrap_speaker(cli, &coach, Box::new(backend));
+                choose_voice(
+                    &mut speaker,
+                    cli.voice.as_deref(),
+                    &voice_language(cli, &coach),
+                )?;
+                if let Some(name) = &cli.urgent_voice {
+                    check_voice(&speaker, name)?;
+                }
+                (speaker, None)
+            }
+            Err(report) => {
+                warn!(
+                    "Speech is not available, falling back to bell and desktop notifications: {report:?}"
+                );
+                let fallback: Box<dyn Speaker + Send> = Box::new(SilentSpeaker);
+                (fallback, Some("Speech unavailable".to_owned()))
+            }
+        }
+    };
+    speaker
+        .set_options(SpeechOptions {
+            rate: cli.speech_rate,
+            pitch: cli.speech_pitch,
+            volume: cli.volume,
+        })
+        .change_context(AppError::Speech)?;
 
-    // viewport height in lines =
-    // 1 (departure time) +
-    // 1 (remaining w/ line gauge) +
-    // (max number of messages)
-    let mut terminal = ratatui::init_with_options(TerminalOptions {
-        viewport: Viewport::Inline(2 + app.notifications.max_emitted as u16),
-    });
+    // The full screen has room for a longer history
+    #[cfg(feature = "tui")]
+    let max_messages = if cli.fullscreen { 50 } else { 10 };
+    #[cfg(not(feature = "tui"))]
+    let max_messages = 10;
+    let mut session = Session::new(&plan, coach, max_messages)?;
+    configure(&mut session, cli);
+    session.state_file = Some(StateFile::new(
+        cli.state_file
+            .clone()
+            .unwrap_or_else(StateFile::default_path),
+    ));
+    if let Some(state) = resumed {
+        info!(
+            "Resuming the session, {} notifications emitted",
+            state.emitted.len()
+        );
+        session.engine.restore(state);
+    }
+    session.session_log = cli
+        .session_log
+        .as_deref()
+        .map(SessionLog::open)
+        .transpose()
+        .change_context(AppError::Storage)?;
+    session.sinks = sink_options(cli)
+        .dispatcher(plan.departure_time())
+        .change_context(AppError::Sink)?;
+    #[cfg(feature = "control")]
+    if cli.control_socket.is_some() || cli.http.is_some() {
+        let mut control = ControlServer::new();
+        if let Some(path) = &cli.control_socket {
+            let path = path.clone().unwrap_or_else(default_socket_path);
+            control
+                .listen_unix(&path)
+                .change_context(AppError::Session)?;
+            info!("Accepting commands on {}", path.display());
+        }
+        if let Some(address) = &cli.http {
+            control
+                .listen_http(address)
+                .change_context(AppError::Session)?;
+            info!("Serving the status on http://{address}/status");
+        }
+        session.control = Some(control);
+    }
 
-    let result = app.run(&mut terminal, &mut speaker);
+    let runtime = session::runtime()?;
+    #[cfg(feature = "tui")]
+    if interface(cli) {
+        let mut app = AppState::with_session(session);
+        app.speech_warning = speech_warning;
+        app.speaker_health = speaker_health;
+        // The warning in the title tells when speech is not available
+        if app.speech_warning.is_none() {
+            app.speech_backend = cli
+                .speaker
+                .to_possible_value()
+                .map(|value| value.get_name().to_owned());
+        }
+        // The other rendezvous are only shown and spoken, the sinks and the control server
+        // follow the first one
+        let mut apps = vec![app];
+        for plan in &cli.also {
+            let mut other = Session::new(plan, build_coach(cli)?, max_messages)?;
+            configure(&mut other, cli);
+            apps.push(AppState::with_session(other));
+        }
+        for app in &mut apps {
+            configure_interface(app, cli);
+            app.logs = Some(logs.clone());
+        }
+        let mut tabs = Tabs::new(apps, cli.fps);
+        tabs.mouse = cli.mouse;
+        #[cfg(feature = "tts")]
+        if cli.presynthesize {
+            presynthesize(
+                &runtime,
+                &mut speaker,
+                tabs.apps.iter().map(|app| &app.session),
+            )?;
+        }
+        let speaker = QueuedSpeaker::new(speaker);
+        for app in &mut tabs.apps {
+            app.speech_activity = Some(speaker.activity());
+        }
+        return tabs.run_in_terminal(&runtime, speaker);
+    }
 
-    ratatui::restore();
+    #[cfg(feature = "tui")]
+    if !cli.also.is_empty() {
+        warn!("Counting down to the first rendezvous only, --also needs the terminal interface");
+    }
+    #[cfg(feature = "tts")]
+    if cli.presynthesize {
+        presynthesize(&runtime, &mut speaker, std::iter::once(&session))?;
+    }
+    let mut speaker = QueuedSpeaker::new(speaker);
+    let result = runtime.block_on(session.run_headless(&mut speaker));
+    speaker.finish();
     result
 }
 
 #[cfg(test)]
 mod tests {
+    use rendezvous_coach::feature::tts::{CancelWatch, SpeakerFuture, SpeakerResult, Voice};
+
     use super::*;
 
     #[test]
-    fn app_state_just_created_is_a_clean_slate() {
-        let plan = Plan {
-            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
-            trip_duration: TimeSpan::of_minutes(15),
-        };
-        let state = AppState::new(&plan, DefaultItCoach, 5).unwrap();
+    fn sink_filters_name_the_plugins() {
+        assert!(parse_sink_filter("plugin=all").is_ok());
+        assert_eq!("plugin-2", parse_sink_filter("plugin-2=high").unwrap().0);
+        assert!(parse_sink_filter("plugin-0=high").is_err());
+        assert!(parse_sink_filter("pager=all").is_err());
+    }
 
-        assert!(!state.exit);
-        assert!(state.notifications.emitted.is_empty());
+    /// A speaker offering a voice in English and one in Italian, remembering the one selected.
+    #[derive(Default)]
+    struct TwoVoicesSpeaker {
+        voice: Option<String>,
+    }
+
+    impl Speaker for TwoVoicesSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            _content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![
+                Voice::new("Samantha", "en_US"),
+                Voice::new("Alice", "it_IT"),
+            ])
+        }
+
+        fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+            self.voice = Some(name.to_owned());
+            Ok(())
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
     }
 
     #[test]
-    fn notifications_emitted_is_a_ring_with_fixed_capacity() {
-        let mut notifications = Notifications::new(vec![], 5);
-
-        for i in 1..=10 {
-            notifications.emit(Notification {
-                time: Timestamp::now().unwrap(),
-                message: format!("{i}"),
-            })
-        }
-
-        let actual: Vec<_> = notifications
-            .emitted
-            .into_iter()
-            .map(|m| m.message)
-            .collect();
-        assert_eq!(vec!["10", "9", "8", "7", "6"], actual);
+    fn choose_voice_speaking_the_language() {
+        let mut speaker = TwoVoicesSpeaker::default();
+
+        choose_voice(&mut speaker, None, "en").unwrap();
+        assert_eq!(Some("Samantha"), speaker.voice.as_deref());
+
+        choose_voice(&mut speaker, Some("alice"), "en").unwrap();
+        assert_eq!(Some("Alice"), speaker.voice.as_deref());
+
+        let mut speaker = TwoVoicesSpeaker::default();
+        choose_voice(&mut speaker, None, "de").unwrap();
+        assert_eq!(None, speaker.voice);
+        assert!(choose_voice(&mut speaker, Some("Paola"), "it").is_err());
+    }
+
+    #[test]
+    fn lang_is_a_language_code() {
+        assert_eq!(Ok("en".to_owned()), parse_language("EN"));
+        assert!(parse_language("english").is_err());
+        assert!(parse_language("e1").is_err());
+    }
+
+    #[cfg(feature = "control")]
+    #[test]
+    fn tmux_status_line_is_compact() {
+        let status = |remaining: u64| serde_json::json!({ "remaining_seconds": remaining });
+
+        assert_eq!(
+            Some("#[fg=default]🚗 1h05#[default]".to_owned()),
+            tmux_status_line(&status(3900))
+        );
+        assert_eq!(
+            Some("#[fg=red]🚗 4:32#[default]".to_owned()),
+            tmux_status_line(&status(272))
+        );
+        assert_eq!(
+            None,
+            tmux_status_line(&serde_json::json!({ "error": "no answer" }))
+        );
     }
 }
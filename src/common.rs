@@ -1 +1,63 @@
 //! Functionality shared between features
+
+use std::path::PathBuf;
+
+/// `rendezvous-coach` in the data directory of the user, e.g. `~/.local/share` on Linux or
+/// `%APPDATA%` on Windows, where the history and the state of the session are kept.
+pub fn data_dir() -> PathBuf {
+    // The roaming one on Windows, as the configuration
+    #[cfg(windows)]
+    let dir = dirs::data_dir();
+    #[cfg(not(windows))]
+    let dir = dirs::data_local_dir();
+    dir.unwrap_or_else(|| fallback_dir(".local/share"))
+        .join("rendezvous-coach")
+}
+
+/// `rendezvous-coach` in the configuration directory of the user, e.g. `~/.config` on Linux or
+/// `%APPDATA%` on Windows.
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| fallback_dir(".config"))
+        .join("rendezvous-coach")
+}
+
+/// The directory of the user when the platform does not tell: `%APPDATA%` on Windows, else
+/// `unix_dir` in the home directory.
+fn fallback_dir(unix_dir: &str) -> PathBuf {
+    if cfg!(windows)
+        && let Some(app_data) = std::env::var_os("APPDATA")
+    {
+        return PathBuf::from(app_data);
+    }
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(unix_dir)
+}
+
+/// Joins `fields` with commas, quoting those with commas, quotes or line breaks.
+pub fn csv_row<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_row_quotes_the_fields_as_needed() {
+        let row = csv_row(["17:40", "Ciao, ci siamo", "il \"treno\""].into_iter());
+
+        assert_eq!("17:40,\"Ciao, ci siamo\",\"il \"\"treno\"\"\"", row);
+    }
+}
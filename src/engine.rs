@@ -0,0 +1,591 @@
+//! The countdown to a departure, independent of the front-end showing it: the notifications
+//! planned and emitted, the state of the speech (muted, paused, snoozed) and what to do at each
+//! tick of a clock. The terminal interface drives it once a second, other front-ends (e.g. a GUI,
+//! a web page, the tests) can drive it with their own clock.
+
+use std::collections::VecDeque;
+
+use error_stack::{Report, ResultExt};
+use tracing::{Span, debug, info, info_span};
+
+use crate::feature::coach::Coach;
+use crate::feature::sink::{Dispatcher, Outcome};
+use crate::feature::state::SessionState;
+use crate::feature::tts::{Announcer, Utterance};
+use crate::plan::{Notification, Plan, Urgency};
+use crate::time::{TimeResult, TimeSpan, Timestamp};
+
+#[derive(Debug, thiserror::Error)]
+#[error("engine error")]
+pub struct EngineError;
+
+pub type EngineResult<T> = Result<T, Report<EngineError>>;
+
+/// Names of the events of a session, in the `event` field of the log, within the `session` span,
+/// so that the log and the metrics share one event model.
+pub mod event {
+    pub const SESSION_STARTED: &str = "session_started";
+    pub const NOTIFICATION_EMITTED: &str = "notification_emitted";
+    pub const SPEECH_COMPLETED: &str = "speech_completed";
+    pub const REPLAN: &str = "replan";
+    pub const SESSION_FINISHED: &str = "session_finished";
+}
+
+/// Where the engine gets the time from.
+pub trait Clock {
+    fn now(&self) -> TimeResult<Timestamp>;
+}
+
+/// The clock of the system, to the second.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> TimeResult<Timestamp> {
+        Timestamp::now()
+    }
+}
+
+/// Notifications still to emit, kept in time order whatever the order they are planned in, so
+/// that the next one due is always first.
+#[derive(Debug, Default)]
+pub struct Pending(VecDeque<Notification>);
+
+impl Pending {
+    pub fn new(mut notifications: Vec<Notification>) -> Self {
+        notifications.sort_by_key(|n| n.time);
+        Self(notifications.into())
+    }
+
+    /// The next one due.
+    pub fn next(&self) -> Option<&Notification> {
+        self.0.front()
+    }
+
+    /// Takes the one due at `now`, however late the tick: the latest one, when several are due,
+    /// the others being stale by then.
+    pub fn pop_due(&mut self, now: &Timestamp) -> Option<Notification> {
+        while self.0.get(1).is_some_and(|n| n.time <= *now) {
+            if let Some(stale) = self.0.pop_front() {
+                debug!("Late, skipping `{}`", stale.message);
+            }
+        }
+        if self.0.front().is_some_and(|n| n.time <= *now) {
+            self.0.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Drops those due before `now`, returning how many.
+    pub fn drop_before(&mut self, now: &Timestamp) -> usize {
+        let pending = self.0.len();
+        self.0.retain(|n| n.time >= *now);
+        pending - self.0.len()
+    }
+
+    /// In time order, the next one due first.
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, Notification> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Pending {
+    type Item = &'a Notification;
+    type IntoIter = std::collections::vec_deque::Iter<'a, Notification>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The notifications still to emit, and the last ones emitted.
+#[derive(Debug)]
+pub struct Notifications {
+    pub pending: Pending,
+    /// Latest first, at most `max_emitted`
+    pub emitted: VecDeque<Notification>,
+    max_emitted: usize,
+}
+
+impl Notifications {
+    pub fn new(pending: Vec<Notification>, max_emitted: usize) -> Self {
+        Self {
+            pending: Pending::new(pending),
+            emitted: VecDeque::with_capacity(max_emitted),
+            max_emitted,
+        }
+    }
+
+    pub fn emit(&mut self, n: Notification) {
+        self.emitted.push_front(n);
+        self.emitted.truncate(self.max_emitted);
+    }
+}
+
+/// A notification emitted at a tick.
+pub struct Emitted {
+    pub notification: Notification,
+    /// Remaining time before departure at the time of the notification
+    pub remaining: TimeSpan,
+    /// Outcome on each channel, the speech first
+    pub outcomes: Vec<(String, Outcome)>,
+    /// Whether it was spoken, when not silenced
+    pub announced: EngineResult<()>,
+}
+
+/// What happened at a tick.
+pub struct Tick {
+    pub now: Timestamp,
+    /// Nothing left to emit, and not counting past departure
+    pub over: bool,
+    pub emitted: Option<Emitted>,
+}
+
+/// The countdown to the departure of a plan.
+pub struct Engine<C> {
+    pub coach: C,
+    pub plan: Plan,
+    pub departure_time: Timestamp,
+    pub started: Timestamp,
+    pub notifications: Notifications,
+    pub muted: bool,
+    /// Notifications neither shown nor spoken, e.g. during a phone call
+    pub paused: bool,
+    /// Not speaking until then
+    pub snoozed_until: Option<Timestamp>,
+    /// Snoozes so far, to resume the session with
+    pub snoozes: u32,
+    /// Speak the remaining time at the next tick, out of schedule
+    pub update_requested: bool,
+    /// Last notification spoken (or due to be spoken while muted)
+    pub last_announced: Option<Notification>,
+    /// Speak the last notification again at the next tick
+    pub replay_requested: bool,
+    /// Message to speak at the next tick, e.g. about a change of plan
+    pub announcement: Option<String>,
+    /// Running past departure, until quit
+    pub overdue: bool,
+    /// The `session` span, which the events of the session are logged in
+    span: Span,
+}
+
+impl<C: Coach> Engine<C> {
+    /// Plans the notifications from `now`, keeping the last `max_emitted` ones emitted.
+    pub fn new(plan: &Plan, coach: C, max_emitted: usize, now: Timestamp) -> EngineResult<Self> {
+        let pending = plan
+            .notifications(&now, &coach)
+            .change_context(EngineError)?;
+        let departure_time = plan.departure_time();
+        let span = info_span!(
+            "session",
+            rendezvous = %plan.rendezvous_time,
+            trip = %plan.trip_duration,
+        );
+        span.in_scope(|| {
+            info!(
+                event = event::SESSION_STARTED,
+                %departure_time,
+                remaining_secs = departure_time.time_span_from(&now).total_secs(),
+                notifications = pending.len(),
+                "Session started"
+            )
+        });
+        Ok(Self {
+            coach,
+            plan: plan.clone(),
+            departure_time,
+            started: now,
+            notifications: Notifications::new(pending, max_emitted),
+            muted: false,
+            paused: false,
+            snoozed_until: None,
+            snoozes: 0,
+            update_requested: false,
+            last_announced: None,
+            replay_requested: false,
+            announcement: None,
+            overdue: false,
+            span,
+        })
+    }
+
+    /// Follows `plan` from `now` on, announcing the new departure time.
+    pub fn replan(&mut self, plan: Plan, now: &Timestamp) -> EngineResult<()> {
+        self.notifications.pending = Pending::new(
+            plan.notifications(now, &self.coach)
+                .change_context(EngineError)?,
+        );
+        self.departure_time = plan.departure_time();
+        self.plan = plan;
+        self.announcement = Some(self.coach.departure_changed_message(&self.departure_time));
+        let _session = self.span.enter();
+        info!(
+            event = event::REPLAN,
+            departure_time = %self.departure_time,
+            remaining_secs = self.remaining_time(now).total_secs(),
+            notifications = self.notifications.pending.len(),
+            "Departure changed"
+        );
+        Ok(())
+    }
+
+    /// Pushes the rendezvous `span` later.
+    pub fn push(&mut self, span: TimeSpan, now: &Timestamp) -> EngineResult<()> {
+        let plan = Plan {
+            rendezvous_time: self.plan.rendezvous_time + span,
+            ..self.plan.clone()
+        };
+        self.replan(plan, now)
+    }
+
+    pub fn total_time(&self) -> TimeSpan {
+        self.departure_time.time_span_from(&self.started)
+    }
+
+    pub fn elapsed_time(&self, now: &Timestamp) -> TimeSpan {
+        now.time_span_from(&self.started)
+    }
+
+    pub fn remaining_time(&self, now: &Timestamp) -> TimeSpan {
+        self.departure_time.time_span_from(now)
+    }
+
+    /// How late it is past departure, when counting past it.
+    pub fn overdue_time(&self, now: &Timestamp) -> Option<TimeSpan> {
+        (self.overdue && *now > self.departure_time)
+            .then(|| now.time_span_from(&self.departure_time))
+    }
+
+    pub fn remaining_ratio(&self, now: &Timestamp) -> f64 {
+        let total_secs = self.total_time().total_secs() as f64;
+        let remaing_secs = self.remaining_time(now).total_secs() as f64;
+        remaing_secs / total_secs
+    }
+
+    /// Whether notifications are not spoken at `now`, being muted or snoozed.
+    pub fn is_silenced(&self, now: &Timestamp) -> bool {
+        self.muted || self.snoozed_until.is_some_and(|until| until > *now)
+    }
+
+    /// When a tick has something to do next, unless asked for: the time of the next
+    /// notification, or departure. `None` once past departure, counting overdue.
+    pub fn next_deadline(&self, now: &Timestamp) -> Option<Timestamp> {
+        let departure = (self.departure_time > *now).then_some(self.departure_time);
+        let next = self.notifications.pending.next().map(|n| n.time);
+        next.into_iter().chain(departure).min()
+    }
+
+    /// Speaks what was asked for since the last tick, and emits the notification due, if any, to
+    /// `sinks` and to `speaker` (unless silenced).
+    pub fn tick<S: Announcer>(
+        &mut self,
+        clock: &impl Clock,
+        sinks: &mut Dispatcher,
+        speaker: &mut S,
+    ) -> EngineResult<Tick> {
+        let now = clock.now().change_context(EngineError)?;
+        let _session = self.span.enter();
+        sinks.update(self.departure_time.time_span_from(&now));
+        if std::mem::take(&mut self.update_requested) {
+            // Asked for explicitly, so spoken even when muted
+            let remaining = self.remaining_time(&now);
+            speaker
+                .announce(
+                    &Utterance::countdown(self.coach.remaining_time_message(&remaining), remaining)
+                        .with_ssml(self.coach.remaining_time_ssml(&remaining))
+                        .with_pace(self.coach.remaining_time_pace(&remaining)),
+                )
+                .change_context(EngineError)?;
+        }
+        if let Some(message) = self.announcement.take() {
+            let remaining = self.remaining_time(&now);
+            let n = Notification {
+                time: now,
+                message,
+                urgency: Urgency::for_remaining(&remaining),
+            };
+            speaker
+                .announce(&Utterance::message(&n.message, n.urgency))
+                .change_context(EngineError)?;
+            self.notifications.emit(n);
+        }
+        if std::mem::take(&mut self.replay_requested)
+            && let Some(n) = &self.last_announced
+        {
+            speaker
+                .announce(&Utterance::message(&n.message, n.urgency))
+                .change_context(EngineError)?;
+        }
+        let over = self.notifications.pending.is_empty() && !self.overdue;
+        let mut emitted = None;
+        if self.paused {
+            // Dropped rather than postponed, they would be stale on resume
+            if let Some(n) = self.notifications.pending.pop_due(&now) {
+                debug!("Paused, skipping `{}`", n.message);
+            }
+        } else if let Some(n) = self.notifications.pending.pop_due(&now) {
+            self.notifications.emit(n.clone());
+            self.last_announced = Some(n.clone());
+            let remaining = self.departure_time.time_span_from(&n.time);
+            info!(
+                event = event::NOTIFICATION_EMITTED,
+                remaining_secs = remaining.total_secs(),
+                urgency = n.urgency.name(),
+                message = %n.message,
+                "Notification emitted"
+            );
+            let mut outcomes = sinks.emit(&n, remaining);
+            let announced = (!self.is_silenced(&now)).then(|| {
+                speaker.announce(
+                    &Utterance::countdown(&n.message, remaining)
+                        .with_ssml(self.coach.remaining_time_ssml(&remaining))
+                        .with_pace(self.coach.remaining_time_pace(&remaining)),
+                )
+            });
+            let speech = match &announced {
+                None => Outcome::Skipped,
+                Some(Ok(())) => Outcome::Delivered,
+                Some(Err(_)) => Outcome::Failed,
+            };
+            if announced.is_some() {
+                info!(
+                    event = event::SPEECH_COMPLETED,
+                    remaining_secs = remaining.total_secs(),
+                    outcome = speech.name(),
+                    "Speech completed"
+                );
+            }
+            outcomes.insert(0, ("speech".to_owned(), speech));
+            emitted = Some(Emitted {
+                notification: n,
+                remaining,
+                outcomes,
+                announced: announced
+                    .transpose()
+                    .change_context(EngineError)
+                    .map(|_| ()),
+            });
+        }
+        Ok(Tick { now, over, emitted })
+    }
+
+    /// Ends the session at `now`, returning the time remaining before departure: zero once
+    /// departed, else the session is abandoned.
+    pub fn finish(&self, now: &Timestamp) -> TimeSpan {
+        let remaining = self.remaining_time(now);
+        let _session = self.span.enter();
+        info!(
+            event = event::SESSION_FINISHED,
+            remaining_secs = remaining.total_secs(),
+            departed = remaining.is_zero(),
+            emitted = self.notifications.emitted.len(),
+            "Session finished"
+        );
+        remaining
+    }
+
+    /// Catches up after the process was stopped (e.g. suspended): the notifications gone by are
+    /// dropped, as they would be stale, and the remaining time is spoken instead. Returns how
+    /// many were dropped.
+    pub fn resync(&mut self, now: &Timestamp) -> usize {
+        let missed = self.notifications.pending.drop_before(now);
+        if missed > 0 {
+            self.update_requested = true;
+        }
+        missed
+    }
+
+    /// What is needed to resume the session.
+    pub fn state(&self) -> SessionState {
+        SessionState {
+            plan: self.plan.clone(),
+            emitted: self.notifications.emitted.iter().cloned().collect(),
+            snoozes: self.snoozes,
+            snoozed_until: self.snoozed_until,
+            muted: self.muted,
+        }
+    }
+
+    /// Takes up a session where `state` left it: the notifications gone by are not planned again,
+    /// and the remaining time is spoken instead.
+    pub fn restore(&mut self, state: SessionState) {
+        for n in state.emitted.into_iter().rev() {
+            self.notifications.emit(n);
+        }
+        self.snoozes = state.snoozes;
+        self.snoozed_until = state.snoozed_until;
+        self.muted = state.muted;
+        self.update_requested = !self.muted;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::feature::coach::DefaultItCoach;
+    use crate::feature::tts::SpeakerResult;
+
+    /// A clock moved forward by the test.
+    pub(crate) struct FakeClock(pub(crate) Cell<Timestamp>);
+
+    impl FakeClock {
+        pub(crate) fn advance(&self, span: TimeSpan) {
+            self.0.set(self.0.get() + span);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> TimeResult<Timestamp> {
+            Ok(self.0.get())
+        }
+    }
+
+    #[derive(Default)]
+    pub(crate) struct RecordingSpeaker {
+        pub(crate) spoken: Vec<String>,
+    }
+
+    impl Announcer for RecordingSpeaker {
+        fn announce(&mut self, utterance: &Utterance) -> SpeakerResult<()> {
+            self.spoken.push(utterance.text.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn notifications_emitted_is_a_ring_with_fixed_capacity() {
+        let mut notifications = Notifications::new(vec![], 5);
+
+        for i in 1..=10 {
+            notifications.emit(Notification {
+                time: Timestamp::now().unwrap(),
+                message: format!("{i}"),
+                urgency: Urgency::Low,
+            })
+        }
+
+        let actual: Vec<_> = notifications
+            .emitted
+            .into_iter()
+            .map(|m| m.message)
+            .collect();
+        assert_eq!(vec!["10", "9", "8", "7", "6"], actual);
+    }
+
+    #[test]
+    fn pending_fires_by_deadline_in_time_order() {
+        let at = |min| Notification {
+            time: Timestamp::new(2025, 10, 24, 19, min, 00).unwrap(),
+            message: format!("{min}"),
+            urgency: Urgency::Low,
+        };
+        let mut pending = Pending::new(vec![at(45), at(40), at(42), at(41)]);
+
+        assert_eq!(Some(&at(40)), pending.next());
+        let jittered = Timestamp::new(2025, 10, 24, 19, 40, 1).unwrap();
+        assert_eq!(Some(at(40)), pending.pop_due(&jittered));
+        assert_eq!(None, pending.pop_due(&jittered));
+        // Late, past two of them
+        let late = Timestamp::new(2025, 10, 24, 19, 42, 30).unwrap();
+        assert_eq!(Some(at(42)), pending.pop_due(&late));
+        assert_eq!(vec![&at(45)], pending.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn engine_finish_tells_whether_departed() {
+        let start = Timestamp::new(2025, 10, 24, 19, 40, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+
+        assert_eq!(TimeSpan::of_minutes(5), engine.finish(&start));
+        assert!(engine.finish(&plan.rendezvous_time).is_zero());
+    }
+
+    #[test]
+    fn engine_emits_the_notifications_as_the_clock_goes() {
+        let start = Timestamp::new(2025, 10, 24, 19, 40, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(start));
+        let mut engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+        let mut sinks = Dispatcher::default();
+        let mut speaker = RecordingSpeaker::default();
+
+        let mut ticks = Vec::new();
+        for _ in 0..=5 * 60 {
+            let tick = engine.tick(&clock, &mut sinks, &mut speaker).unwrap();
+            ticks.push(tick);
+            clock.advance(TimeSpan::of_seconds(1));
+        }
+
+        // At 19:40, then every minute from 19:41 to 19:45
+        let emitted = ticks.iter().filter(|tick| tick.emitted.is_some()).count();
+        assert_eq!(6, emitted);
+        assert_eq!(6, speaker.spoken.len());
+        assert_eq!(6, engine.notifications.emitted.len());
+        assert!(!ticks[0].over);
+        assert!(engine.notifications.pending.is_empty());
+    }
+
+    #[test]
+    fn engine_silenced_emits_without_speaking() {
+        let start = Timestamp::new(2025, 10, 24, 19, 44, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(start));
+        let mut engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+        engine.muted = true;
+        let mut speaker = RecordingSpeaker::default();
+
+        let tick = engine
+            .tick(&clock, &mut Dispatcher::default(), &mut speaker)
+            .unwrap();
+
+        let emitted = tick.emitted.unwrap();
+        assert_eq!(("speech".to_owned(), Outcome::Skipped), emitted.outcomes[0]);
+        assert!(speaker.spoken.is_empty());
+    }
+
+    #[test]
+    fn engine_next_deadline_is_the_next_notification_or_departure() {
+        let start = Timestamp::new(2025, 10, 24, 19, 40, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(start));
+        let mut engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+        let mut speaker = RecordingSpeaker::default();
+
+        assert_eq!(Some(start), engine.next_deadline(&start));
+        engine
+            .tick(&clock, &mut Dispatcher::default(), &mut speaker)
+            .unwrap();
+        let next = Timestamp::new(2025, 10, 24, 19, 41, 00).unwrap();
+        assert_eq!(Some(next), engine.next_deadline(&start));
+
+        engine.notifications.pending = Pending::default();
+        assert_eq!(Some(engine.departure_time), engine.next_deadline(&start));
+        engine.overdue = true;
+        let late = engine.departure_time + TimeSpan::of_minutes(1);
+        assert_eq!(None, engine.next_deadline(&late));
+    }
+}
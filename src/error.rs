@@ -1,12 +1,106 @@
 //! Top-level error types
 
+use std::process::ExitCode;
+
 use error_stack::Report;
 
-#[derive(Debug, thiserror::Error)]
-#[error("An application error has occured")]
-pub struct AppError;
+/// What went wrong, telling the exit code of the process and what the user can do about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AppError {
+    #[error("invalid rendezvous")]
+    InvalidRendezvous,
+    #[error("speech is not available")]
+    Speech,
+    #[error("terminal error")]
+    Terminal,
+    #[error("configuration error")]
+    Config,
+    #[error("cannot reach the session")]
+    Session,
+    #[error("cannot store the data")]
+    Storage,
+    #[error("cannot notify the sinks")]
+    Sink,
+    #[error("An application error has occured")]
+    Other,
+}
+
+impl AppError {
+    /// Exit code of the process, a distinct one for each error after `sysexits.h`.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::InvalidRendezvous => 65,
+            Self::Speech => 69,
+            Self::Storage => 73,
+            Self::Terminal => 74,
+            Self::Session => 75,
+            Self::Sink => 76,
+            Self::Config => 78,
+        }
+    }
+
+    /// What the user can do about it, when nothing more specific is suggested.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            Self::InvalidRendezvous => Some(
+                "give the rendezvous as HH:MM, YYYY-MM-DD HH:MM or RFC 3339, the trip as HH:MM",
+            ),
+            Self::Speech => Some(
+                "pick another backend with --speaker, or check the voices with \
+                 `rendezvous-coach voices`",
+            ),
+            Self::Terminal => Some("run it in a terminal, or pass --headless"),
+            Self::Config => {
+                Some("check the configuration file, printed by `rendezvous-coach config path`")
+            }
+            Self::Session => Some("start a session with --detach, or --control-socket"),
+            Self::Storage => Some("check the permissions of the data directory"),
+            Self::Sink => Some("check the options of the sinks, or leave them out"),
+            Self::Other => None,
+        }
+    }
+}
 
 pub type AppResult<T> = Result<T, Report<AppError>>;
 
 /// A suggestion displayed to the user
-pub struct Suggestion(pub &'static str);
\ No newline at end of file
+pub struct Suggestion(pub &'static str);
+
+/// Prints `report` with the hint of its error, unless something more specific is suggested
+/// already, and gives the exit code of the error.
+pub fn exit(report: Report<AppError>) -> ExitCode {
+    let error = *report.current_context();
+    let report = match error.hint() {
+        Some(hint) if !report.contains::<Suggestion>() => report.attach_opaque(Suggestion(hint)),
+        _ => report,
+    };
+    eprintln!("Error: {report:?}");
+    ExitCode::from(error.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_errors_exit_with_distinct_codes() {
+        let errors = [
+            AppError::InvalidRendezvous,
+            AppError::Speech,
+            AppError::Terminal,
+            AppError::Config,
+            AppError::Session,
+            AppError::Storage,
+            AppError::Sink,
+            AppError::Other,
+        ];
+
+        let mut codes: Vec<_> = errors.iter().map(|e| e.exit_code()).collect();
+        codes.sort();
+        codes.dedup();
+
+        assert_eq!(errors.len(), codes.len());
+        assert!(!codes.contains(&0));
+    }
+}
@@ -1,2 +1,19 @@
 pub mod coach;
-pub mod tts;
\ No newline at end of file
+#[cfg(feature = "cli")]
+pub mod config;
+#[cfg(feature = "control")]
+pub mod control;
+pub mod daemon;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod logs;
+pub mod plugin;
+pub mod preview;
+#[cfg(feature = "cli")]
+pub mod profile;
+pub mod sink;
+pub mod state;
+pub mod systemd;
+pub mod tts;
+#[cfg(feature = "tui")]
+pub mod tui;
\ No newline at end of file
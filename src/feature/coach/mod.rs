@@ -1,7 +1,57 @@
-use crate::time::TimeSpan;
+use crate::feature::tts::{Pace, escape_ssml};
+use crate::time::{TimeSpan, Timestamp};
+
+mod plugin;
+
+pub use plugin::PluginCoach;
 
 pub trait Coach {
+    /// Language of the messages, as an ISO 639-1 code (e.g. `it`)
+    fn language(&self) -> &str;
+
     fn remaining_time_message(&self, remaining_time: &TimeSpan) -> String;
+
+    /// Announces the new departure time, when the plan changes during the session.
+    fn departure_changed_message(&self, departure_time: &Timestamp) -> String;
+
+    /// Farewell, when the session is abandoned `remaining_time` before departure.
+    fn abandoned_message(&self, remaining_time: &TimeSpan) -> String;
+
+    /// The remaining time message as SSML markup, when it adds something to the plain text.
+    fn remaining_time_ssml(&self, _remaining_time: &TimeSpan) -> Option<String> {
+        None
+    }
+
+    /// How fast the remaining time message should be spoken.
+    fn remaining_time_pace(&self, _remaining_time: &TimeSpan) -> Pace {
+        Pace::Normal
+    }
+}
+
+impl<C: Coach + ?Sized> Coach for Box<C> {
+    fn language(&self) -> &str {
+        (**self).language()
+    }
+
+    fn remaining_time_message(&self, remaining_time: &TimeSpan) -> String {
+        (**self).remaining_time_message(remaining_time)
+    }
+
+    fn departure_changed_message(&self, departure_time: &Timestamp) -> String {
+        (**self).departure_changed_message(departure_time)
+    }
+
+    fn abandoned_message(&self, remaining_time: &TimeSpan) -> String {
+        (**self).abandoned_message(remaining_time)
+    }
+
+    fn remaining_time_ssml(&self, remaining_time: &TimeSpan) -> Option<String> {
+        (**self).remaining_time_ssml(remaining_time)
+    }
+
+    fn remaining_time_pace(&self, remaining_time: &TimeSpan) -> Pace {
+        (**self).remaining_time_pace(remaining_time)
+    }
 }
 
 pub struct DefaultItCoach;
@@ -19,36 +69,63 @@ impl DefaultItCoach {
             _ => None,
         }
     }
+
+    /// `time_span` in words, e.g. `1 ora e 5 minuti`; it must not be zero.
+    fn duration_words(&self, time_span: &TimeSpan) -> String {
+        let components = [
+            self.remaining_time_component(time_span.hours(), "ora", "ore"),
+            self.remaining_time_component(time_span.minutes(), "minuto", "minuti"),
+            self.remaining_time_component(time_span.seconds(), "secondo", "secondi"),
+        ];
+        let components: Vec<_> = components.iter().flatten().collect();
+        match components.len() {
+            3 => format!("{}, {} e {}", components[0], components[1], components[2]),
+            2 => format!("{} e {}", components[0], components[1]),
+            1 => components[0].to_string(),
+            _ => unreachable!(),
+        }
+    }
 }
 
 impl Coach for DefaultItCoach {
+    fn language(&self) -> &str {
+        "it"
+    }
+
     fn remaining_time_message(&self, remaining_time: &TimeSpan) -> String {
         if remaining_time == &TimeSpan::ZERO {
             "Ora di partire!".to_owned()
         } else {
-            let seconds = remaining_time.seconds();
-            let minutes = remaining_time.minutes();
-            let hours = remaining_time.hours();
-            let components = vec![
-                self.remaining_time_component(hours, "ora", "ore"),
-                self.remaining_time_component(minutes, "minuto", "minuti"),
-                self.remaining_time_component(seconds, "secondo", "secondi"),
-            ];
-            let components: Vec<_> = components.iter().flat_map(|c| c).collect();
-            let prefix = if seconds + minutes + hours == 1 {
-                "Manca"
-            } else {
-                "Mancano"
-            };
-            match components.len() {
-                3 => format!(
-                    "{prefix} {}, {} e {}",
-                    components[0], components[1], components[2]
-                ),
-                2 => format!("{prefix} {} e {}", components[0], components[1]),
-                1 => format!("{prefix} {}", components[0]),
-                _ => unreachable!(),
-            }
+            let units =
+                remaining_time.seconds() + remaining_time.minutes() + remaining_time.hours();
+            let prefix = if units == 1 { "Manca" } else { "Mancano" };
+            format!("{prefix} {}", self.duration_words(remaining_time))
+        }
+    }
+
+    fn departure_changed_message(&self, departure_time: &Timestamp) -> String {
+        format!("Nuovo orario di partenza: {}", departure_time.to_hh_mm())
+    }
+
+    fn abandoned_message(&self, remaining_time: &TimeSpan) -> String {
+        format!(
+            "Appuntamento abbandonato a {} dalla partenza",
+            self.duration_words(remaining_time)
+        )
+    }
+
+    fn remaining_time_ssml(&self, remaining_time: &TimeSpan) -> Option<String> {
+        remaining_time.is_zero().then(|| {
+            let message = escape_ssml(&self.remaining_time_message(remaining_time));
+            format!("<speak><emphasis level=\"strong\">{message}</emphasis></speak>")
+        })
+    }
+
+    fn remaining_time_pace(&self, remaining_time: &TimeSpan) -> Pace {
+        match remaining_time {
+            t if t.is_zero() => Pace::Slow,
+            t if *t >= TimeSpan::of_minutes(30) => Pace::Fast,
+            _ => Pace::Normal,
         }
     }
 }
@@ -62,6 +139,42 @@ mod tests {
         assert_eq!(expected_message, message);
     }
 
+    #[test]
+    fn remaining_time_ssml_emphasizes_the_departure_only() {
+        assert_eq!(
+            Some("<speak><emphasis level=\"strong\">Ora di partire!</emphasis></speak>".to_owned()),
+            DefaultItCoach.remaining_time_ssml(&TimeSpan::ZERO)
+        );
+        assert_eq!(
+            None,
+            DefaultItCoach.remaining_time_ssml(&TimeSpan::of_minutes(5))
+        );
+    }
+
+    #[test]
+    fn remaining_time_pace_slows_down_for_the_final_call() {
+        assert_eq!(
+            Pace::Slow,
+            DefaultItCoach.remaining_time_pace(&TimeSpan::ZERO)
+        );
+        assert_eq!(
+            Pace::Normal,
+            DefaultItCoach.remaining_time_pace(&TimeSpan::of_minutes(5))
+        );
+        assert_eq!(
+            Pace::Fast,
+            DefaultItCoach.remaining_time_pace(&TimeSpan::of_hours(1))
+        );
+    }
+
+    #[test]
+    fn abandoned_message_tells_how_long_before_departure() {
+        assert_eq!(
+            "Appuntamento abbandonato a 12 minuti e 30 secondi dalla partenza",
+            DefaultItCoach.abandoned_message(&TimeSpan::new(0, 12, 30))
+        );
+    }
+
     #[test]
     fn remaining_time_message_should_format_message_it_0s() {
         assert_message(TimeSpan::ZERO, "Ora di partire!");
@@ -0,0 +1,114 @@
+use std::sync::{Mutex, PoisonError};
+
+use tracing::warn;
+
+use super::{Coach, DefaultItCoach};
+use crate::feature::plugin::{Plugin, PluginResult, Role};
+use crate::time::{TimeSpan, Timestamp};
+
+/// Coach whose messages come from an external [plugin](crate::feature::plugin).
+///
+/// The plugin answers the `hello` with the `language` of its messages, then the requests
+/// `remaining_time_message` and `abandoned_message`, with the `remaining_seconds`, and
+/// `departure_changed_message`, with the `departure_time` in RFC 3339, with the `message`. When
+/// it fails, the message of the default coach is used instead.
+pub struct PluginCoach {
+    plugin: Mutex<Plugin>,
+    language: String,
+}
+
+impl PluginCoach {
+    /// Starts the plugin with `command_line`.
+    pub fn spawn(command_line: &str) -> PluginResult<Self> {
+        let plugin = Plugin::spawn(command_line, Role::Coach)?;
+        let language = plugin.hello()["language"]
+            .as_str()
+            .unwrap_or(DefaultItCoach.language())
+            .to_owned();
+        Ok(Self {
+            plugin: Mutex::new(plugin),
+            language,
+        })
+    }
+
+    /// The message answered to the request of `kind` with `fields`, or `fallback` when the plugin
+    /// fails.
+    fn message(
+        &self,
+        kind: &str,
+        mut fields: serde_json::Value,
+        fallback: impl FnOnce() -> String,
+    ) -> String {
+        fields["type"] = kind.into();
+        let answer = self
+            .plugin
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .request(fields);
+        match answer.map(|answer| answer["message"].as_str().map(str::to_owned)) {
+            Ok(Some(message)) => message,
+            Ok(None) => {
+                warn!("no message from the coach plugin for `{kind}`");
+                fallback()
+            }
+            Err(report) => {
+                warn!("cannot get the message from the coach plugin: {report:?}");
+                fallback()
+            }
+        }
+    }
+}
+
+impl Coach for PluginCoach {
+    fn language(&self) -> &str {
+        &self.language
+    }
+
+    fn remaining_time_message(&self, remaining_time: &TimeSpan) -> String {
+        self.message(
+            "remaining_time_message",
+            serde_json::json!({ "remaining_seconds": remaining_time.total_secs() }),
+            || DefaultItCoach.remaining_time_message(remaining_time),
+        )
+    }
+
+    fn departure_changed_message(&self, departure_time: &Timestamp) -> String {
+        self.message(
+            "departure_changed_message",
+            serde_json::json!({ "departure_time": departure_time.to_rfc3339() }),
+            || DefaultItCoach.departure_changed_message(departure_time),
+        )
+    }
+
+    fn abandoned_message(&self, remaining_time: &TimeSpan) -> String {
+        self.message(
+            "abandoned_message",
+            serde_json::json!({ "remaining_seconds": remaining_time.total_secs() }),
+            || DefaultItCoach.abandoned_message(remaining_time),
+        )
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::feature::plugin::tests::echo_plugin;
+
+    #[test]
+    fn plugin_coach_falls_back_without_a_message() {
+        let coach =
+            PluginCoach::spawn(&echo_plugin(r#"{"language": "en", "message": "Hurry"}"#)).unwrap();
+        assert_eq!("en", coach.language());
+        assert_eq!(
+            "Hurry",
+            coach.remaining_time_message(&TimeSpan::of_minutes(5))
+        );
+
+        let coach = PluginCoach::spawn(&echo_plugin("{}")).unwrap();
+        assert_eq!("it", coach.language());
+        assert_eq!(
+            "Mancano 5 minuti",
+            coach.remaining_time_message(&TimeSpan::of_minutes(5))
+        );
+    }
+}
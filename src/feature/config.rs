@@ -0,0 +1,441 @@
+//! Configuration file, `config.toml` in the configuration directory of the user.
+//!
+//! Each key is the long name of an option of the command line, e.g. `speaker = "say"`,
+//! `voice = "Alice (it)"`, `theme = "ocean"`, `desktop-notifications = true` or
+//! `theme-color = ["gauge.low=blue"]` for those given several times, and the `[keys]` table
+//! binds the actions of the terminal interface to other keys, e.g. `quit = "x"`. The options are
+//! turned into arguments, given before those of the command line so that these prevail, and
+//! the environment variables prevail over the file too, see [`Layers`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::parser::ValueSource;
+use error_stack::{Report, ResultExt};
+
+use crate::common::config_dir;
+use crate::error::Suggestion;
+
+/// Prefix of the environment variables giving the options, e.g. `RENDEZVOUS_VOICE` for `--voice`.
+pub const ENV_PREFIX: &str = "RENDEZVOUS_";
+
+/// The actions of the terminal interface that can be bound to other keys, with their own.
+const ACTIONS: [(&str, char); 11] = [
+    ("quit", 'q'),
+    ("mute", 'm'),
+    ("speak-now", 's'),
+    ("repeat", 'r'),
+    ("later", '+'),
+    ("next", 'n'),
+    ("log", 'l'),
+    ("count", 'c'),
+    ("trip", 't'),
+    ("rendezvous", 'e'),
+    ("pause", 'p'),
+];
+
+#[derive(Debug, thiserror::Error)]
+#[error("configuration error")]
+pub struct ConfigError;
+
+pub type ConfigResult<T> = Result<T, Report<ConfigError>>;
+
+/// Contents of the configuration file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    options: toml::Table,
+    /// Key of each action, by name
+    keys: Vec<(String, char)>,
+}
+
+impl Config {
+    /// `config.toml` in the configuration directory of the user.
+    pub fn default_path() -> PathBuf {
+        config_dir().join("config.toml")
+    }
+
+    /// The configuration file: `RENDEZVOUS_CONFIG`, or the default one.
+    pub fn path() -> PathBuf {
+        std::env::var_os("RENDEZVOUS_CONFIG").map_or_else(Self::default_path, Into::into)
+    }
+
+    /// Reads the configuration at `path`, empty when there is no file.
+    pub fn load(path: &Path) -> ConfigResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                Self::parse(&content).attach(format!("invalid configuration in {}", path.display()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(Report::new(e)
+                .change_context(ConfigError)
+                .attach(format!("cannot read {}", path.display()))),
+        }
+    }
+
+    /// A configuration with `options`, by long name, e.g. to save as a profile.
+    pub fn new(options: toml::Table) -> Self {
+        Self {
+            options,
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn parse(content: &str) -> ConfigResult<Self> {
+        let mut options: toml::Table = content.parse().change_context(ConfigError)?;
+        let keys = match options.remove("keys") {
+            None => Vec::new(),
+            Some(toml::Value::Table(keys)) => keys
+                .into_iter()
+                .map(|(action, key)| {
+                    let mut chars = key.as_str().unwrap_or_default().chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(key), None) => Ok((action, key)),
+                        _ => Err(Report::new(ConfigError)
+                            .attach(format!("the key of `{action}` is not a single character"))),
+                    }
+                })
+                .collect::<ConfigResult<_>>()?,
+            Some(_) => {
+                return Err(Report::new(ConfigError).attach("`keys` is not a table"));
+            }
+        };
+        for (name, value) in &options {
+            if let toml::Value::Table(_) | toml::Value::Datetime(_) = value {
+                return Err(Report::new(ConfigError)
+                    .attach(format!("`{name}` is not a value of an option")));
+            }
+        }
+        Ok(Self { options, keys })
+    }
+
+    /// The options as command line arguments (e.g. `--voice NAME`), but those for which `skip`
+    /// is true, e.g. given otherwise.
+    pub fn args(&self, skip: impl Fn(&str) -> bool) -> Vec<OsString> {
+        let mut args = Vec::new();
+        for (name, value) in self.options.iter().filter(|(name, _)| !skip(name)) {
+            let values = match value {
+                toml::Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                match value {
+                    toml::Value::Boolean(false) => {}
+                    toml::Value::Boolean(true) => args.push(format!("--{name}").into()),
+                    toml::Value::String(value) => {
+                        args.push(format!("--{name}").into());
+                        args.push(value.into());
+                    }
+                    value => {
+                        args.push(format!("--{name}").into());
+                        args.push(value.to_string().into());
+                    }
+                }
+            }
+        }
+        args
+    }
+
+    /// Key of each action of the terminal interface bound to another one, by name.
+    pub fn keys(&self) -> &[(String, char)] {
+        &self.keys
+    }
+
+    /// The keys bound to the actions, to the own keys of the actions.
+    pub fn key_bindings(&self) -> ConfigResult<HashMap<char, char>> {
+        self.keys
+            .iter()
+            .map(|(action, key)| {
+                let (_, own) = ACTIONS
+                    .iter()
+                    .find(|(name, _)| name == action)
+                    .ok_or(Report::new(ConfigError))
+                    .attach(format!("unknown action `{action}` in the keys"))
+                    .attach_opaque(Suggestion(
+                        "bind quit, mute, speak-now, repeat, later, next, log, count, trip, \
+                         rendezvous or pause",
+                    ))?;
+                Ok((*key, *own))
+            })
+            .collect()
+    }
+
+    /// This configuration, with the options and keys of `other` in place of its own.
+    pub fn overridden_by(mut self, other: Config) -> Self {
+        self.options.extend(other.options);
+        self.keys
+            .retain(|(action, _)| !other.keys.iter().any(|(other, _)| other == action));
+        self.keys.extend(other.keys);
+        self
+    }
+
+    /// The configuration as the content of a file.
+    pub fn to_toml(&self) -> String {
+        let mut content = self.options.to_string();
+        if !self.keys.is_empty() {
+            let keys: toml::Table = self
+                .keys
+                .iter()
+                .map(|(action, key)| (action.clone(), toml::Value::String(key.to_string())))
+                .collect();
+            content.push_str(&format!("\n[keys]\n{keys}"));
+        }
+        content
+    }
+}
+
+/// The arguments of the command line, after those from the environment variables and from the
+/// configuration file for the options not given on it: the command line prevails over the
+/// environment, which prevails over the file, also over the options conflicting with those given
+/// (e.g. `--headless` over `fullscreen = true`).
+pub struct Layers {
+    command: clap::Command,
+    args: Vec<OsString>,
+    /// Where the options come from, by long name, if not from the command line or the defaults
+    origins: BTreeMap<String, &'static str>,
+}
+
+impl Layers {
+    /// The arguments `args` of `command`, after those from the environment and `config`.
+    pub fn new(command: clap::Command, config: &Config, args: Vec<OsString>) -> Self {
+        // Only to tell the options given, the required ones may come from the other layers
+        let given = command
+            .clone()
+            .ignore_errors(true)
+            .try_get_matches_from(&args)
+            .unwrap_or_default();
+        let mut origins = BTreeMap::new();
+        for arg in command.get_arguments() {
+            let (Some(long), Some(source)) =
+                (arg.get_long(), given.value_source(arg.get_id().as_str()))
+            else {
+                continue;
+            };
+            match source {
+                ValueSource::CommandLine => origins.insert(long.to_owned(), "command line"),
+                ValueSource::EnvVariable => origins.insert(long.to_owned(), "environment"),
+                _ => None,
+            };
+        }
+        // Whether an option given is `long`, or conflicts with it
+        let overridden = |origins: &BTreeMap<String, &str>, long: &str| {
+            let Some(arg) = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(long))
+            else {
+                return false;
+            };
+            origins.contains_key(long)
+                || command.get_arguments().any(|other| {
+                    other
+                        .get_long()
+                        .is_some_and(|other| origins.contains_key(other))
+                        && (command.get_arg_conflicts_with(arg).contains(&other)
+                            || command.get_arg_conflicts_with(other).contains(&arg))
+                })
+        };
+        let mut layered = Vec::new();
+        for arg in command.get_arguments() {
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            let variable = format!("{ENV_PREFIX}{}", long.to_uppercase().replace('-', "_"));
+            let Some(value) = std::env::var_os(&variable) else {
+                continue;
+            };
+            if overridden(&origins, long) {
+                continue;
+            }
+            if arg.get_action().takes_values() {
+                layered.push(format!("--{long}").into());
+                layered.push(value);
+            } else if value == "1" || value == "true" {
+                layered.push(format!("--{long}").into());
+            }
+            origins.insert(long.to_owned(), "environment");
+        }
+        let from_file = config.args(|name| overridden(&origins, name));
+        for arg in &from_file {
+            if let Some(long) = arg.to_str().and_then(|arg| arg.strip_prefix("--")) {
+                origins.entry(long.to_owned()).or_insert("file");
+            }
+        }
+        // Before those of the command line, for a subcommand to come after them
+        let args = args
+            .iter()
+            .take(1)
+            .cloned()
+            .chain(layered)
+            .chain(from_file)
+            .chain(args.iter().skip(1).cloned())
+            .collect();
+        Self {
+            command,
+            args,
+            origins,
+        }
+    }
+
+    pub fn args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// The arguments parsed, exiting with the usage when they are not valid.
+    pub fn matches(&self) -> clap::ArgMatches {
+        self.command.clone().get_matches_from(&self.args)
+    }
+
+    /// The options in effect in `matches`, one per line as in a configuration file, commented
+    /// with where each comes from.
+    pub fn dump(&self, matches: &clap::ArgMatches) -> String {
+        let mut dump = String::new();
+        for arg in self.command.get_arguments() {
+            let (Some(long), Some(value)) = (arg.get_long(), option_value(arg, matches)) else {
+                continue;
+            };
+            let origin = self.origins.get(long).copied().unwrap_or("default");
+            dump.push_str(&format!("{long} = {value} # {origin}\n"));
+        }
+        dump
+    }
+}
+
+/// The value of the option `arg` in `matches`, as in a configuration file: a boolean for a flag,
+/// an array when given several times.
+pub fn option_value(arg: &clap::Arg, matches: &clap::ArgMatches) -> Option<toml::Value> {
+    let values: Vec<toml::Value> = matches
+        .get_raw(arg.get_id().as_str())?
+        .map(|value| {
+            let value = value.to_string_lossy();
+            match value.as_ref() {
+                "true" | "false" if !arg.get_action().takes_values() => {
+                    toml::Value::Boolean(value == "true")
+                }
+                _ => toml::Value::String(value.into_owned()),
+            }
+        })
+        .collect();
+    Some(match <[toml::Value; 1]>::try_from(values) {
+        Ok([value]) => value,
+        Err(values) => toml::Value::Array(values),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_turns_the_options_into_arguments() {
+        let config = Config::parse(
+            "speaker = \"say\"\n\
+             voice = \"Alice (it)\"\n\
+             desktop-notifications = true\n\
+             fullscreen = false\n\
+             volume = 0.8\n\
+             theme-color = [\"gauge.low=blue\", \"title=cyan\"]\n\
+             \n\
+             [keys]\n\
+             quit = \"x\"\n",
+        )
+        .unwrap();
+
+        let args = config.args(|name| name == "voice");
+
+        assert_eq!(
+            vec![
+                "--desktop-notifications",
+                "--speaker",
+                "say",
+                "--theme-color",
+                "gauge.low=blue",
+                "--theme-color",
+                "title=cyan",
+                "--volume",
+                "0.8",
+            ],
+            args
+        );
+        assert_eq!(&[("quit".to_owned(), 'x')], config.keys());
+    }
+
+    #[test]
+    fn config_rejects_what_is_not_an_option() {
+        assert!(Config::parse("[sinks]\nwebhook = \"https://example.org\"").is_err());
+        assert!(Config::parse("[keys]\nquit = \"esc\"").is_err());
+        assert!(Config::parse("voice = ").is_err());
+    }
+
+    #[test]
+    fn config_overridden_by_a_profile() {
+        let config =
+            Config::parse("speaker = \"say\"\nvoice = \"Alice\"\n[keys]\nquit = \"x\"").unwrap();
+        let profile =
+            Config::parse("trip = \"00:20\"\nvoice = \"Federica\"\n[keys]\nquit = \"z\"").unwrap();
+
+        let config = config.overridden_by(profile);
+
+        assert_eq!(
+            vec!["--speaker", "say", "--trip", "00:20", "--voice", "Federica"],
+            config.args(|_| false)
+        );
+        assert_eq!(&[("quit".to_owned(), 'z')], config.keys());
+        assert_eq!(config, Config::parse(&config.to_toml()).unwrap());
+    }
+
+    #[test]
+    fn config_binds_only_the_known_actions() {
+        let config = Config::parse("[keys]\nquit = \"x\"").unwrap();
+        assert_eq!(HashMap::from([('x', 'q')]), config.key_bindings().unwrap());
+
+        let config = Config::parse("[keys]\nfly = \"x\"").unwrap();
+        assert!(config.key_bindings().is_err());
+    }
+
+    #[test]
+    fn layers_put_the_command_line_over_the_file() {
+        let command = clap::Command::new("coach")
+            .arg(clap::Arg::new("voice").long("voice"))
+            .arg(clap::Arg::new("speaker").long("speaker"))
+            .arg(
+                clap::Arg::new("headless")
+                    .long("headless")
+                    .action(clap::ArgAction::SetTrue)
+                    .conflicts_with("fullscreen"),
+            )
+            .arg(
+                clap::Arg::new("fullscreen")
+                    .long("fullscreen")
+                    .action(clap::ArgAction::SetTrue),
+            );
+        let config =
+            Config::parse("voice = \"Alice\"\nspeaker = \"say\"\nfullscreen = true").unwrap();
+        let args = ["coach", "--voice", "Federica", "--headless"].map(OsString::from);
+
+        let layers = Layers::new(command, &config, args.to_vec());
+
+        assert_eq!(
+            [
+                "coach",
+                "--speaker",
+                "say",
+                "--voice",
+                "Federica",
+                "--headless"
+            ]
+            .map(OsString::from),
+            layers.args()
+        );
+        let dump = layers.dump(&layers.matches());
+        assert!(dump.contains("voice = \"Federica\" # command line\n"));
+        assert!(dump.contains("speaker = \"say\" # file\n"));
+        assert!(dump.contains("fullscreen = false # default\n"));
+    }
+
+    #[test]
+    fn config_without_a_file_is_empty() {
+        let path = std::env::temp_dir().join("rendezvous-coach-no-such-config.toml");
+
+        assert_eq!(Config::default(), Config::load(&path).unwrap());
+    }
+}
@@ -0,0 +1,74 @@
+//! `GET /status` answers with the session status as JSON; the other commands are `POST`ed to
+//! their own path, e.g. `/mute` or `/snooze/5m`, and answered with the resulting status.
+
+use error_stack::Report;
+use tiny_http::{Header, Method, Response, Server};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use super::{ControlCommand, ControlError, ControlRequest, ControlResult, ask};
+
+pub(super) fn listen(
+    address: &str,
+    requests: UnboundedSender<ControlRequest>,
+) -> ControlResult<()> {
+    let server = Server::http(address).map_err(|e| {
+        Report::new(ControlError).attach(format!("cannot listen on {address}: {e}"))
+    })?;
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let (status, body) = match command(request.method(), request.url()) {
+                Ok(command) => (200, ask(&requests, command)),
+                Err((status, error)) => (status, serde_json::json!({ "error": error })),
+            };
+            let response = Response::from_string(body.to_string())
+                .with_status_code(status)
+                .with_header(
+                    Header::from_bytes("Content-Type", "application/json").expect("valid header"),
+                );
+            if let Err(e) = request.respond(response) {
+                warn!("cannot answer an HTTP request: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The command requested, or the HTTP status and error to answer with.
+fn command(method: &Method, url: &str) -> Result<ControlCommand, (u16, String)> {
+    let path = url.split('?').next().unwrap_or_default();
+    let command: ControlCommand = path
+        .trim_matches('/')
+        .replace('/', " ")
+        .parse()
+        .map_err(|e| (404, e))?;
+    match (method, command) {
+        (Method::Get, ControlCommand::Status) | (Method::Post, _) => Ok(command),
+        _ => Err((405, format!("use POST for {path}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::TimeSpan;
+
+    #[test]
+    fn command_from_the_request_path() {
+        assert_eq!(Ok(ControlCommand::Status), command(&Method::Get, "/status"));
+        assert_eq!(
+            Ok(ControlCommand::Snooze(TimeSpan::of_minutes(5))),
+            command(&Method::Post, "/snooze/5m")
+        );
+        assert_eq!(
+            Ok(ControlCommand::Mute),
+            command(&Method::Post, "/mute?now")
+        );
+    }
+
+    #[test]
+    fn command_from_an_invalid_request() {
+        assert_eq!(404, command(&Method::Get, "/dance").unwrap_err().0);
+        assert_eq!(405, command(&Method::Get, "/quit").unwrap_err().0);
+    }
+}
@@ -0,0 +1,222 @@
+//! Control of a running session from other programs, through a Unix socket or HTTP (only HTTP on
+//! Windows).
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use error_stack::Report;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tracing::debug;
+
+use crate::time::TimeSpan;
+
+mod http;
+// The local socket of the platform: a Unix socket, none on Windows
+#[cfg(unix)]
+#[path = "unix.rs"]
+mod local;
+#[cfg(windows)]
+#[path = "windows.rs"]
+mod local;
+
+pub use local::request;
+
+/// How long a connection waits for the session to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+#[error("control error")]
+pub struct ControlError;
+
+pub type ControlResult<T> = Result<T, Report<ControlError>>;
+
+/// A command for the running session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    Status,
+    /// Do not speak for a while
+    Snooze(TimeSpan),
+    Mute,
+    Unmute,
+    /// Speak the remaining time now
+    SpeakNow,
+    /// Push the rendezvous later, by the given time or the configured increment
+    Push(Option<TimeSpan>),
+    Quit,
+}
+
+impl FromStr for ControlCommand {
+    type Err = String;
+
+    /// Parses a command line, e.g. `status` or `snooze 5m`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut words = input.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("status"), None) => ControlCommand::Status,
+            (Some("snooze"), Some(duration)) => ControlCommand::Snooze(parse_duration(duration)?),
+            (Some("mute"), None) => ControlCommand::Mute,
+            (Some("unmute"), None) => ControlCommand::Unmute,
+            (Some("speak-now"), None) => ControlCommand::SpeakNow,
+            (Some("push"), None) => ControlCommand::Push(None),
+            (Some("push"), Some(duration)) => ControlCommand::Push(Some(parse_duration(duration)?)),
+            (Some("quit"), None) => ControlCommand::Quit,
+            _ => {
+                return Err(format!(
+                    "invalid command `{}`, expected status, snooze DURATION, mute, unmute, \
+                     speak-now, push [DURATION] or quit",
+                    input.trim()
+                ));
+            }
+        };
+        match words.next() {
+            Some(extra) => Err(format!("unexpected `{extra}`")),
+            None => Ok(command),
+        }
+    }
+}
+
+/// Parses a duration like `90s`, `5m`, `1h` or `00:05`.
+fn parse_duration(input: &str) -> Result<TimeSpan, String> {
+    let invalid = || format!("invalid duration `{input}`, expected e.g. 5m, 90s, 1h or HH:MM");
+    let Some(unit) = input.chars().last() else {
+        return Err(invalid());
+    };
+    let amount = &input[..input.len() - unit.len_utf8()];
+    let span = match unit {
+        's' => amount.parse().map(TimeSpan::of_seconds),
+        'm' => amount.parse().map(TimeSpan::of_minutes),
+        'h' => amount.parse().map(TimeSpan::of_hours),
+        _ => return TimeSpan::parse(input).map_err(|_| invalid()),
+    };
+    span.map_err(|_| invalid())
+}
+
+/// A command received from a client, waiting for the session's answer.
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    reply: Sender<serde_json::Value>,
+}
+
+impl ControlRequest {
+    pub fn reply(self, response: serde_json::Value) {
+        // The client may have gone away in the meantime.
+        let _ = self.reply.send(response);
+    }
+}
+
+/// Hands the commands received from other programs over to the session.
+pub struct ControlServer {
+    sender: UnboundedSender<ControlRequest>,
+    requests: UnboundedReceiver<ControlRequest>,
+    /// Unix socket listened on, removed when dropped
+    socket: Option<PathBuf>,
+}
+
+impl ControlServer {
+    pub fn new() -> Self {
+        let (sender, requests) = unbounded_channel();
+        Self {
+            sender,
+            requests,
+            socket: None,
+        }
+    }
+
+    /// Accepts commands on the Unix socket at `path`.
+    pub fn listen_unix(&mut self, path: &Path) -> ControlResult<()> {
+        local::listen(path, self.sender.clone())?;
+        self.socket = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Serves the status and accepts commands over HTTP, at `address` (e.g. `127.0.0.1:8080`).
+    pub fn listen_http(&mut self, address: &str) -> ControlResult<()> {
+        http::listen(address, self.sender.clone())
+    }
+
+    /// The next command received, if any, without waiting.
+    pub fn try_recv(&mut self) -> Option<ControlRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    /// Waits for the next command. Never `None` while listening, as the server keeps a sender.
+    pub async fn recv(&mut self) -> Option<ControlRequest> {
+        self.requests.recv().await
+    }
+}
+
+impl Default for ControlServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        if let Some(socket) = &self.socket
+            && let Err(e) = std::fs::remove_file(socket)
+        {
+            debug!("cannot remove the control socket: {e}");
+        }
+    }
+}
+
+/// Sends `command` to the session, waiting for its answer.
+fn ask(requests: &UnboundedSender<ControlRequest>, command: ControlCommand) -> serde_json::Value {
+    let (reply, response) = mpsc::channel();
+    if requests.send(ControlRequest { command, reply }).is_err() {
+        return serde_json::json!({ "error": "the session is over" });
+    }
+    response
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| serde_json::json!({ "error": "no answer from the session" }))
+}
+
+/// Default location of the control socket, in the user's runtime directory.
+pub fn default_socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rendezvous-coach.sock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_command_parse() {
+        assert_eq!(Ok(ControlCommand::Status), "status".parse());
+        assert_eq!(Ok(ControlCommand::SpeakNow), " speak-now\n".parse());
+        assert_eq!(
+            Ok(ControlCommand::Snooze(TimeSpan::of_minutes(5))),
+            "snooze 5m".parse()
+        );
+        assert_eq!(
+            Ok(ControlCommand::Snooze(TimeSpan::of_minutes(10))),
+            "snooze 00:10".parse()
+        );
+        assert_eq!(Ok(ControlCommand::Push(None)), "push".parse());
+        assert_eq!(
+            Ok(ControlCommand::Push(Some(TimeSpan::of_minutes(30)))),
+            "push 30m".parse()
+        );
+    }
+
+    #[test]
+    fn control_command_parse_invalid() {
+        assert!("dance".parse::<ControlCommand>().is_err());
+        assert!("snooze".parse::<ControlCommand>().is_err());
+        assert!("snooze 5x".parse::<ControlCommand>().is_err());
+        assert!("mute now".parse::<ControlCommand>().is_err());
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(Ok(TimeSpan::of_seconds(90)), parse_duration("90s"));
+        assert_eq!(Ok(TimeSpan::of_hours(1)), parse_duration("1h"));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+}
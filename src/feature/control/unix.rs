@@ -0,0 +1,134 @@
+//! Commands sent to a Unix socket, one per line, each answered with a line of JSON.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::time::Duration;
+
+use error_stack::{Report, ResultExt};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{debug, warn};
+
+use super::{ControlError, ControlRequest, ControlResult, REPLY_TIMEOUT, ask};
+
+/// Sends a command line (e.g. `status`) to the session listening on `path`, returning its
+/// answer.
+pub fn request(path: &Path, command: &str) -> ControlResult<serde_json::Value> {
+    let connect = || -> std::io::Result<String> {
+        let mut stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT + Duration::from_secs(1)))?;
+        writeln!(stream, "{command}")?;
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response)?;
+        Ok(response)
+    };
+    let response = connect()
+        .change_context(ControlError)
+        .attach(format!("no session listening on {}", path.display()))?;
+    serde_json::from_str(&response)
+        .change_context(ControlError)
+        .attach("invalid answer from the session")
+}
+
+/// Listens on the socket at `path`, replacing a stale one left by a crashed session.
+pub(super) fn listen(path: &Path, requests: UnboundedSender<ControlRequest>) -> ControlResult<()> {
+    let listener = match UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(_) if path.exists() && UnixStream::connect(path).is_err() => {
+            debug!("Removing stale control socket {}", path.display());
+            std::fs::remove_file(path)
+                .change_context(ControlError)
+                .attach(format!("cannot remove stale socket {}", path.display()))?;
+            UnixListener::bind(path)
+                .change_context(ControlError)
+                .attach(format!("cannot listen on {}", path.display()))?
+        }
+        Err(e) => {
+            return Err(Report::new(e)
+                .change_context(ControlError)
+                .attach(format!("cannot listen on {}", path.display())));
+        }
+    };
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let requests = requests.clone();
+                    std::thread::spawn(move || serve(stream, requests));
+                }
+                Err(e) => warn!("cannot accept a control connection: {e}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Answers the commands of a client, until it disconnects.
+fn serve(stream: UnixStream, requests: UnboundedSender<ControlRequest>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            warn!("cannot answer a control connection: {e}");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match line.parse() {
+            Ok(command) => ask(&requests, command),
+            Err(e) => serde_json::json!({ "error": e }),
+        };
+        if writeln!(writer, "{response}").is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::feature::control::{ControlCommand, ControlServer};
+    use crate::time::TimeSpan;
+
+    #[test]
+    fn request_to_a_missing_session_fails() {
+        let path = std::env::temp_dir().join("rendezvous-control-missing");
+
+        assert!(request(&path, "status").is_err());
+    }
+
+    #[test]
+    fn control_server_hands_the_commands_over() {
+        let path = std::env::temp_dir().join(format!("rendezvous-control-{}", std::process::id()));
+        let mut server = ControlServer::new();
+        server.listen_unix(&path).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        writeln!(client, "snooze 5m").unwrap();
+        let request = loop {
+            if let Some(request) = server.try_recv() {
+                break request;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(
+            ControlCommand::Snooze(TimeSpan::of_minutes(5)),
+            request.command
+        );
+        request.reply(serde_json::json!({ "ok": true }));
+
+        let mut response = String::new();
+        BufReader::new(client).read_line(&mut response).unwrap();
+        assert_eq!("{\"ok\":true}\n", response);
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,22 @@
+//! No Unix sockets on Windows: the session is controlled over HTTP instead.
+
+use std::path::Path;
+
+use error_stack::Report;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{ControlError, ControlRequest, ControlResult};
+
+const SUGGESTION: &str = "Unix sockets are not available on Windows, use --http";
+
+pub fn request(path: &Path, _command: &str) -> ControlResult<serde_json::Value> {
+    Err(Report::new(ControlError)
+        .attach(format!("no session listening on {}", path.display()))
+        .attach(SUGGESTION))
+}
+
+pub(super) fn listen(path: &Path, _requests: UnboundedSender<ControlRequest>) -> ControlResult<()> {
+    Err(Report::new(ControlError)
+        .attach(format!("cannot listen on {}", path.display()))
+        .attach(SUGGESTION))
+}
@@ -0,0 +1,290 @@
+//! Coaching sessions started on their own for the rendezvous of a schedule.
+//!
+//! A schedule is a file with one rendezvous per line, as date, time, trip duration and an
+//! optional label (e.g. `2025-10-24 20:00 00:15 Dinner at Marco's`), or a directory of such
+//! files. Empty lines and lines starting with `#` are skipped.
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::{Duration, SystemTime};
+
+use error_stack::{Report, ResultExt};
+use tracing::{info, warn};
+
+use crate::time::{TimeSpan, Timestamp};
+
+#[derive(Debug, thiserror::Error)]
+#[error("daemon error")]
+pub struct DaemonError;
+
+pub type DaemonResult<T> = Result<T, Report<DaemonError>>;
+
+/// A rendezvous of the schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendezvous {
+    pub time: Timestamp,
+    pub trip: TimeSpan,
+    pub label: Option<String>,
+}
+
+impl Rendezvous {
+    pub fn departure_time(&self) -> Timestamp {
+        self.time - self.trip
+    }
+
+    /// Whether the session is to run at `now`, starting `lead` before departure.
+    pub fn is_due(&self, now: &Timestamp, lead: TimeSpan) -> bool {
+        let departure_time = self.departure_time();
+        &(departure_time - lead) <= now && now < &departure_time
+    }
+
+    fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{label} ({:?})", self.time),
+            None => format!("{:?}", self.time),
+        }
+    }
+}
+
+/// Parses the rendezvous of a schedule file.
+pub fn parse_schedule(content: &str) -> DaemonResult<Vec<Rendezvous>> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| {
+            let words: Vec<_> = line.split_whitespace().collect();
+            let [date, time, trip, label @ ..] = words.as_slice() else {
+                return Err(Report::new(DaemonError)
+                    .attach(format!("line {n}: expected YYYY-MM-DD HH:MM TRIP [LABEL]")));
+            };
+            Ok(Rendezvous {
+                time: Timestamp::parse(&format!("{date} {time}"))
+                    .change_context(DaemonError)
+                    .attach(format!("line {n}: invalid rendezvous"))?,
+                trip: TimeSpan::parse(trip)
+                    .change_context(DaemonError)
+                    .attach(format!("line {n}: invalid trip duration"))?,
+                label: (!label.is_empty()).then(|| label.join(" ")),
+            })
+        })
+        .collect()
+}
+
+/// Loads the schedule at `path`, a file or a directory of files.
+pub fn load_schedule(path: &Path) -> DaemonResult<Vec<Rendezvous>> {
+    let files = if path.is_dir() {
+        let mut files = std::fs::read_dir(path)
+            .change_context(DaemonError)
+            .attach(format!("cannot read {}", path.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.is_file()
+                    && !file
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let mut schedule = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)
+            .change_context(DaemonError)
+            .attach(format!("cannot read {}", file.display()))?;
+        schedule.extend(
+            parse_schedule(&content).attach(format!("invalid schedule {}", file.display()))?,
+        );
+    }
+    Ok(schedule)
+}
+
+/// A session started for a rendezvous, `None` when it is over.
+struct Session {
+    rendezvous: Rendezvous,
+    child: Option<Child>,
+}
+
+/// Starts a coaching session for each rendezvous of the schedule, `lead` before departure, as a
+/// process running `program` without the terminal interface.
+pub struct Daemon {
+    program: PathBuf,
+    options: Vec<String>,
+    lead: TimeSpan,
+    sessions: Vec<Session>,
+}
+
+impl Daemon {
+    /// The sessions run `program` with `options`, besides the rendezvous and trip.
+    pub fn new(program: PathBuf, options: Vec<String>, lead: TimeSpan) -> Self {
+        Self {
+            program,
+            options,
+            lead,
+            sessions: Vec::new(),
+        }
+    }
+
+    /// Starts the sessions due at `now`, and stops those of the rendezvous no longer in
+    /// `schedule`.
+    pub fn update(&mut self, schedule: &[Rendezvous], now: &Timestamp) {
+        for session in &mut self.sessions {
+            let Some(child) = &mut session.child else {
+                continue;
+            };
+            let name = session.rendezvous.name();
+            if !schedule.contains(&session.rendezvous) {
+                info!("Stopping the session of {name}, no longer scheduled");
+                if let Err(e) = child.kill().and_then(|_| child.wait()) {
+                    warn!("cannot stop the session of {name}: {e}");
+                }
+                session.child = None;
+                continue;
+            }
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => {
+                    info!("Session of {name} over");
+                    session.child = None;
+                }
+                Ok(Some(status)) => {
+                    warn!("Session of {name} failed: {status}");
+                    session.child = None;
+                }
+                Ok(None) => {}
+                Err(e) => warn!("cannot check the session of {name}: {e}"),
+            }
+        }
+        // Past rendezvous are not started again
+        self.sessions
+            .retain(|s| s.child.is_some() || &s.rendezvous.departure_time() > now);
+
+        for rendezvous in schedule {
+            if !rendezvous.is_due(now, self.lead)
+                || self.sessions.iter().any(|s| &s.rendezvous == rendezvous)
+            {
+                continue;
+            }
+            let name = rendezvous.name();
+            info!("Starting the session of {name}");
+            let child = self
+                .command(rendezvous)
+                .spawn()
+                .inspect_err(|e| warn!("cannot start the session of {name}: {e}"))
+                .ok();
+            self.sessions.push(Session {
+                rendezvous: rendezvous.clone(),
+                child,
+            });
+        }
+    }
+
+    /// Runs the sessions of the rendezvous in the schedule at `path`, reading it again whenever it
+    /// changes, a broken change keeping the previous schedule.
+    pub fn watch(&mut self, path: &Path) -> DaemonResult<()> {
+        let mut loaded = last_modified(path);
+        let mut schedule = load_schedule(path)?;
+        info!("{} rendezvous scheduled", schedule.len());
+        loop {
+            let modified = last_modified(path);
+            if modified != loaded {
+                loaded = modified;
+                match load_schedule(path) {
+                    Ok(reloaded) => {
+                        info!("{} rendezvous scheduled", reloaded.len());
+                        schedule = reloaded;
+                    }
+                    Err(report) => warn!("keeping the previous schedule: {report:?}"),
+                }
+            }
+            self.update(&schedule, &Timestamp::now().change_context(DaemonError)?);
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    fn command(&self, rendezvous: &Rendezvous) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(["--output", "json", "--rendezvous"])
+            .arg(rendezvous.time.to_rfc3339())
+            .arg("--trip")
+            .arg(rendezvous.trip.to_string())
+            .args(&self.options);
+        command
+    }
+}
+
+/// When the file at `path`, or any file in the directory, was last changed.
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let entries = std::fs::read_dir(path).into_iter().flatten().flatten();
+    entries
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .chain([modified])
+        .max()
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        for child in self.sessions.iter_mut().filter_map(|s| s.child.as_mut()) {
+            let _ = child.kill().and_then(|_| child.wait());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_schedule_lines() {
+        let content = "# Next week\n\
+                       2025-10-24 20:00 00:15 Dinner at Marco's\n\
+                       \n\
+                       2025-10-27 08:00 00:20\n";
+
+        let schedule = parse_schedule(content).unwrap();
+
+        assert_eq!(
+            vec![
+                Rendezvous {
+                    time: Timestamp::new(2025, 10, 24, 20, 0, 0).unwrap(),
+                    trip: TimeSpan::of_minutes(15),
+                    label: Some("Dinner at Marco's".to_owned()),
+                },
+                Rendezvous {
+                    time: Timestamp::new(2025, 10, 27, 8, 0, 0).unwrap(),
+                    trip: TimeSpan::of_minutes(20),
+                    label: None,
+                },
+            ],
+            schedule
+        );
+    }
+
+    #[test]
+    fn parse_schedule_invalid_lines() {
+        assert!(parse_schedule("2025-10-24 20:00").is_err());
+        assert!(parse_schedule("2025-10-24 25:00 00:15").is_err());
+        assert!(parse_schedule("2025-10-24 20:00 quarter").is_err());
+    }
+
+    #[test]
+    fn rendezvous_is_due_from_the_lead_to_departure() {
+        let rendezvous = Rendezvous {
+            time: Timestamp::new(2025, 10, 24, 20, 0, 0).unwrap(),
+            trip: TimeSpan::of_minutes(15),
+            label: None,
+        };
+        let lead = TimeSpan::of_hours(1);
+
+        let at = |hour, min| Timestamp::new(2025, 10, 24, hour, min, 0).unwrap();
+        assert!(!rendezvous.is_due(&at(18, 44), lead));
+        assert!(rendezvous.is_due(&at(18, 45), lead));
+        assert!(rendezvous.is_due(&at(19, 44), lead));
+        assert!(!rendezvous.is_due(&at(19, 45), lead));
+    }
+}
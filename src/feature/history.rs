@@ -0,0 +1,623 @@
+//! History of the coaching sessions, in a SQLite database in the data directory of the user.
+//!
+//! Each session is stored with its plan, when it started and ended and how, along with the
+//! notifications emitted, as a basis for reviewing and calibrating the trip estimates.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, ResultExt};
+use rusqlite::{Connection, params};
+
+use crate::common::{csv_row, data_dir};
+use crate::plan::{Notification, Plan, Urgency};
+use crate::time::{TimeSpan, Timestamp};
+
+#[derive(Debug, thiserror::Error)]
+#[error("history error")]
+pub struct HistoryError;
+
+pub type HistoryResult<T> = Result<T, Report<HistoryError>>;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY,
+    rendezvous_time TEXT NOT NULL,
+    trip_secs INTEGER NOT NULL,
+    started TEXT NOT NULL,
+    ended TEXT,
+    end_reason TEXT,
+    snoozes INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS notifications (
+    session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    time TEXT NOT NULL,
+    remaining_secs INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    urgency TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS notifications_by_session ON notifications(session_id);
+";
+
+/// Identifies a session in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionId(i64);
+
+/// How a session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEnd {
+    /// The countdown reached zero
+    Departed,
+    /// Quit before departure, at the time it ended
+    Abandoned,
+}
+
+impl SessionEnd {
+    /// Lowercase name, e.g. `departed`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionEnd::Departed => "departed",
+            SessionEnd::Abandoned => "abandoned",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "departed" => Some(SessionEnd::Departed),
+            // As stored by the earlier versions
+            "abandoned" | "quit" => Some(SessionEnd::Abandoned),
+            _ => None,
+        }
+    }
+}
+
+/// A session of the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub id: SessionId,
+    pub rendezvous_time: Timestamp,
+    pub trip_duration: TimeSpan,
+    pub started: Timestamp,
+    /// When the session ended, `None` when still running or interrupted
+    pub ended: Option<Timestamp>,
+    pub end: Option<SessionEnd>,
+    /// How many times the notifications were snoozed
+    pub snoozes: u32,
+}
+
+impl Session {
+    pub fn departure_time(&self) -> Timestamp {
+        self.rendezvous_time - self.trip_duration
+    }
+
+    /// How long before departure the session started.
+    pub fn lead(&self) -> TimeSpan {
+        self.departure_time().time_span_from(&self.started)
+    }
+}
+
+/// A notification emitted in a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationRecord {
+    pub notification: Notification,
+    pub remaining: TimeSpan,
+}
+
+pub struct History {
+    connection: Connection,
+}
+
+impl History {
+    /// `history.sqlite3` in the data directory of the user.
+    pub fn default_path() -> PathBuf {
+        data_dir().join("history.sqlite3")
+    }
+
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open(path: &Path) -> HistoryResult<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .change_context(HistoryError)
+                .attach(format!("cannot create {}", dir.display()))?;
+        }
+        let connection = Connection::open(path)
+            .change_context(HistoryError)
+            .attach(format!("cannot open {}", path.display()))?;
+        Self::with_connection(connection)
+    }
+
+    /// Keeps the history in memory only, e.g. for tests.
+    pub fn in_memory() -> HistoryResult<Self> {
+        Self::with_connection(Connection::open_in_memory().change_context(HistoryError)?)
+    }
+
+    fn with_connection(connection: Connection) -> HistoryResult<Self> {
+        connection
+            .execute_batch(SCHEMA)
+            .change_context(HistoryError)
+            .attach("cannot create the tables")?;
+        Ok(Self { connection })
+    }
+
+    /// Stores a session of `plan` starting at `started`.
+    pub fn start(&self, plan: &Plan, started: &Timestamp) -> HistoryResult<SessionId> {
+        self.connection
+            .execute(
+                "INSERT INTO sessions (rendezvous_time, trip_secs, started) VALUES (?1, ?2, ?3)",
+                params![
+                    plan.rendezvous_time.to_rfc3339(),
+                    plan.trip_duration.total_secs(),
+                    started.to_rfc3339()
+                ],
+            )
+            .change_context(HistoryError)
+            .attach("cannot store the session")?;
+        Ok(SessionId(self.connection.last_insert_rowid()))
+    }
+
+    pub fn record_notification(
+        &self,
+        session: SessionId,
+        notification: &Notification,
+        remaining: TimeSpan,
+    ) -> HistoryResult<()> {
+        self.connection
+            .execute(
+                "INSERT INTO notifications (session_id, time, remaining_secs, message, urgency)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    session.0,
+                    notification.time.to_rfc3339(),
+                    remaining.total_secs(),
+                    notification.message,
+                    notification.urgency.name()
+                ],
+            )
+            .change_context(HistoryError)
+            .attach("cannot store the notification")?;
+        Ok(())
+    }
+
+    pub fn record_snooze(&self, session: SessionId) -> HistoryResult<()> {
+        self.connection
+            .execute(
+                "UPDATE sessions SET snoozes = snoozes + 1 WHERE id = ?1",
+                params![session.0],
+            )
+            .change_context(HistoryError)
+            .attach("cannot store the snooze")?;
+        Ok(())
+    }
+
+    pub fn finish(
+        &self,
+        session: SessionId,
+        ended: &Timestamp,
+        end: SessionEnd,
+    ) -> HistoryResult<()> {
+        self.connection
+            .execute(
+                "UPDATE sessions SET ended = ?2, end_reason = ?3 WHERE id = ?1",
+                params![session.0, ended.to_rfc3339(), end.name()],
+            )
+            .change_context(HistoryError)
+            .attach("cannot store the end of the session")?;
+        Ok(())
+    }
+
+    /// Sessions started from `since`, or all of them, oldest first.
+    pub fn sessions(&self, since: Option<&Timestamp>) -> HistoryResult<Vec<Session>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT id, rendezvous_time, trip_secs, started, ended, end_reason, snoozes
+                 FROM sessions ORDER BY id",
+            )
+            .change_context(HistoryError)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, u32>(6)?,
+                ))
+            })
+            .change_context(HistoryError)?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            let (id, rendezvous_time, trip_secs, started, ended, end, snoozes) =
+                row.change_context(HistoryError)?;
+            let session = Session {
+                id: SessionId(id),
+                rendezvous_time: timestamp(&rendezvous_time)?,
+                trip_duration: TimeSpan::of_seconds(trip_secs),
+                started: timestamp(&started)?,
+                ended: ended.as_deref().map(timestamp).transpose()?,
+                end: end.as_deref().and_then(SessionEnd::from_name),
+                snoozes,
+            };
+            if since.is_none_or(|since| &session.started >= since) {
+                sessions.push(session);
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// Notifications emitted in `session`, in order.
+    pub fn notifications(&self, session: SessionId) -> HistoryResult<Vec<NotificationRecord>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT time, remaining_secs, message, urgency FROM notifications
+                 WHERE session_id = ?1 ORDER BY rowid",
+            )
+            .change_context(HistoryError)?;
+        let rows = statement
+            .query_map(params![session.0], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .change_context(HistoryError)?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (time, remaining_secs, message, urgency) = row.change_context(HistoryError)?;
+            let remaining = TimeSpan::of_seconds(remaining_secs);
+            records.push(NotificationRecord {
+                notification: Notification {
+                    time: timestamp(&time)?,
+                    message,
+                    urgency: Urgency::from_name(&urgency)
+                        .unwrap_or_else(|| Urgency::for_remaining(&remaining)),
+                },
+                remaining,
+            });
+        }
+        Ok(records)
+    }
+}
+
+/// Columns of the CSV export
+const CSV_HEADER: &str = "session,rendezvous_time,trip_seconds,started,ended,end,snoozes,\
+                          notification_time,remaining_seconds,message,urgency";
+
+impl History {
+    /// Writes the sessions started from `since` as a JSON array, each with its notifications.
+    pub fn export_json(
+        &self,
+        since: Option<&Timestamp>,
+        out: &mut impl Write,
+    ) -> HistoryResult<()> {
+        let mut sessions = Vec::new();
+        for session in self.sessions(since)? {
+            let notifications = self
+                .notifications(session.id)?
+                .iter()
+                .map(|record| {
+                    serde_json::json!({
+                        "time": record.notification.time.to_rfc3339(),
+                        "remaining_seconds": record.remaining.total_secs(),
+                        "message": record.notification.message,
+                        "urgency": record.notification.urgency.name(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            sessions.push(serde_json::json!({
+                "id": session.id.0,
+                "rendezvous_time": session.rendezvous_time.to_rfc3339(),
+                "trip_seconds": session.trip_duration.total_secs(),
+                "started": session.started.to_rfc3339(),
+                "ended": session.ended.map(|ended| ended.to_rfc3339()),
+                "end": session.end.map(|end| end.name()),
+                "snoozes": session.snoozes,
+                "notifications": notifications,
+            }));
+        }
+        serde_json::to_writer_pretty(&mut *out, &sessions)
+            .change_context(HistoryError)
+            .and_then(|_| writeln!(out).change_context(HistoryError))
+            .attach("cannot write the export")
+    }
+
+    /// Writes the sessions started from `since` as CSV, one row per notification along with its
+    /// session (and one row for a session without notifications).
+    pub fn export_csv(&self, since: Option<&Timestamp>, out: &mut impl Write) -> HistoryResult<()> {
+        let mut rows = vec![CSV_HEADER.to_owned()];
+        for session in self.sessions(since)? {
+            let columns = [
+                session.id.0.to_string(),
+                session.rendezvous_time.to_rfc3339(),
+                session.trip_duration.total_secs().to_string(),
+                session.started.to_rfc3339(),
+                session
+                    .ended
+                    .map(|ended| ended.to_rfc3339())
+                    .unwrap_or_default(),
+                session
+                    .end
+                    .map(|end| end.name())
+                    .unwrap_or_default()
+                    .to_owned(),
+                session.snoozes.to_string(),
+            ];
+            let notifications = self.notifications(session.id)?;
+            if notifications.is_empty() {
+                rows.push(csv_row(
+                    columns.iter().map(String::as_str).chain(["", "", "", ""]),
+                ));
+            }
+            for record in notifications {
+                let notification = [
+                    record.notification.time.to_rfc3339(),
+                    record.remaining.total_secs().to_string(),
+                    record.notification.message,
+                    record.notification.urgency.name().to_owned(),
+                ];
+                rows.push(csv_row(
+                    columns.iter().chain(&notification).map(String::as_str),
+                ));
+            }
+        }
+        rows.iter()
+            .try_for_each(|row| writeln!(out, "{row}"))
+            .change_context(HistoryError)
+            .attach("cannot write the export")
+    }
+}
+
+/// Summary of the sessions of the history, to calibrate the trip estimates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    pub sessions: usize,
+    /// Sessions whose countdown reached departure
+    pub departed: usize,
+    /// Sessions abandoned before departure
+    pub abandoned: usize,
+    /// Average snoozes per session
+    pub average_snoozes: f64,
+    /// Median time before departure the sessions started
+    pub typical_lead: Option<TimeSpan>,
+    /// Median time before departure the abandoned sessions were abandoned
+    pub typical_abandoned_before: Option<TimeSpan>,
+}
+
+impl Statistics {
+    pub fn of(sessions: &[Session]) -> Self {
+        let count = |end| sessions.iter().filter(|s| s.end == Some(end)).count();
+        let snoozes: u32 = sessions.iter().map(|s| s.snoozes).sum();
+        let abandoned_before = sessions
+            .iter()
+            .filter(|s| s.end == Some(SessionEnd::Abandoned))
+            .filter_map(|s| {
+                s.ended
+                    .map(|ended| s.departure_time().time_span_from(&ended))
+            })
+            .collect();
+        Self {
+            sessions: sessions.len(),
+            departed: count(SessionEnd::Departed),
+            abandoned: count(SessionEnd::Abandoned),
+            average_snoozes: if sessions.is_empty() {
+                0.0
+            } else {
+                snoozes as f64 / sessions.len() as f64
+            },
+            typical_lead: median(sessions.iter().map(Session::lead).collect()),
+            typical_abandoned_before: median(abandoned_before),
+        }
+    }
+}
+
+/// The statistics as printed by the stats command, one per line.
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.sessions == 0 {
+            return writeln!(f, "No sessions stored yet");
+        }
+        let share = |count: usize| count * 100 / self.sessions;
+        writeln!(f, "Sessions: {}", self.sessions)?;
+        writeln!(
+            f,
+            "Reached departure: {} ({}%)",
+            self.departed,
+            share(self.departed)
+        )?;
+        write!(
+            f,
+            "Abandoned: {} ({}%)",
+            self.abandoned,
+            share(self.abandoned)
+        )?;
+        match self.typical_abandoned_before {
+            Some(before) => writeln!(f, ", typically {before} before departure")?,
+            None => writeln!(f)?,
+        }
+        writeln!(f, "Snoozes per session: {:.1}", self.average_snoozes)?;
+        if let Some(lead) = self.typical_lead {
+            writeln!(f, "Typical start: {lead} before departure")?;
+        }
+        Ok(())
+    }
+}
+
+fn median(mut spans: Vec<TimeSpan>) -> Option<TimeSpan> {
+    spans.sort();
+    spans.get(spans.len() / 2).copied()
+}
+
+fn timestamp(value: &str) -> HistoryResult<Timestamp> {
+    Timestamp::parse(value)
+        .change_context(HistoryError)
+        .attach(format!("invalid timestamp `{value}` in the history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> Plan {
+        Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 0, 0).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        }
+    }
+
+    #[test]
+    fn history_stores_the_sessions() {
+        let history = History::in_memory().unwrap();
+        let started = Timestamp::new(2025, 10, 24, 19, 0, 0).unwrap();
+        let notification = Notification {
+            time: Timestamp::new(2025, 10, 24, 19, 40, 0).unwrap(),
+            message: "Mancano 5 minuti".to_owned(),
+            urgency: Urgency::Medium,
+        };
+
+        let session = history.start(&plan(), &started).unwrap();
+        history
+            .record_notification(session, &notification, TimeSpan::of_minutes(5))
+            .unwrap();
+        history.record_snooze(session).unwrap();
+        let ended = Timestamp::new(2025, 10, 24, 19, 45, 0).unwrap();
+        history
+            .finish(session, &ended, SessionEnd::Departed)
+            .unwrap();
+
+        let sessions = history.sessions(None).unwrap();
+        assert_eq!(
+            vec![Session {
+                id: session,
+                rendezvous_time: plan().rendezvous_time,
+                trip_duration: TimeSpan::of_minutes(15),
+                started,
+                ended: Some(ended),
+                end: Some(SessionEnd::Departed),
+                snoozes: 1,
+            }],
+            sessions
+        );
+        assert_eq!(TimeSpan::of_minutes(45), sessions[0].lead());
+        assert_eq!(
+            vec![NotificationRecord {
+                notification,
+                remaining: TimeSpan::of_minutes(5),
+            }],
+            history.notifications(session).unwrap()
+        );
+    }
+
+    #[test]
+    fn history_sessions_since() {
+        let history = History::in_memory().unwrap();
+        let at = |day| Timestamp::new(2025, 10, day, 19, 0, 0).unwrap();
+        history.start(&plan(), &at(20)).unwrap();
+        let recent = history.start(&plan(), &at(24)).unwrap();
+
+        let sessions = history.sessions(Some(&at(22))).unwrap();
+
+        assert_eq!(
+            vec![recent],
+            sessions.iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+        assert_eq!(None, sessions[0].end);
+    }
+
+    #[test]
+    fn history_export_csv() {
+        let history = History::in_memory().unwrap();
+        let started = Timestamp::new(2025, 10, 24, 19, 0, 0).unwrap();
+        let notification = Notification {
+            time: Timestamp::new(2025, 10, 24, 19, 40, 0).unwrap(),
+            message: "Mancano 5 minuti, \"parti\"".to_owned(),
+            urgency: Urgency::Medium,
+        };
+        let session = history.start(&plan(), &started).unwrap();
+        history
+            .record_notification(session, &notification, TimeSpan::of_minutes(5))
+            .unwrap();
+        history.start(&plan(), &started).unwrap();
+
+        let mut out = Vec::new();
+        history.export_csv(None, &mut out).unwrap();
+
+        let csv = String::from_utf8(out).unwrap();
+        let rows: Vec<_> = csv.lines().collect();
+        assert_eq!(3, rows.len());
+        assert_eq!(
+            format!(
+                "1,{},900,{},,,0,{},300,\"Mancano 5 minuti, \"\"parti\"\"\",medium",
+                plan().rendezvous_time.to_rfc3339(),
+                started.to_rfc3339(),
+                notification.time.to_rfc3339()
+            ),
+            rows[1]
+        );
+        assert!(rows[2].ends_with(",0,,,,"));
+    }
+
+    #[test]
+    fn history_export_json() {
+        let history = History::in_memory().unwrap();
+        let started = Timestamp::new(2025, 10, 24, 19, 0, 0).unwrap();
+        let session = history.start(&plan(), &started).unwrap();
+        let ended = Timestamp::new(2025, 10, 24, 19, 30, 0).unwrap();
+        history
+            .finish(session, &ended, SessionEnd::Abandoned)
+            .unwrap();
+
+        let mut out = Vec::new();
+        history.export_json(None, &mut out).unwrap();
+
+        let sessions: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!("abandoned", sessions[0]["end"]);
+        assert_eq!(900, sessions[0]["trip_seconds"]);
+        assert_eq!(0, sessions[0]["notifications"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn statistics_of_the_sessions() {
+        let history = History::in_memory().unwrap();
+        let at = |hour, min| Timestamp::new(2025, 10, 24, hour, min, 0).unwrap();
+        for (started, ended, end, snoozes) in [
+            (at(18, 45), at(19, 45), SessionEnd::Departed, 0),
+            (at(19, 15), at(19, 45), SessionEnd::Departed, 1),
+            (at(19, 0), at(19, 35), SessionEnd::Abandoned, 2),
+        ] {
+            let session = history.start(&plan(), &started).unwrap();
+            for _ in 0..snoozes {
+                history.record_snooze(session).unwrap();
+            }
+            history.finish(session, &ended, end).unwrap();
+        }
+
+        let statistics = Statistics::of(&history.sessions(None).unwrap());
+
+        assert_eq!(
+            Statistics {
+                sessions: 3,
+                departed: 2,
+                abandoned: 1,
+                average_snoozes: 1.0,
+                typical_lead: Some(TimeSpan::of_minutes(45)),
+                typical_abandoned_before: Some(TimeSpan::of_minutes(10)),
+            },
+            statistics
+        );
+        let lines: Vec<_> = statistics.to_string().lines().map(str::to_owned).collect();
+        assert_eq!("Reached departure: 2 (66%)", lines[1]);
+        assert_eq!(
+            "Abandoned: 1 (33%), typically 00:10:00 before departure",
+            lines[2]
+        );
+        assert_eq!("Typical start: 00:45:00 before departure", lines[4]);
+        assert_eq!(None, Statistics::of(&[]).typical_lead);
+        assert_eq!("No sessions stored yet\n", Statistics::of(&[]).to_string());
+    }
+}
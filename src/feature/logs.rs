@@ -0,0 +1,113 @@
+//! The last log events, kept in memory to diagnose problems from the terminal interface without
+//! hunting for a log file (e.g. speech failures, sink retries, changes of plan).
+
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write};
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+use crate::time::Timestamp;
+
+/// A log event, on one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub level: Level,
+    /// Time, message and fields, e.g. `17:40:02 cannot deliver sink=mqtt`
+    pub text: String,
+}
+
+/// Tracing layer keeping the last `capacity` log events.
+#[derive(Debug, Clone)]
+pub struct RecentLogs {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+    capacity: usize,
+}
+
+impl RecentLogs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// The events kept, latest first.
+    pub fn lines(&self) -> Vec<LogLine> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RecentLogs {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = TextVisitor::default();
+        event.record(&mut visitor);
+        let time = Timestamp::now()
+            .map(|now| now.to_hh_mm_ss())
+            .unwrap_or_default();
+        let text = format!("{time} {}{}", visitor.message, visitor.fields);
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_back();
+        }
+        lines.push_front(LogLine {
+            level: *event.metadata().level(),
+            text,
+        });
+    }
+}
+
+/// The message of an event, and its other fields as ` name=value`.
+#[derive(Default)]
+struct TextVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for TextVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn recent_logs_keep_the_last_events() {
+        let logs = RecentLogs::new(2);
+        let subscriber = tracing_subscriber::registry().with(logs.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Recording the messages");
+            tracing::warn!(sink = "mqtt", "cannot deliver");
+            tracing::error!("Speech failing");
+        });
+
+        let lines = logs.lines();
+        assert_eq!(2, lines.len());
+        assert_eq!(Level::ERROR, lines[0].level);
+        assert!(lines[0].text.ends_with(" Speech failing"));
+        assert_eq!(Level::WARN, lines[1].level);
+        assert!(lines[1].text.ends_with(" cannot deliver sink=mqtt"));
+    }
+}
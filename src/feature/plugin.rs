@@ -0,0 +1,251 @@
+//! External plugins, programs in any language extending the coach as sinks or coaches.
+//!
+//! A plugin is started with its command line, and talks line-delimited JSON: each request is a
+//! line with a JSON object on its standard input, with the kind of request in `type` and a number
+//! in `id`, answered by a line with a JSON object on its standard output with the same `id`, or
+//! `{"id": ..., "error": "..."}` when it fails. An answer coming too late is skipped. What it
+//! writes to its standard error is logged.
+//!
+//! The first request is `{"type": "hello", "id": 1, "protocol": 1, "role": "sink"}` (or
+//! `"coach"`), answered with what the plugin is, e.g. `{"id": 1, "name": "pushover"}`. Then come
+//! the requests of the role, see [`PluginSink`](super::sink::PluginSink) and
+//! [`PluginCoach`](super::coach::PluginCoach).
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use error_stack::{Report, ResultExt};
+use tracing::{debug, warn};
+
+/// Version of the protocol, in the `hello` request
+pub const PROTOCOL_VERSION: u32 = 1;
+/// How long a plugin has to answer a request
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+#[error("plugin error")]
+pub struct PluginError;
+
+pub type PluginResult<T> = Result<T, Report<PluginError>>;
+
+/// What a plugin is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Sink,
+    Coach,
+}
+
+impl Role {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Sink => "sink",
+            Role::Coach => "coach",
+        }
+    }
+}
+
+/// A running plugin, stopped when dropped.
+pub struct Plugin {
+    command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    answers: Receiver<String>,
+    /// Id of the last request
+    last_id: u64,
+    /// How long the plugin has to answer a request
+    timeout: Duration,
+    /// Answer to the `hello` request
+    hello: serde_json::Value,
+}
+
+impl Plugin {
+    /// Starts the plugin with `command_line` (the program and its arguments, separated by
+    /// spaces) and greets it as `role`.
+    pub fn spawn(command_line: &str, role: Role) -> PluginResult<Self> {
+        let mut words = command_line.split_whitespace();
+        let program = words
+            .next()
+            .ok_or(Report::new(PluginError))
+            .attach("empty plugin command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .change_context(PluginError)
+            .attach(format!("cannot start the plugin `{command_line}`"))?;
+        let stdin = child.stdin.take();
+        let (sender, answers) = mpsc::channel();
+        if let Some(stdout) = child.stdout.take() {
+            std::thread::spawn(move || {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let command = command_line.to_owned();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    warn!("{command}: {line}");
+                }
+            });
+        }
+        let mut plugin = Self {
+            command: command_line.to_owned(),
+            child,
+            stdin,
+            answers,
+            last_id: 0,
+            timeout: ANSWER_TIMEOUT,
+            hello: serde_json::Value::Null,
+        };
+        plugin.hello = plugin.request(serde_json::json!({
+            "type": "hello",
+            "protocol": PROTOCOL_VERSION,
+            "role": role.name(),
+        }))?;
+        debug!("Plugin `{command_line}` started: {}", plugin.hello);
+        Ok(plugin)
+    }
+
+    /// Answer to the `hello` request, telling what the plugin is.
+    pub fn hello(&self) -> &serde_json::Value {
+        &self.hello
+    }
+
+    /// Sends `request`, waiting for the answer.
+    pub fn request(&mut self, mut request: serde_json::Value) -> PluginResult<serde_json::Value> {
+        self.last_id += 1;
+        request["id"] = self.last_id.into();
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or(Report::new(PluginError))
+            .attach(format!("the plugin `{}` has stopped", self.command))?;
+        writeln!(stdin, "{request}")
+            .and_then(|_| stdin.flush())
+            .change_context(PluginError)
+            .attach(format!("cannot write to the plugin `{}`", self.command))?;
+        let deadline = Instant::now() + self.timeout;
+        let answer = loop {
+            let line = self
+                .answers
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .change_context(PluginError)
+                .attach(format!("no answer from the plugin `{}`", self.command))?;
+            let answer: serde_json::Value = serde_json::from_str(&line)
+                .change_context(PluginError)
+                .attach(format!(
+                    "invalid answer from the plugin `{}`: {line}",
+                    self.command
+                ))?;
+            // Answers to the requests given up on before
+            if answer["id"] == self.last_id {
+                break answer;
+            }
+            debug!(
+                "Late answer from the plugin `{}` skipped: {line}",
+                self.command
+            );
+        };
+        match answer.get("error") {
+            Some(error) => Err(Report::new(PluginError)
+                .attach(format!("the plugin `{}` failed: {error}", self.command))),
+            None => Ok(answer),
+        }
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        // End of the input first, for the plugin to stop on its own
+        self.stdin.take();
+        if let Ok(None) = self.child.try_wait() {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// A plugin running `body` for each request, in `line`, with its `id`.
+    fn script_plugin(body: &str) -> String {
+        static SCRIPTS: AtomicUsize = AtomicUsize::new(0);
+        let script = std::env::temp_dir().join(format!(
+            "rendezvous-plugin-{}-{}.sh",
+            std::process::id(),
+            SCRIPTS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let lines = [
+            "#!/bin/sh",
+            "while read -r line; do",
+            r#"  id=$(echo "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')"#,
+            body,
+            "done",
+        ];
+        std::fs::write(&script, lines.join("\n")).unwrap();
+        format!("sh {}", script.display())
+    }
+
+    /// A plugin answering each request with the fields of `answer`, the `hello` included.
+    pub(crate) fn echo_plugin(answer: &str) -> String {
+        let fields = answer.trim_start_matches('{').trim();
+        let fields = if fields == "}" {
+            fields.to_owned()
+        } else {
+            format!(",{fields}")
+        };
+        script_plugin(&format!(r#"  printf '{{"id":%s%s\n' "$id" '{fields}'"#))
+    }
+
+    #[test]
+    fn plugin_answers_the_requests() {
+        let mut plugin = Plugin::spawn(&echo_plugin(r#"{"name": "echo"}"#), Role::Sink).unwrap();
+
+        assert_eq!("echo", plugin.hello()["name"]);
+        assert_eq!(
+            "echo",
+            plugin.request(serde_json::json!({"type": "ping"})).unwrap()["name"]
+        );
+    }
+
+    #[test]
+    fn plugin_answers_too_late_are_skipped() {
+        let mut plugin = Plugin::spawn(
+            &script_plugin(
+                r#"  kind=$(echo "$line" | sed 's/.*"type":"\([a-z]*\)".*/\1/')
+  [ "$kind" = slow ] && sleep 1
+  printf '{"id":%s,"kind":"%s"}\n' "$id" "$kind""#,
+            ),
+            Role::Coach,
+        )
+        .unwrap();
+
+        plugin.timeout = Duration::from_millis(200);
+        assert!(plugin.request(serde_json::json!({"type": "slow"})).is_err());
+        plugin.timeout = Duration::from_secs(5);
+        let answer = plugin.request(serde_json::json!({"type": "ping"})).unwrap();
+
+        assert_eq!("ping", answer["kind"]);
+    }
+
+    #[test]
+    fn plugin_errors_are_reported() {
+        assert!(Plugin::spawn(&echo_plugin(r#"{"error": "no"}"#), Role::Sink).is_err());
+        assert!(Plugin::spawn("", Role::Sink).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! The notifications of a plan ahead of the session, as a table for the terminal or as JSON or
+//! CSV for other programs.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use error_stack::ResultExt;
+
+use crate::common::csv_row;
+use crate::error::{AppError, AppResult};
+use crate::feature::coach::Coach;
+use crate::plan::Plan;
+use crate::time::Timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PreviewFormat {
+    /// An object with the plan and its departure time, and the array of the notifications
+    Json,
+    /// One row per notification
+    Csv,
+}
+
+/// Writes the notifications planned from `now` by `coach` as `format`.
+pub fn preview(
+    plan: &Plan,
+    now: &Timestamp,
+    coach: &impl Coach,
+    format: PreviewFormat,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    let departure = plan.departure_time();
+    // Planned backward from departure
+    let mut notifications = plan
+        .notifications(now, coach)
+        .change_context(AppError::InvalidRendezvous)?;
+    notifications.reverse();
+    match format {
+        PreviewFormat::Json => {
+            let notifications: Vec<_> = notifications
+                .iter()
+                .map(|n| {
+                    serde_json::json!({
+                        "time": n.time.to_rfc3339(),
+                        "remaining_seconds": departure.time_span_from(&n.time).total_secs(),
+                        "message": n.message,
+                        "urgency": n.urgency.name(),
+                    })
+                })
+                .collect();
+            let preview = serde_json::json!({
+                "rendezvous_time": plan.rendezvous_time.to_rfc3339(),
+                "trip_seconds": plan.trip_duration.total_secs(),
+                "departure_time": departure.to_rfc3339(),
+                "notifications": notifications,
+            });
+            serde_json::to_writer_pretty(&mut *out, &preview)
+                .change_context(AppError::Other)
+                .and_then(|_| writeln!(out).change_context(AppError::Other))
+        }
+        PreviewFormat::Csv => std::iter::once("time,remaining_seconds,message,urgency".to_owned())
+            .chain(notifications.iter().map(|n| {
+                let remaining = departure.time_span_from(&n.time).total_secs().to_string();
+                csv_row(
+                    [
+                        n.time.to_rfc3339().as_str(),
+                        remaining.as_str(),
+                        n.message.as_str(),
+                        n.urgency.name(),
+                    ]
+                    .into_iter(),
+                )
+            }))
+            .try_for_each(|row| writeln!(out, "{row}"))
+            .change_context(AppError::Other),
+    }
+    .attach("cannot write the preview")
+}
+
+/// The departure time of `plan`, and the table of the notifications planned from `now` with their
+/// times.
+pub fn schedule_table(plan: &Plan, now: &Timestamp, coach: &impl Coach) -> AppResult<String> {
+    let departure = plan.departure_time();
+    let mut table = format!(
+        "Rendezvous {}, trip {}: departure at {departure}\n\n{:<10}{:<10}{:<10}MESSAGE\n",
+        plan.rendezvous_time, plan.trip_duration, "TIME", "LEFT", "URGENCY"
+    );
+    // Planned backward from departure
+    let notifications = plan
+        .notifications(now, coach)
+        .change_context(AppError::InvalidRendezvous)?;
+    for n in notifications.iter().rev() {
+        table.push_str(&format!(
+            "{:<10}{:<10}{:<10}{}\n",
+            n.time.to_hh_mm_ss(),
+            departure.time_span_from(&n.time).to_string(),
+            n.urgency.name(),
+            n.message
+        ));
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feature::coach::DefaultItCoach;
+    use crate::time::TimeSpan;
+
+    #[test]
+    fn schedule_table_lists_the_notifications_in_order() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let now = Timestamp::new(2025, 10, 24, 19, 00, 00).unwrap();
+
+        let table = schedule_table(&plan, &now, &DefaultItCoach).unwrap();
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].ends_with("departure at 2025-10-24 19:45:00"));
+        assert_eq!("TIME      LEFT      URGENCY   MESSAGE", lines[2]);
+        assert!(lines[3].starts_with("19:05:00  00:40:00  low       "));
+        assert!(lines[9].starts_with("19:40:00  00:05:00  medium    "));
+        assert!(lines[14].starts_with("19:45:00  00:00:00  critical  "));
+        assert_eq!(15, lines.len());
+    }
+
+    #[test]
+    fn preview_as_csv() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let now = Timestamp::new(2025, 10, 24, 19, 42, 00).unwrap();
+        let mut out = Vec::new();
+
+        preview(&plan, &now, &DefaultItCoach, PreviewFormat::Csv, &mut out).unwrap();
+
+        let csv = String::from_utf8(out).unwrap();
+        let rows: Vec<_> = csv.lines().collect();
+        assert_eq!("time,remaining_seconds,message,urgency", rows[0]);
+        assert!(rows[1].starts_with(&format!(
+            "{},180,",
+            Timestamp::new(2025, 10, 24, 19, 42, 00).unwrap().to_rfc3339()
+        )));
+        assert!(rows[4].ends_with(",critical"));
+        assert_eq!(5, rows.len());
+    }
+
+    #[test]
+    fn preview_as_json() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let now = Timestamp::new(2025, 10, 24, 19, 00, 00).unwrap();
+        let mut out = Vec::new();
+
+        preview(&plan, &now, &DefaultItCoach, PreviewFormat::Json, &mut out).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(900, json["trip_seconds"]);
+        let notifications = json["notifications"].as_array().unwrap();
+        assert_eq!(12, notifications.len());
+        assert_eq!(2400, notifications[0]["remaining_seconds"]);
+        assert_eq!("low", notifications[0]["urgency"]);
+        assert_eq!("critical", notifications[11]["urgency"]);
+    }
+}
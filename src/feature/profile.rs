@@ -0,0 +1,125 @@
+//! Named plans, e.g. `school`, each a file in the format of the configuration file in the
+//! `profiles` directory next to it, with the options of a routine (rendezvous, trip duration,
+//! voice, sinks...) so that it starts with `rendezvous-coach run school`.
+
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, ResultExt};
+
+use super::config::Config;
+
+#[derive(Debug, thiserror::Error)]
+#[error("profile error")]
+pub struct ProfileError;
+
+pub type ProfileResult<T> = Result<T, Report<ProfileError>>;
+
+/// The profiles stored in a directory.
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    dir: PathBuf,
+}
+
+impl Profiles {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `profiles` next to the configuration file at `config_path`.
+    pub fn beside(config_path: &Path) -> Self {
+        Self::new(
+            config_path
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join("profiles"),
+        )
+    }
+
+    fn path(&self, name: &str) -> ProfileResult<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Report::new(ProfileError).attach(format!(
+                "invalid profile name `{name}`, only letters, digits, - and _ are allowed"
+            )));
+        }
+        Ok(self.dir.join(format!("{name}.toml")))
+    }
+
+    /// Names of the profiles, in alphabetical order.
+    pub fn list(&self) -> ProfileResult<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(Report::new(e)
+                    .change_context(ProfileError)
+                    .attach(format!("cannot read {}", self.dir.display())));
+            }
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "toml")
+                    .then(|| path.file_stem()?.to_str().map(str::to_owned))
+                    .flatten()
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(&self, name: &str) -> ProfileResult<Config> {
+        let path = self.path(name)?;
+        let content = std::fs::read_to_string(&path)
+            .change_context(ProfileError)
+            .attach(format!("no profile `{name}` in {}", self.dir.display()))?;
+        Config::parse(&content)
+            .change_context(ProfileError)
+            .attach(format!("invalid profile in {}", path.display()))
+    }
+
+    /// Stores `config` as `name`, replacing the profile of the same name.
+    pub fn save(&self, name: &str, config: &Config) -> ProfileResult<PathBuf> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)
+            .change_context(ProfileError)
+            .attach(format!("cannot create {}", self.dir.display()))?;
+        std::fs::write(&path, config.to_toml())
+            .change_context(ProfileError)
+            .attach(format!("cannot write {}", path.display()))?;
+        Ok(path)
+    }
+
+    pub fn delete(&self, name: &str) -> ProfileResult<()> {
+        let path = self.path(name)?;
+        std::fs::remove_file(&path)
+            .change_context(ProfileError)
+            .attach(format!("no profile `{name}` in {}", self.dir.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_are_saved_listed_and_deleted() {
+        let dir = std::env::temp_dir().join(format!("rendezvous-profiles-{}", std::process::id()));
+        let profiles = Profiles::new(&dir);
+        let school = Config::parse("rendezvous = \"08:00\"\ntrip = \"00:20\"").unwrap();
+
+        profiles.save("school", &school).unwrap();
+        profiles.save("gym", &Config::default()).unwrap();
+
+        assert_eq!(vec!["gym", "school"], profiles.list().unwrap());
+        assert_eq!(school, profiles.load("school").unwrap());
+        profiles.delete("gym").unwrap();
+        assert_eq!(vec!["school"], profiles.list().unwrap());
+        assert!(profiles.load("gym").is_err());
+        assert!(profiles.save("../school", &school).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+
+use tracing::{debug, warn};
+
+use super::{Sink, SinkResult};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+enum Message {
+    Emit(Notification, TimeSpan),
+    Update(TimeSpan),
+    Finish(TimeSpan),
+}
+
+/// Sink delivering the notifications from a worker thread, so that a slow one (e.g. over the
+/// network) does not hold up the countdown.
+///
+/// Failures are logged and counted, as nothing is waiting for them. Dropping the sink waits for
+/// the notifications still queued to be delivered.
+pub struct BackgroundSink {
+    sender: Option<Sender<Message>>,
+    worker: Option<JoinHandle<()>>,
+    failures: Arc<AtomicU32>,
+    /// Whether the inner sink is of use at every tick
+    wants_updates: bool,
+}
+
+impl BackgroundSink {
+    pub fn new<S: Sink + Send + 'static>(mut inner: S) -> Self {
+        let wants_updates = inner.wants_updates();
+        let (sender, receiver) = mpsc::channel::<Message>();
+        let failures = Arc::new(AtomicU32::new(0));
+        let worker_failures = failures.clone();
+        let worker = std::thread::spawn(move || {
+            for message in receiver {
+                match message {
+                    Message::Emit(notification, remaining) => {
+                        if let Err(report) = inner.emit(&notification, remaining) {
+                            warn!("cannot deliver `{}`: {report:?}", notification.message);
+                            worker_failures.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    Message::Update(remaining) => {
+                        if let Err(report) = inner.update(remaining) {
+                            debug!("cannot update the remaining time: {report:?}");
+                        }
+                    }
+                    Message::Finish(remaining) => {
+                        if let Err(report) = inner.finish(remaining) {
+                            warn!("cannot deliver the end of the session: {report:?}");
+                            worker_failures.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            worker: Some(worker),
+            failures,
+            wants_updates,
+        }
+    }
+
+    fn send(&self, message: Message) {
+        if let Some(sender) = &self.sender {
+            // The worker only stops once the sender is dropped.
+            let _ = sender.send(message);
+        }
+    }
+}
+
+impl Sink for BackgroundSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        self.send(Message::Emit(notification.clone(), remaining));
+        Ok(())
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.send(Message::Update(remaining));
+        Ok(())
+    }
+
+    fn wants_updates(&self) -> bool {
+        self.wants_updates
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.send(Message::Finish(remaining));
+        Ok(())
+    }
+
+    fn failures(&self) -> u32 {
+        self.failures.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for BackgroundSink {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::feature::sink::SinkError;
+    use crate::plan::Urgency;
+    use crate::time::Timestamp;
+
+    struct SharedSink(Arc<Mutex<Vec<String>>>);
+
+    impl Sink for SharedSink {
+        fn emit(&mut self, notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+            self.0.lock().unwrap().push(notification.message.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn background_sink_delivers_everything_before_being_dropped() {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let mut sink = BackgroundSink::new(SharedSink(delivered.clone()));
+
+        for message in ["Mancano 5 minuti", "Ora di partire!"] {
+            let notification = Notification {
+                time: Timestamp::now().unwrap(),
+                message: message.to_owned(),
+                urgency: Urgency::Medium,
+            };
+            sink.emit(&notification, TimeSpan::ZERO).unwrap();
+        }
+        drop(sink);
+
+        assert_eq!(
+            vec!["Mancano 5 minuti", "Ora di partire!"],
+            *delivered.lock().unwrap()
+        );
+    }
+
+    struct BrokenSink;
+
+    impl Sink for BrokenSink {
+        fn emit(&mut self, _notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+            Err(error_stack::Report::new(SinkError).attach("connection refused"))
+        }
+    }
+
+    #[test]
+    fn background_sink_counts_the_failures() {
+        let mut sink = BackgroundSink::new(BrokenSink);
+        let notification = Notification {
+            time: Timestamp::now().unwrap(),
+            message: "Mancano 5 minuti".to_owned(),
+            urgency: Urgency::Medium,
+        };
+
+        sink.emit(&notification, TimeSpan::ZERO).unwrap();
+        sink.emit(&notification, TimeSpan::ZERO).unwrap();
+        // Waiting for the worker
+        let failures = sink.failures.clone();
+        drop(sink);
+
+        assert_eq!(2, failures.load(Ordering::Relaxed));
+    }
+}
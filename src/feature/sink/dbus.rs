@@ -0,0 +1,95 @@
+use error_stack::ResultExt;
+use zbus::blocking::Connection;
+use zbus::{SignalContext, interface};
+
+use super::{Sink, SinkError, SinkResult};
+use crate::plan::Notification;
+use crate::time::{TimeSpan, Timestamp};
+
+const BUS_NAME: &str = "io.github.manuelp.RendezvousCoach";
+const OBJECT_PATH: &str = "/io/github/manuelp/RendezvousCoach";
+
+/// The countdown, as seen on the bus.
+struct Countdown {
+    departure_time: Timestamp,
+}
+
+impl Countdown {
+    fn remaining(&self) -> TimeSpan {
+        Timestamp::now()
+            .map(|now| self.departure_time.time_span_from(&now))
+            .unwrap_or(TimeSpan::ZERO)
+    }
+}
+
+#[interface(name = "io.github.manuelp.RendezvousCoach1")]
+impl Countdown {
+    /// Seconds remaining before departure
+    #[zbus(property)]
+    fn remaining_seconds(&self) -> u64 {
+        self.remaining().total_secs()
+    }
+
+    /// Departure time, in RFC 3339 format
+    #[zbus(property)]
+    fn departure_time(&self) -> String {
+        self.departure_time.to_rfc3339()
+    }
+
+    /// `counting` until departure, then `departed`
+    #[zbus(property)]
+    fn state(&self) -> &str {
+        if self.remaining().is_zero() {
+            "departed"
+        } else {
+            "counting"
+        }
+    }
+
+    /// A message of the countdown
+    #[zbus(signal)]
+    async fn notification(
+        ctxt: &SignalContext<'_>,
+        message: &str,
+        urgency: &str,
+        remaining_seconds: u64,
+    ) -> zbus::Result<()>;
+}
+
+/// Sink emitting a D-Bus signal for each notification on the session bus, where the countdown
+/// can also be queried, for applets and scripts.
+pub struct DbusSink {
+    connection: Connection,
+}
+
+impl DbusSink {
+    pub fn new(departure_time: Timestamp) -> SinkResult<Self> {
+        let connection = zbus::blocking::connection::Builder::session()
+            .and_then(|b| b.name(BUS_NAME))
+            .and_then(|b| b.serve_at(OBJECT_PATH, Countdown { departure_time }))
+            .and_then(|b| b.build())
+            .change_context(SinkError)
+            .attach(format!(
+                "cannot register `{BUS_NAME}` on the D-Bus session bus"
+            ))?;
+        Ok(Self { connection })
+    }
+}
+
+impl Sink for DbusSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        let countdown = self
+            .connection
+            .object_server()
+            .interface::<_, Countdown>(OBJECT_PATH)
+            .change_context(SinkError)?;
+        zbus::block_on(Countdown::notification(
+            countdown.signal_context(),
+            &notification.message,
+            notification.urgency.name(),
+            remaining.total_secs(),
+        ))
+        .change_context(SinkError)
+        .attach("cannot emit the D-Bus signal")
+    }
+}
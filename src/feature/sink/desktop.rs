@@ -0,0 +1,52 @@
+use error_stack::ResultExt;
+
+use super::{Sink, SinkError, SinkResult};
+use crate::plan::Notification;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::plan::Urgency;
+use crate::time::TimeSpan;
+
+/// Sink raising a desktop notification for each message, so that it is visible even when the
+/// terminal is behind other windows.
+#[derive(Debug, Default)]
+pub struct DesktopSink;
+
+impl Sink for DesktopSink {
+    fn emit(&mut self, notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+        let mut desktop = notify_rust::Notification::new();
+        desktop
+            .appname("rendezvous-coach")
+            .summary("rendezvous-coach")
+            .body(&notification.message);
+        #[cfg(all(unix, not(target_os = "macos")))]
+        desktop.urgency(urgency(notification.urgency));
+        desktop
+            .show()
+            .map(|_| ())
+            .change_context(SinkError)
+            .attach("cannot show a desktop notification")
+    }
+}
+
+/// Desktop notification urgency: the final minutes stay on screen until dismissed.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn urgency(urgency: Urgency) -> notify_rust::Urgency {
+    match urgency {
+        Urgency::Low => notify_rust::Urgency::Low,
+        Urgency::Medium => notify_rust::Urgency::Normal,
+        Urgency::High | Urgency::Critical => notify_rust::Urgency::Critical,
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urgency_escalates_to_critical_in_the_last_minutes() {
+        assert_eq!(notify_rust::Urgency::Low, urgency(Urgency::Low));
+        assert_eq!(notify_rust::Urgency::Normal, urgency(Urgency::Medium));
+        assert_eq!(notify_rust::Urgency::Critical, urgency(Urgency::High));
+        assert_eq!(notify_rust::Urgency::Critical, urgency(Urgency::Critical));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use tracing::{debug, warn};
+
+use super::{Sink, SinkFilter};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+/// What became of a notification on a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Delivered, or queued for delivery
+    Delivered,
+    /// Not meant for the channel (e.g. filtered out, or muted)
+    Skipped,
+    Failed,
+}
+
+impl Outcome {
+    /// Short name, e.g. `ok`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Delivered => "ok",
+            Outcome::Skipped => "skipped",
+            Outcome::Failed => "failed",
+        }
+    }
+}
+
+struct Route {
+    name: String,
+    filter: SinkFilter,
+    sink: Box<dyn Sink>,
+    /// Deliveries failed so far
+    failures: u32,
+}
+
+/// Delivers the notifications to the sinks their filters let them through to, logging the
+/// failures.
+#[derive(Default)]
+pub struct Dispatcher {
+    routes: Vec<Route>,
+    /// Configured filters, by sink name
+    filters: HashMap<String, SinkFilter>,
+}
+
+impl Dispatcher {
+    pub fn new(filters: impl IntoIterator<Item = (String, SinkFilter)>) -> Self {
+        Self {
+            routes: Vec::new(),
+            filters: filters.into_iter().collect(),
+        }
+    }
+
+    /// Adds `sink` as `name`, filtered as configured for it, or with `default`.
+    pub fn add(&mut self, name: &str, default: SinkFilter, sink: Box<dyn Sink>) {
+        let filter = self.filters.get(name).copied().unwrap_or(default);
+        self.routes.push(Route {
+            name: name.to_owned(),
+            filter,
+            sink,
+            failures: 0,
+        });
+    }
+
+    /// Delivers `notification` to each sink, returning the outcome by sink name.
+    pub fn emit(
+        &mut self,
+        notification: &Notification,
+        remaining: TimeSpan,
+    ) -> Vec<(String, Outcome)> {
+        let mut outcomes = Vec::with_capacity(self.routes.len());
+        for route in &mut self.routes {
+            let outcome = if !route.filter.accepts(notification, remaining) {
+                Outcome::Skipped
+            } else if let Err(report) = route.sink.emit(notification, remaining) {
+                warn!(
+                    "cannot deliver `{}` to {}: {report:?}",
+                    notification.message, route.name
+                );
+                route.failures += 1;
+                Outcome::Failed
+            } else {
+                Outcome::Delivered
+            };
+            outcomes.push((route.name.clone(), outcome));
+        }
+        outcomes
+    }
+
+    /// Names of the sinks, with the deliveries failed so far, including those in the background.
+    pub fn failures(&self) -> Vec<(&str, u32)> {
+        self.routes
+            .iter()
+            .map(|route| (route.name.as_str(), route.failures + route.sink.failures()))
+            .collect()
+    }
+
+    /// Whether any sink is of use at every tick, see [`Sink::wants_updates`].
+    pub fn wants_updates(&self) -> bool {
+        self.routes.iter().any(|route| route.sink.wants_updates())
+    }
+
+    pub fn update(&mut self, remaining: TimeSpan) {
+        for route in &mut self.routes {
+            if let Err(report) = route.sink.update(remaining) {
+                debug!(
+                    "cannot update the remaining time of {}: {report:?}",
+                    route.name
+                );
+            }
+        }
+    }
+
+    pub fn finish(&mut self, remaining: TimeSpan) {
+        for route in &mut self.routes {
+            if let Err(report) = route.sink.finish(remaining) {
+                warn!(
+                    "cannot deliver the end of the session to {}: {report:?}",
+                    route.name
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::feature::sink::{SinkError, SinkResult};
+    use crate::plan::Urgency;
+    use crate::time::Timestamp;
+
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+    impl Sink for RecordingSink {
+        fn emit(&mut self, notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+            self.0.lock().unwrap().push(notification.message.clone());
+            Ok(())
+        }
+    }
+
+    struct BrokenSink;
+
+    impl Sink for BrokenSink {
+        fn emit(&mut self, _notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+            Err(error_stack::Report::new(SinkError).attach("connection refused"))
+        }
+    }
+
+    #[test]
+    fn dispatcher_applies_the_filters() {
+        let filters = [("phone".to_owned(), "00:15".parse().unwrap())];
+        let mut dispatcher = Dispatcher::new(filters);
+        let desktop = Arc::new(Mutex::new(Vec::new()));
+        let phone = Arc::new(Mutex::new(Vec::new()));
+        let high = SinkFilter::new(Some(Urgency::High), None);
+        dispatcher.add(
+            "desktop",
+            SinkFilter::ALL,
+            Box::new(RecordingSink(desktop.clone())),
+        );
+        dispatcher.add("phone", high, Box::new(RecordingSink(phone.clone())));
+
+        let mut outcomes = Vec::new();
+        for minutes in [30, 15, 4] {
+            let remaining = TimeSpan::of_minutes(minutes);
+            let notification = Notification {
+                time: Timestamp::now().unwrap(),
+                message: format!("{minutes}"),
+                urgency: Urgency::for_remaining(&remaining),
+            };
+            outcomes = dispatcher.emit(&notification, remaining);
+        }
+
+        assert_eq!(vec!["30", "15", "4"], *desktop.lock().unwrap());
+        assert_eq!(vec!["15", "4"], *phone.lock().unwrap());
+        assert_eq!(
+            vec![
+                ("desktop".to_owned(), Outcome::Delivered),
+                ("phone".to_owned(), Outcome::Delivered)
+            ],
+            outcomes
+        );
+    }
+
+    #[test]
+    fn dispatcher_counts_the_failures() {
+        let mut dispatcher = Dispatcher::default();
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        dispatcher.add(
+            "desktop",
+            SinkFilter::ALL,
+            Box::new(RecordingSink(delivered)),
+        );
+        dispatcher.add("mqtt", SinkFilter::ALL, Box::new(BrokenSink));
+        let notification = Notification {
+            time: Timestamp::now().unwrap(),
+            message: "Ora di partire!".to_owned(),
+            urgency: Urgency::Critical,
+        };
+
+        dispatcher.emit(&notification, TimeSpan::ZERO);
+
+        assert_eq!(vec![("desktop", 0), ("mqtt", 1)], dispatcher.failures());
+    }
+}
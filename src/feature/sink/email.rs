@@ -0,0 +1,160 @@
+use std::cmp::Reverse;
+
+use error_stack::ResultExt;
+use lettre::message::header::ContentType;
+use lettre::message::{Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+
+use super::{Sink, SinkError, SinkResult};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+/// SMTP server the reminders are sent through, with STARTTLS.
+#[derive(Debug, Clone)]
+pub struct SmtpServer {
+    pub host: String,
+    pub port: u16,
+    /// User name and password, if the server requires them
+    pub credentials: Option<(String, String)>,
+}
+
+/// Remaining times to send a reminder at, not reached yet.
+#[derive(Debug)]
+struct Reminders {
+    /// From the farthest from departure to the closest
+    thresholds: Vec<TimeSpan>,
+    next: usize,
+}
+
+impl Reminders {
+    fn new(mut thresholds: Vec<TimeSpan>) -> Self {
+        thresholds.sort_by_key(|t| Reverse(*t));
+        Self {
+            thresholds,
+            next: 0,
+        }
+    }
+
+    /// Whether a reminder is due when `remaining` is left, having just reached a threshold.
+    ///
+    /// When several thresholds are reached at once, a single reminder is due.
+    fn reached(&mut self, remaining: &TimeSpan) -> bool {
+        let reached = self.count_reached(remaining);
+        self.next += reached;
+        reached > 0
+    }
+
+    /// Whether a reminder is due when `remaining` is left, without moving past the thresholds
+    /// reached, so that it is still due until sent.
+    fn is_due(&self, remaining: &TimeSpan) -> bool {
+        self.count_reached(remaining) > 0
+    }
+
+    fn count_reached(&self, remaining: &TimeSpan) -> usize {
+        self.thresholds[self.next..]
+            .iter()
+            .take_while(|t| *t >= remaining)
+            .count()
+    }
+}
+
+/// Sink emailing a reminder when the countdown reaches given remaining times (e.g. one hour
+/// and a quarter before departure), for countdowns spanning many hours.
+pub struct EmailSink {
+    mailer: SmtpTransport,
+    from: Mailbox,
+    to: Mailbox,
+    reminders: Reminders,
+}
+
+impl EmailSink {
+    pub fn new(
+        server: &SmtpServer,
+        from: &str,
+        to: &str,
+        thresholds: Vec<TimeSpan>,
+    ) -> SinkResult<Self> {
+        let from = from
+            .parse()
+            .change_context(SinkError)
+            .attach(format!("invalid sender address `{from}`"))?;
+        let to = to
+            .parse()
+            .change_context(SinkError)
+            .attach(format!("invalid recipient address `{to}`"))?;
+        let mut mailer = SmtpTransport::starttls_relay(&server.host)
+            .change_context(SinkError)
+            .attach(format!("invalid SMTP server `{}`", server.host))?
+            .port(server.port);
+        if let Some((username, password)) = &server.credentials {
+            mailer = mailer.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self {
+            mailer: mailer.build(),
+            from,
+            to,
+            reminders: Reminders::new(thresholds),
+        })
+    }
+}
+
+impl Sink for EmailSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        if !self.reminders.is_due(&remaining) {
+            return Ok(());
+        }
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(format!("rendezvous-coach: {}", notification.message))
+            .header(ContentType::TEXT_PLAIN)
+            .body(format!(
+                "{}\n\n{}\n",
+                notification.message,
+                notification.time.to_rfc3339()
+            ))
+            .change_context(SinkError)
+            .attach("cannot compose the reminder email")?;
+        self.mailer
+            .send(&email)
+            .change_context(SinkError)
+            .attach(format!("cannot email the reminder to {}", self.to))?;
+        self.reminders.reached(&remaining);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminders_are_due_once_per_threshold() {
+        let mut reminders = Reminders::new(vec![TimeSpan::of_minutes(15), TimeSpan::of_hours(1)]);
+
+        assert!(!reminders.reached(&TimeSpan::of_minutes(75)));
+        assert!(reminders.reached(&TimeSpan::of_hours(1)));
+        assert!(!reminders.reached(&TimeSpan::of_minutes(50)));
+        assert!(reminders.reached(&TimeSpan::of_minutes(15)));
+        assert!(!reminders.reached(&TimeSpan::of_minutes(10)));
+    }
+
+    #[test]
+    fn reminders_reached_together_are_due_once() {
+        let mut reminders = Reminders::new(vec![TimeSpan::of_hours(1), TimeSpan::of_minutes(15)]);
+
+        assert!(reminders.reached(&TimeSpan::of_minutes(10)));
+        assert!(!reminders.reached(&TimeSpan::of_minutes(5)));
+    }
+
+    #[test]
+    fn reminders_stay_due_until_reached() {
+        let mut reminders = Reminders::new(vec![TimeSpan::of_minutes(15)]);
+
+        assert!(reminders.is_due(&TimeSpan::of_minutes(15)));
+        assert!(reminders.is_due(&TimeSpan::of_minutes(15)));
+        assert!(reminders.reached(&TimeSpan::of_minutes(15)));
+        assert!(!reminders.is_due(&TimeSpan::of_minutes(15)));
+    }
+}
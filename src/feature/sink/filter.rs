@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use crate::plan::{Notification, Urgency};
+use crate::time::TimeSpan;
+
+/// Which notifications a sink gets: from a minimum urgency, and only within some time before
+/// departure.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SinkFilter {
+    min_urgency: Option<Urgency>,
+    within: Option<TimeSpan>,
+}
+
+impl SinkFilter {
+    /// Lets every notification through.
+    pub const ALL: Self = Self {
+        min_urgency: None,
+        within: None,
+    };
+
+    pub fn new(min_urgency: Option<Urgency>, within: Option<TimeSpan>) -> Self {
+        Self {
+            min_urgency,
+            within,
+        }
+    }
+
+    /// Whether `notification`, emitted `remaining` before departure, is let through.
+    pub fn accepts(&self, notification: &Notification, remaining: TimeSpan) -> bool {
+        self.min_urgency
+            .is_none_or(|min_urgency| notification.urgency >= min_urgency)
+            && self.within.is_none_or(|within| remaining <= within)
+    }
+}
+
+impl FromStr for SinkFilter {
+    type Err = String;
+
+    /// Parses `all`, or a minimum urgency (`low`, `medium`, `high` or `critical`) and/or the
+    /// remaining time within which notifications are delivered, separated by a comma (e.g.
+    /// `high`, `00:15` or `medium,00:15`).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::ALL;
+        for part in input.split(',').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "all" => {}
+                "low" => filter.min_urgency = Some(Urgency::Low),
+                "medium" => filter.min_urgency = Some(Urgency::Medium),
+                "high" => filter.min_urgency = Some(Urgency::High),
+                "critical" => filter.min_urgency = Some(Urgency::Critical),
+                _ => {
+                    let within = TimeSpan::parse(part).map_err(|_| {
+                        format!("invalid filter `{part}`, expected an urgency or HH:MM")
+                    })?;
+                    filter.within = Some(within);
+                }
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timestamp;
+
+    fn notification(remaining: TimeSpan) -> Notification {
+        Notification {
+            time: Timestamp::now().unwrap(),
+            message: "Mancano".to_owned(),
+            urgency: Urgency::for_remaining(&remaining),
+        }
+    }
+
+    #[test]
+    fn sink_filter_parse() {
+        assert_eq!(Ok(SinkFilter::ALL), "all".parse());
+        assert_eq!(
+            Ok(SinkFilter::new(Some(Urgency::High), None)),
+            "High".parse()
+        );
+        assert_eq!(
+            Ok(SinkFilter::new(
+                Some(Urgency::Medium),
+                Some(TimeSpan::of_minutes(15))
+            )),
+            "medium, 00:15".parse()
+        );
+        assert!("urgent".parse::<SinkFilter>().is_err());
+    }
+
+    #[test]
+    fn sink_filter_accepts_from_the_urgency_within_the_time() {
+        let filter = SinkFilter::new(Some(Urgency::Medium), Some(TimeSpan::of_minutes(15)));
+        let accepts = |remaining| filter.accepts(&notification(remaining), remaining);
+
+        assert!(!accepts(TimeSpan::of_minutes(20)));
+        assert!(accepts(TimeSpan::of_minutes(15)));
+        assert!(accepts(TimeSpan::ZERO));
+        assert!(
+            SinkFilter::ALL.accepts(&notification(TimeSpan::of_hours(2)), TimeSpan::of_hours(2))
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::Write;
+
+use error_stack::ResultExt;
+
+use super::{Sink, SinkError, SinkResult, payload};
+use crate::plan::{Notification, Urgency};
+use crate::time::TimeSpan;
+
+/// Sink writing one line of JSON per event, for `jq` and other tools.
+///
+/// The events are `state`, when the urgency of the remaining time changes (and at the start),
+/// `notification`, with the fields of the webhook payload, and `end`.
+pub struct JsonLinesSink<W> {
+    out: W,
+    urgency: Option<Urgency>,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, urgency: None }
+    }
+
+    fn write(&mut self, event: &str, mut fields: serde_json::Value) -> SinkResult<()> {
+        fields["event"] = event.into();
+        writeln!(self.out, "{fields}")
+            .and_then(|_| self.out.flush())
+            .change_context(SinkError)
+            .attach("cannot write the event")
+    }
+}
+
+impl<W: Write> Sink for JsonLinesSink<W> {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        self.write("notification", payload(notification, remaining))
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        let urgency = Urgency::for_remaining(&remaining);
+        if self.urgency == Some(urgency) {
+            return Ok(());
+        }
+        self.urgency = Some(urgency);
+        self.write(
+            "state",
+            serde_json::json!({
+                "remaining_seconds": remaining.total_secs(),
+                "urgency": urgency.name(),
+            }),
+        )
+    }
+
+    fn wants_updates(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.write(
+            "end",
+            serde_json::json!({ "remaining_seconds": remaining.total_secs() }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(sink: JsonLinesSink<Vec<u8>>) -> Vec<serde_json::Value> {
+        String::from_utf8(sink.out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn json_lines_sink_writes_the_state_changes() {
+        let mut sink = JsonLinesSink::new(Vec::new());
+
+        for minutes in [31, 30, 29, 28] {
+            sink.update(TimeSpan::of_minutes(minutes)).unwrap();
+        }
+        sink.finish(TimeSpan::ZERO).unwrap();
+
+        let events = events(sink);
+        let names: Vec<_> = events
+            .iter()
+            .map(|e| e["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(vec!["state", "state", "end"], names);
+        assert_eq!("low", events[0]["urgency"]);
+        assert_eq!("medium", events[1]["urgency"]);
+        assert_eq!(1740, events[1]["remaining_seconds"]);
+    }
+}
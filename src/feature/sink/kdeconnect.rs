@@ -0,0 +1,42 @@
+use std::process::{Command, Stdio};
+
+use error_stack::{Report, ResultExt};
+
+use super::{Sink, SinkError, SinkResult};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+/// Sink pinging a phone paired with KDE Connect for each notification, through `kdeconnect-cli`,
+/// so that the final call reaches you in another room.
+#[derive(Debug, Clone)]
+pub struct KdeConnectSink {
+    /// Name of the paired device, as listed by `kdeconnect-cli --list-available`
+    device: String,
+}
+
+impl KdeConnectSink {
+    pub fn new(device: impl Into<String>) -> Self {
+        Self {
+            device: device.into(),
+        }
+    }
+}
+
+impl Sink for KdeConnectSink {
+    fn emit(&mut self, notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+        let output = Command::new("kdeconnect-cli")
+            .args(["--name", &self.device, "--ping-msg", &notification.message])
+            .stdin(Stdio::null())
+            .output()
+            .change_context(SinkError)
+            .attach("cannot run kdeconnect-cli")?;
+        if !output.status.success() {
+            return Err(Report::new(SinkError).attach(format!(
+                "cannot ping `{}`: {}",
+                self.device,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use error_stack::ResultExt;
+
+use super::{Outcome, SinkError, SinkResult};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+/// Log of the notifications emitted, appended to a file for reviewing afterwards what was
+/// announced and when.
+///
+/// Each line has the time, the remaining time, the message and the outcome for each channel,
+/// separated by tabs, e.g. `2025-10-24T17:40:00+02:00	00:05:00	Mancano 5 minuti	speech=ok
+/// webhook=failed`.
+pub struct SessionLog<W> {
+    out: W,
+}
+
+impl SessionLog<File> {
+    /// Appends to the file at `path`, creating it if needed.
+    pub fn open(path: &Path) -> SinkResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .change_context(SinkError)
+            .attach(format!("cannot open the session log {}", path.display()))?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write> SessionLog<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn record(
+        &mut self,
+        notification: &Notification,
+        remaining: TimeSpan,
+        outcomes: &[(String, Outcome)],
+    ) -> SinkResult<()> {
+        let outcomes = outcomes
+            .iter()
+            .map(|(channel, outcome)| format!("{channel}={}", outcome.name()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            self.out,
+            "{}\t{remaining}\t{}\t{outcomes}",
+            notification.time.to_rfc3339(),
+            notification.message.replace(['\t', '\n'], " ")
+        )
+        .and_then(|_| self.out.flush())
+        .change_context(SinkError)
+        .attach("cannot write to the session log")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Urgency;
+    use crate::time::Timestamp;
+
+    #[test]
+    fn session_log_records_the_outcome_of_each_channel() {
+        let mut log = SessionLog::new(Vec::new());
+        let notification = Notification {
+            time: Timestamp::new(2025, 10, 24, 17, 40, 0).unwrap(),
+            message: "Mancano 5 minuti".to_owned(),
+            urgency: Urgency::Medium,
+        };
+        let outcomes = [
+            ("speech".to_owned(), Outcome::Delivered),
+            ("kdeconnect".to_owned(), Outcome::Skipped),
+            ("webhook".to_owned(), Outcome::Failed),
+        ];
+
+        log.record(&notification, TimeSpan::of_minutes(5), &outcomes)
+            .unwrap();
+
+        let line = String::from_utf8(log.out).unwrap();
+        let fields: Vec<_> = line.trim_end().split('\t').collect();
+        assert_eq!(
+            vec![
+                notification.time.to_rfc3339().as_str(),
+                "00:05:00",
+                "Mancano 5 minuti",
+                "speech=ok kdeconnect=skipped webhook=failed"
+            ],
+            fields
+        );
+    }
+}
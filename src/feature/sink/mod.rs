@@ -0,0 +1,148 @@
+use error_stack::Report;
+
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+mod background;
+#[cfg(feature = "dbus")]
+mod dbus;
+#[cfg(feature = "desktop")]
+mod desktop;
+mod dispatch;
+#[cfg(feature = "sinks-net")]
+mod email;
+mod filter;
+mod json;
+mod kdeconnect;
+mod log;
+#[cfg(feature = "sinks-net")]
+mod mqtt;
+mod options;
+mod plugin;
+mod retry;
+mod status;
+mod title;
+#[cfg(feature = "sinks-net")]
+mod webhook;
+#[cfg(feature = "sinks-net")]
+mod websocket;
+
+pub use background::BackgroundSink;
+#[cfg(feature = "dbus")]
+pub use dbus::DbusSink;
+#[cfg(feature = "desktop")]
+pub use desktop::DesktopSink;
+pub use dispatch::{Dispatcher, Outcome};
+#[cfg(feature = "sinks-net")]
+pub use email::{EmailSink, SmtpServer};
+pub use filter::SinkFilter;
+pub use json::JsonLinesSink;
+pub use kdeconnect::KdeConnectSink;
+pub use log::SessionLog;
+#[cfg(feature = "sinks-net")]
+pub use mqtt::MqttSink;
+#[cfg(feature = "sinks-net")]
+pub use options::NetworkSinkOptions;
+pub use options::SinkOptions;
+pub use plugin::PluginSink;
+pub use retry::{Backoff, DeadLetters, RetryingSink};
+pub use status::{StatusFormat, StatusLineSink};
+pub use title::TerminalTitleSink;
+#[cfg(feature = "sinks-net")]
+pub use webhook::WebhookSink;
+#[cfg(feature = "sinks-net")]
+pub use websocket::WebSocketSink;
+
+#[derive(Debug, thiserror::Error)]
+#[error("sink error")]
+pub struct SinkError;
+
+pub type SinkResult<T> = Result<T, Report<SinkError>>;
+
+/// Somewhere notifications are delivered to as they are emitted, besides the terminal and
+/// the speaker.
+pub trait Sink {
+    /// Delivers `notification`, emitted `remaining` before departure.
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()>;
+
+    /// Keeps up with the time remaining before departure, at every tick of the countdown.
+    fn update(&mut self, _remaining: TimeSpan) -> SinkResult<()> {
+        Ok(())
+    }
+
+    /// Whether [`Sink::update`] is of use, the countdown ticking at every second for it rather
+    /// than only when a notification is due.
+    fn wants_updates(&self) -> bool {
+        false
+    }
+
+    /// Closes the session, at departure or `remaining` before it when quitting early.
+    fn finish(&mut self, _remaining: TimeSpan) -> SinkResult<()> {
+        Ok(())
+    }
+
+    /// Deliveries that failed out of sight of the callers so far, e.g. on a worker thread.
+    fn failures(&self) -> u32 {
+        0
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        (**self).emit(notification, remaining)
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        (**self).update(remaining)
+    }
+
+    fn wants_updates(&self) -> bool {
+        (**self).wants_updates()
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        (**self).finish(remaining)
+    }
+
+    fn failures(&self) -> u32 {
+        (**self).failures()
+    }
+}
+
+/// The notification as JSON, for the sinks talking to other systems.
+fn payload(notification: &Notification, remaining: TimeSpan) -> serde_json::Value {
+    serde_json::json!({
+        "time": notification.time.to_rfc3339(),
+        "remaining_seconds": remaining.total_secs(),
+        "message": notification.message,
+        "urgency": notification.urgency.name(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Urgency;
+    use crate::time::Timestamp;
+
+    #[test]
+    fn payload_describes_the_notification() {
+        let notification = Notification {
+            time: Timestamp::new(2025, 10, 24, 17, 40, 0).unwrap(),
+            message: "Mancano 5 minuti".to_owned(),
+            urgency: Urgency::Medium,
+        };
+
+        let payload = payload(&notification, TimeSpan::of_minutes(5));
+
+        assert!(
+            payload["time"]
+                .as_str()
+                .unwrap()
+                .starts_with("2025-10-24T17:40:00")
+        );
+        assert_eq!(300, payload["remaining_seconds"]);
+        assert_eq!("Mancano 5 minuti", payload["message"]);
+        assert_eq!("medium", payload["urgency"]);
+    }
+}
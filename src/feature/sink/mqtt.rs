@@ -0,0 +1,246 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use error_stack::ResultExt;
+use rumqttc::{Client, Event, LastWill, MqttOptions, Outgoing, QoS};
+use tracing::{debug, warn};
+
+use super::{Sink, SinkError, SinkResult, payload};
+use crate::plan::{Notification, Urgency};
+use crate::time::TimeSpan;
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+/// Wait before reconnecting to an unreachable broker
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Messages waiting to be published, before new ones are dropped
+const CAPACITY: usize = 64;
+
+/// Sink publishing to an MQTT broker, e.g. for Home Assistant.
+///
+/// Each notification is published as JSON to `<topic>/notification`. The seconds remaining
+/// before departure, the urgency and the state of the session (`counting`, `departed` or
+/// `stopped`) are kept up to date in the retained `<topic>/remaining`, `<topic>/urgency` and
+/// `<topic>/state`, and `<topic>/availability` is `online` while the session runs.
+pub struct MqttSink {
+    client: Client,
+    topic: String,
+    /// Last remaining time published, to publish only the changes
+    remaining: Option<TimeSpan>,
+    closing: Arc<AtomicBool>,
+    connection: Option<JoinHandle<()>>,
+}
+
+impl MqttSink {
+    /// Connects to the broker at `host`, publishing under `topic`.
+    pub fn new(
+        host: &str,
+        port: u16,
+        credentials: Option<(String, String)>,
+        topic: impl Into<String>,
+    ) -> Self {
+        let id = format!("rendezvous-coach-{}", std::process::id());
+        let mut options = MqttOptions::new(id, host, port);
+        options.set_keep_alive(KEEP_ALIVE);
+        let topic = topic.into();
+        options.set_last_will(LastWill::new(
+            format!("{topic}/availability"),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let Some((username, password)) = credentials {
+            options.set_credentials(username, password);
+        }
+        let (client, mut connection) = Client::new(options, CAPACITY);
+        let closing = Arc::new(AtomicBool::new(false));
+        let connection = {
+            let closing = closing.clone();
+            std::thread::spawn(move || {
+                for event in connection.iter() {
+                    match event {
+                        Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                        Ok(_) => {}
+                        Err(e) if closing.load(Ordering::Relaxed) => {
+                            debug!("MQTT connection closed: {e}");
+                            break;
+                        }
+                        Err(e) => {
+                            warn!("MQTT connection failed: {e}");
+                            std::thread::sleep(RECONNECT_DELAY);
+                        }
+                    }
+                }
+            })
+        };
+        let sink = Self {
+            client,
+            topic,
+            remaining: None,
+            closing,
+            connection: Some(connection),
+        };
+        if let Err(report) = sink.publish("availability", true, "online".to_owned()) {
+            warn!("cannot announce the session: {report:?}");
+        }
+        sink
+    }
+
+    /// Also registers the remaining time, urgency and state as sensors of a device in Home
+    /// Assistant, through MQTT discovery under `prefix` (usually `homeassistant`).
+    pub fn with_discovery(self, prefix: &str) -> SinkResult<Self> {
+        for (topic, config) in discovery_configs(prefix, &self.topic) {
+            self.publish_to(&topic, true, config.to_string())?;
+        }
+        Ok(self)
+    }
+
+    fn publish(&self, subtopic: &str, retain: bool, payload: String) -> SinkResult<()> {
+        self.publish_to(&format!("{}/{subtopic}", self.topic), retain, payload)
+    }
+
+    fn publish_to(&self, topic: &str, retain: bool, payload: String) -> SinkResult<()> {
+        self.client
+            .try_publish(topic, QoS::AtLeastOnce, retain, payload)
+            .change_context(SinkError)
+            .attach(format!("cannot publish to `{topic}`"))
+    }
+}
+
+/// The Home Assistant discovery topics and configurations of the sensors published under
+/// `topic`.
+fn discovery_configs(prefix: &str, topic: &str) -> Vec<(String, serde_json::Value)> {
+    let node_id: String = topic
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let device = serde_json::json!({
+        "identifiers": [node_id],
+        "name": "Rendezvous Coach",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let sensor = |object_id: &str, name: &str, mut config: serde_json::Value| {
+        config["name"] = name.into();
+        config["unique_id"] = format!("{node_id}_{object_id}").into();
+        config["object_id"] = format!("{node_id}_{object_id}").into();
+        config["state_topic"] = format!("{topic}/{object_id}").into();
+        config["availability_topic"] = format!("{topic}/availability").into();
+        config["device"] = device.clone();
+        (
+            format!("{prefix}/sensor/{node_id}/{object_id}/config"),
+            config,
+        )
+    };
+    vec![
+        sensor(
+            "remaining",
+            "Remaining time",
+            serde_json::json!({
+                "device_class": "duration",
+                "unit_of_measurement": "s",
+                "icon": "mdi:car-clock",
+            }),
+        ),
+        sensor(
+            "urgency",
+            "Urgency",
+            serde_json::json!({
+                "device_class": "enum",
+                "options": ["low", "medium", "high", "critical"],
+            }),
+        ),
+        sensor(
+            "state",
+            "State",
+            serde_json::json!({
+                "device_class": "enum",
+                "options": ["counting", "departed", "stopped"],
+            }),
+        ),
+    ]
+}
+
+impl Sink for MqttSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        self.publish(
+            "notification",
+            false,
+            payload(notification, remaining).to_string(),
+        )
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        let previous = self.remaining.replace(remaining);
+        if previous == Some(remaining) {
+            return Ok(());
+        }
+        self.publish("remaining", true, remaining.total_secs().to_string())?;
+        let urgency = Urgency::for_remaining(&remaining);
+        if previous.is_none_or(|previous| Urgency::for_remaining(&previous) != urgency) {
+            self.publish("urgency", true, urgency.name().to_owned())?;
+        }
+        if previous.is_none() {
+            self.publish("state", true, "counting".to_owned())?;
+        }
+        Ok(())
+    }
+
+    fn wants_updates(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        let state = if remaining.is_zero() {
+            "departed"
+        } else {
+            "stopped"
+        };
+        self.publish("state", true, state.to_owned())
+    }
+}
+
+impl Drop for MqttSink {
+    /// Disconnects once the pending messages are published, or right away if the broker is
+    /// unreachable.
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::Relaxed);
+        // Not sent as last will on a clean disconnection
+        let _ = self.publish("availability", true, "offline".to_owned());
+        if self.client.try_disconnect().is_err() {
+            return;
+        }
+        if let Some(connection) = self.connection.take() {
+            let _ = connection.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovery_configs_describe_the_sensors() {
+        let configs = discovery_configs("homeassistant", "home/rendezvous-coach");
+
+        let topics: Vec<_> = configs.iter().map(|(topic, _)| topic.as_str()).collect();
+        assert_eq!(
+            vec![
+                "homeassistant/sensor/home_rendezvous_coach/remaining/config",
+                "homeassistant/sensor/home_rendezvous_coach/urgency/config",
+                "homeassistant/sensor/home_rendezvous_coach/state/config",
+            ],
+            topics
+        );
+        let remaining = &configs[0].1;
+        assert_eq!("home_rendezvous_coach_remaining", remaining["unique_id"]);
+        assert_eq!("home/rendezvous-coach/remaining", remaining["state_topic"]);
+        assert_eq!(
+            "home/rendezvous-coach/availability",
+            remaining["availability_topic"]
+        );
+        assert_eq!("duration", remaining["device_class"]);
+        assert_eq!("Rendezvous Coach", remaining["device"]["name"]);
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "sinks-net")]
+use std::path::PathBuf;
+#[cfg(feature = "sinks-net")]
+use std::time::Duration;
+
+#[cfg(feature = "sinks-net")]
+use tracing::info;
+
+#[cfg(feature = "dbus")]
+use super::DbusSink;
+#[cfg(feature = "desktop")]
+use super::DesktopSink;
+use super::{
+    BackgroundSink, Dispatcher, JsonLinesSink, KdeConnectSink, PluginSink, Sink, SinkFilter,
+    SinkResult, StatusFormat, StatusLineSink, TerminalTitleSink,
+};
+#[cfg(feature = "sinks-net")]
+use super::{
+    Backoff, DeadLetters, EmailSink, MqttSink, RetryingSink, SmtpServer, WebSocketSink, WebhookSink,
+};
+use crate::plan::Urgency;
+use crate::time::{TimeSpan, Timestamp};
+
+/// The sinks a session delivers to, as asked on the command line.
+#[derive(Debug, Clone)]
+pub struct SinkOptions {
+    /// Filter of the sinks, by name, the last one given prevailing
+    pub filters: Vec<(String, SinkFilter)>,
+    /// Each message as a line of JSON on the standard output
+    pub json: bool,
+    /// A status line on the standard output, every `status_interval`
+    pub status: Option<StatusFormat>,
+    pub status_interval: TimeSpan,
+    /// The remaining time in the terminal title
+    pub terminal_title: bool,
+    /// Whether the terminal interface takes the standard output: the title is set through it
+    /// too, rather than through the standard error, out of the way of the lines written
+    pub interface: bool,
+    #[cfg(feature = "desktop")]
+    pub desktop_notifications: bool,
+    /// Name of the phone paired with KDE Connect, pinged for the messages of the last 5 minutes
+    pub kdeconnect: Option<String>,
+    #[cfg(feature = "dbus")]
+    pub dbus: bool,
+    /// Command lines of the sink plugins
+    pub plugins: Vec<String>,
+    #[cfg(feature = "sinks-net")]
+    pub network: NetworkSinkOptions,
+}
+
+/// The sinks delivering over the network, retried on failure.
+#[cfg(feature = "sinks-net")]
+#[derive(Debug, Clone)]
+pub struct NetworkSinkOptions {
+    /// Times a delivery is retried, waiting twice as long each time from 1 second
+    pub retries: u32,
+    /// Where the messages that could not be delivered are recorded
+    pub dead_letters: PathBuf,
+    /// URL each message is posted to
+    pub webhook: Option<String>,
+    /// Authorization header of the webhook requests
+    pub webhook_auth: Option<String>,
+    /// Host of the MQTT broker
+    pub mqtt: Option<String>,
+    pub mqtt_port: u16,
+    pub mqtt_topic: String,
+    /// User name and password for the MQTT broker
+    pub mqtt_credentials: Option<(String, String)>,
+    /// Prefix of the Home Assistant discovery topics
+    pub mqtt_discovery: Option<String>,
+    /// Recipient of the reminder emails, sent through `smtp`
+    pub email_to: Option<String>,
+    /// Sender of the reminder emails (default: the recipient)
+    pub email_from: Option<String>,
+    /// Remaining times to email a reminder at
+    pub email_at: Vec<TimeSpan>,
+    pub smtp: Option<SmtpServer>,
+    /// Address serving the page of the live countdown, and broadcasting it over WebSocket
+    pub websocket: Option<String>,
+}
+
+impl SinkOptions {
+    /// The dispatcher delivering to the sinks, for a departure at `departure_time`.
+    pub fn dispatcher(
+        &self,
+        #[cfg_attr(
+            not(any(feature = "dbus", feature = "sinks-net")),
+            allow(unused_variables)
+        )]
+        departure_time: Timestamp,
+    ) -> SinkResult<Dispatcher> {
+        let mut sinks = Dispatcher::new(self.filters.clone());
+        let all = SinkFilter::ALL;
+        if self.json {
+            sinks.add("json", all, Box::new(JsonLinesSink::new(std::io::stdout())));
+        }
+        if let Some(format) = &self.status {
+            let status =
+                StatusLineSink::new(std::io::stdout(), format.clone(), self.status_interval);
+            sinks.add("status", all, Box::new(status));
+        }
+        if self.terminal_title {
+            let title: Box<dyn Sink> = if self.interface {
+                Box::new(TerminalTitleSink::new(std::io::stdout()))
+            } else {
+                Box::new(TerminalTitleSink::new(std::io::stderr()))
+            };
+            sinks.add("title", all, title);
+        }
+        #[cfg(feature = "desktop")]
+        if self.desktop_notifications {
+            sinks.add("desktop", all, Box::new(DesktopSink));
+        }
+        if let Some(device) = &self.kdeconnect {
+            let kdeconnect = BackgroundSink::new(KdeConnectSink::new(device));
+            // The last 5 minutes, unless configured otherwise
+            let high = SinkFilter::new(Some(Urgency::High), None);
+            sinks.add("kdeconnect", high, Box::new(kdeconnect));
+        }
+        #[cfg(feature = "dbus")]
+        if self.dbus {
+            sinks.add("dbus", all, Box::new(DbusSink::new(departure_time)?));
+        }
+        #[cfg(feature = "sinks-net")]
+        self.network.add_to(&mut sinks, departure_time)?;
+        // `plugin` filters all the plugins, `plugin-N` only the Nth
+        let plugins = self
+            .filters
+            .iter()
+            .rfind(|(name, _)| name == "plugin")
+            .map_or(all, |(_, filter)| *filter);
+        for (i, command) in self.plugins.iter().enumerate() {
+            let plugin = PluginSink::spawn(command)?;
+            sinks.add(
+                &format!("plugin-{}", i + 1),
+                plugins,
+                Box::new(BackgroundSink::new(plugin)),
+            );
+        }
+        Ok(sinks)
+    }
+}
+
+#[cfg(feature = "sinks-net")]
+impl NetworkSinkOptions {
+    fn add_to(&self, sinks: &mut Dispatcher, departure_time: Timestamp) -> SinkResult<()> {
+        let all = SinkFilter::ALL;
+        let backoff = Backoff::new(self.retries, Duration::from_secs(1));
+        let dead_letters = DeadLetters::new(self.dead_letters.clone());
+        if let Some(url) = &self.webhook {
+            let webhook = WebhookSink::new(url, self.webhook_auth.clone());
+            let webhook = network_sink("webhook", webhook, backoff, &dead_letters);
+            sinks.add("webhook", all, webhook);
+        }
+        if let Some(host) = &self.mqtt {
+            let mut mqtt = MqttSink::new(
+                host,
+                self.mqtt_port,
+                self.mqtt_credentials.clone(),
+                &self.mqtt_topic,
+            );
+            if let Some(prefix) = &self.mqtt_discovery {
+                mqtt = mqtt.with_discovery(prefix)?;
+            }
+            let mqtt = network_sink("mqtt", mqtt, backoff, &dead_letters);
+            sinks.add("mqtt", all, mqtt);
+        }
+        if let (Some(to), Some(server)) = (&self.email_to, &self.smtp) {
+            let from = self.email_from.as_deref().unwrap_or(to);
+            let email = EmailSink::new(server, from, to, self.email_at.clone())?;
+            let email = network_sink("email", email, backoff, &dead_letters);
+            sinks.add("email", all, email);
+        }
+        if let Some(address) = &self.websocket {
+            let websocket = WebSocketSink::bind(address, departure_time)?;
+            info!("Showing the countdown at http://{address}/");
+            sinks.add("websocket", all, Box::new(BackgroundSink::new(websocket)));
+        }
+        Ok(())
+    }
+}
+
+/// `sink`, delivering over the network: retried with `backoff` on failure, from a worker thread.
+#[cfg(feature = "sinks-net")]
+fn network_sink<S: Sink + Send + 'static>(
+    name: &str,
+    sink: S,
+    backoff: Backoff,
+    dead_letters: &DeadLetters,
+) -> Box<dyn Sink> {
+    let retrying = RetryingSink::new(name, sink, backoff).with_dead_letters(dead_letters.clone());
+    Box::new(BackgroundSink::new(retrying))
+}
@@ -0,0 +1,85 @@
+use error_stack::ResultExt;
+
+use super::{Sink, SinkError, SinkResult, payload};
+use crate::feature::plugin::{Plugin, Role};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+/// Sink delivering to an external [plugin](crate::feature::plugin).
+///
+/// The requests are `notification`, with the fields of the webhook payload, `update` with the
+/// `remaining_seconds` at every tick, when the plugin answers the `hello` with `"updates": true`,
+/// and `finish` with the `remaining_seconds`. Any answer but an error will do.
+pub struct PluginSink {
+    plugin: Plugin,
+    wants_updates: bool,
+}
+
+impl PluginSink {
+    /// Starts the plugin with `command_line`.
+    pub fn spawn(command_line: &str) -> SinkResult<Self> {
+        let plugin = Plugin::spawn(command_line, Role::Sink).change_context(SinkError)?;
+        let wants_updates = plugin.hello()["updates"].as_bool().unwrap_or_default();
+        Ok(Self {
+            plugin,
+            wants_updates,
+        })
+    }
+
+    fn send(&mut self, kind: &str, mut fields: serde_json::Value) -> SinkResult<()> {
+        fields["type"] = kind.into();
+        self.plugin
+            .request(fields)
+            .map(drop)
+            .change_context(SinkError)
+    }
+}
+
+impl Sink for PluginSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        self.send("notification", payload(notification, remaining))
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.send(
+            "update",
+            serde_json::json!({ "remaining_seconds": remaining.total_secs() }),
+        )
+    }
+
+    fn wants_updates(&self) -> bool {
+        self.wants_updates
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.send(
+            "finish",
+            serde_json::json!({ "remaining_seconds": remaining.total_secs() }),
+        )
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::feature::plugin::tests::echo_plugin;
+    use crate::plan::Urgency;
+    use crate::time::Timestamp;
+
+    #[test]
+    fn plugin_sink_delivers_to_the_plugin() {
+        let notification = Notification {
+            time: Timestamp::new(2025, 10, 24, 17, 40, 0).unwrap(),
+            message: "Mancano 5 minuti".to_owned(),
+            urgency: Urgency::Medium,
+        };
+        let mut sink = PluginSink::spawn(&echo_plugin(r#"{"updates": true}"#)).unwrap();
+
+        assert!(sink.wants_updates());
+        sink.emit(&notification, TimeSpan::of_minutes(5)).unwrap();
+        sink.finish(TimeSpan::ZERO).unwrap();
+
+        let sink = PluginSink::spawn(&echo_plugin("{}")).unwrap();
+        assert!(!sink.wants_updates());
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use error_stack::{Report, ResultExt};
+use tracing::{debug, warn};
+
+use super::{Sink, SinkError, SinkResult, payload};
+use crate::common::data_dir;
+use crate::plan::Notification;
+use crate::time::{TimeSpan, Timestamp};
+
+/// How many times a failed delivery is tried again, waiting twice as long each time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    retries: u32,
+    initial_delay: Duration,
+}
+
+impl Backoff {
+    pub fn new(retries: u32, initial_delay: Duration) -> Self {
+        Self {
+            retries,
+            initial_delay,
+        }
+    }
+
+    /// Wait before the `retry`-th retry, counting from 0.
+    fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+impl Default for Backoff {
+    /// 3 retries, after 1, 2 and 4 seconds.
+    fn default() -> Self {
+        Self::new(3, Duration::from_secs(1))
+    }
+}
+
+/// File where the notifications that could not be delivered are appended, one JSON line each,
+/// for review after the session.
+#[derive(Debug, Clone)]
+pub struct DeadLetters {
+    path: PathBuf,
+}
+
+impl DeadLetters {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `dead-letters.jsonl` in the data directory of the user.
+    pub fn default_path() -> PathBuf {
+        data_dir().join("dead-letters.jsonl")
+    }
+
+    fn record(
+        &self,
+        sink: &str,
+        notification: &Notification,
+        remaining: TimeSpan,
+        report: &Report<SinkError>,
+    ) -> SinkResult<()> {
+        let mut letter = payload(notification, remaining);
+        letter["sink"] = sink.into();
+        letter["error"] = format!("{report:?}").into();
+        if let Ok(now) = Timestamp::now() {
+            letter["failed_at"] = now.to_rfc3339().into();
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).change_context(SinkError)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{letter}"))
+            .change_context(SinkError)
+            .attach(format!("cannot write to {}", self.path.display()))
+    }
+}
+
+/// Sink trying the notifications again when their delivery fails, e.g. on a Wi-Fi drop, and
+/// recording them as dead letters when giving up.
+///
+/// Waits between the retries, so it is meant to run in a [`BackgroundSink`](super::BackgroundSink).
+pub struct RetryingSink<S> {
+    inner: S,
+    /// Name of the sink, in the dead letters
+    name: String,
+    backoff: Backoff,
+    dead_letters: Option<DeadLetters>,
+}
+
+impl<S: Sink> RetryingSink<S> {
+    pub fn new(name: impl Into<String>, inner: S, backoff: Backoff) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            backoff,
+            dead_letters: None,
+        }
+    }
+
+    pub fn with_dead_letters(self, dead_letters: DeadLetters) -> Self {
+        Self {
+            dead_letters: Some(dead_letters),
+            ..self
+        }
+    }
+}
+
+impl<S: Sink> Sink for RetryingSink<S> {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        let mut retry = 0;
+        loop {
+            match self.inner.emit(notification, remaining) {
+                Ok(()) => return Ok(()),
+                Err(report) if retry < self.backoff.retries => {
+                    let delay = self.backoff.delay(retry);
+                    debug!(
+                        "cannot deliver to {}, retrying in {delay:?}: {report:?}",
+                        self.name
+                    );
+                    std::thread::sleep(delay);
+                    retry += 1;
+                }
+                Err(report) => {
+                    if let Some(dead_letters) = &self.dead_letters
+                        && let Err(e) =
+                            dead_letters.record(&self.name, notification, remaining, &report)
+                    {
+                        warn!("cannot record the dead letter: {e:?}");
+                    }
+                    return Err(report.attach(format!("gave up after {} attempts", retry + 1)));
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.inner.update(remaining)
+    }
+
+    fn wants_updates(&self) -> bool {
+        self.inner.wants_updates()
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.inner.finish(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Urgency;
+
+    /// Sink failing the first `failures` deliveries.
+    struct FlakySink {
+        failures: u32,
+        attempts: u32,
+    }
+
+    impl Sink for FlakySink {
+        fn emit(&mut self, _notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+            self.attempts += 1;
+            if self.attempts <= self.failures {
+                return Err(Report::new(SinkError).attach("network unreachable"));
+            }
+            Ok(())
+        }
+    }
+
+    fn notification() -> Notification {
+        Notification {
+            time: Timestamp::now().unwrap(),
+            message: "Ora di partire!".to_owned(),
+            urgency: Urgency::Critical,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_the_delay() {
+        let backoff = Backoff::default();
+
+        assert_eq!(Duration::from_secs(1), backoff.delay(0));
+        assert_eq!(Duration::from_secs(4), backoff.delay(2));
+    }
+
+    #[test]
+    fn retrying_sink_delivers_after_transient_failures() {
+        let flaky = FlakySink {
+            failures: 2,
+            attempts: 0,
+        };
+        let mut sink = RetryingSink::new("webhook", flaky, Backoff::new(3, Duration::ZERO));
+
+        sink.emit(&notification(), TimeSpan::ZERO).unwrap();
+
+        assert_eq!(3, sink.inner.attempts);
+    }
+
+    #[test]
+    fn retrying_sink_records_the_dead_letters() {
+        let path = std::env::temp_dir().join(format!(
+            "rendezvous-coach-dead-letters-{}.jsonl",
+            std::process::id()
+        ));
+        let flaky = FlakySink {
+            failures: u32::MAX,
+            attempts: 0,
+        };
+        let mut sink = RetryingSink::new("webhook", flaky, Backoff::new(2, Duration::ZERO))
+            .with_dead_letters(DeadLetters::new(&path));
+
+        assert!(sink.emit(&notification(), TimeSpan::ZERO).is_err());
+
+        let letters = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let letter: serde_json::Value = serde_json::from_str(letters.trim()).unwrap();
+        assert_eq!(3, sink.inner.attempts);
+        assert_eq!("webhook", letter["sink"]);
+        assert_eq!("Ora di partire!", letter["message"]);
+    }
+}
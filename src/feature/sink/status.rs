@@ -0,0 +1,144 @@
+use std::io::Write;
+
+use error_stack::ResultExt;
+
+use super::{Sink, SinkError, SinkResult};
+use crate::plan::{Notification, Urgency};
+use crate::time::TimeSpan;
+
+/// How the status line is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusFormat {
+    /// The template, with `{remaining}`, `{urgency}` and `{message}` replaced (e.g. for polybar
+    /// or i3blocks)
+    Plain(String),
+    /// The template as text of a waybar custom module, with the urgency as class and the last
+    /// message as tooltip
+    Waybar(String),
+}
+
+/// Sink writing a status line with the remaining time at a regular interval, and whenever the
+/// urgency changes, for desktop bars.
+pub struct StatusLineSink<W> {
+    out: W,
+    format: StatusFormat,
+    interval: TimeSpan,
+    /// Remaining time at the last line written
+    written: Option<TimeSpan>,
+    message: String,
+}
+
+impl<W: Write> StatusLineSink<W> {
+    pub fn new(out: W, format: StatusFormat, interval: TimeSpan) -> Self {
+        Self {
+            out,
+            format,
+            interval,
+            written: None,
+            message: String::new(),
+        }
+    }
+
+    fn is_due(&self, remaining: TimeSpan) -> bool {
+        match self.written {
+            None => true,
+            Some(written) => {
+                written.total_secs().saturating_sub(remaining.total_secs())
+                    >= self.interval.total_secs()
+                    || Urgency::for_remaining(&written) != Urgency::for_remaining(&remaining)
+            }
+        }
+    }
+
+    fn line(&self, remaining: TimeSpan) -> String {
+        let urgency = Urgency::for_remaining(&remaining);
+        let render = |template: &str| {
+            template
+                .replace("{remaining}", &remaining.to_string())
+                .replace("{urgency}", urgency.name())
+                .replace("{message}", &self.message)
+        };
+        match &self.format {
+            StatusFormat::Plain(template) => render(template),
+            StatusFormat::Waybar(template) => serde_json::json!({
+                "text": render(template),
+                "tooltip": self.message,
+                "class": urgency.name(),
+                "alt": urgency.name(),
+            })
+            .to_string(),
+        }
+    }
+}
+
+impl<W: Write> Sink for StatusLineSink<W> {
+    fn emit(&mut self, notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+        self.message = notification.message.clone();
+        Ok(())
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        if !self.is_due(remaining) {
+            return Ok(());
+        }
+        self.written = Some(remaining);
+        let line = self.line(remaining);
+        writeln!(self.out, "{line}")
+            .and_then(|_| self.out.flush())
+            .change_context(SinkError)
+            .attach("cannot write the status line")
+    }
+
+    fn wants_updates(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(sink: StatusLineSink<Vec<u8>>) -> Vec<String> {
+        String::from_utf8(sink.out)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn status_line_sink_writes_at_each_interval() {
+        let format = StatusFormat::Plain("{remaining} ({urgency})".to_owned());
+        let mut sink = StatusLineSink::new(Vec::new(), format, TimeSpan::of_seconds(5));
+
+        for seconds in (0..=310).rev() {
+            sink.update(TimeSpan::of_seconds(seconds)).unwrap();
+        }
+
+        let lines = lines(sink);
+        assert_eq!("00:05:10 (medium)", lines[0]);
+        assert_eq!("00:05:05 (medium)", lines[1]);
+        // The urgency changes right away
+        assert!(lines.contains(&"00:04:59 (high)".to_owned()));
+        assert_eq!(Some(&"00:00:00 (critical)".to_owned()), lines.last());
+    }
+
+    #[test]
+    fn status_line_sink_waybar_format() {
+        let format = StatusFormat::Waybar("🚗 {remaining}".to_owned());
+        let mut sink = StatusLineSink::new(Vec::new(), format, TimeSpan::of_seconds(1));
+        let notification = Notification {
+            time: crate::time::Timestamp::now().unwrap(),
+            message: "Mancano 4 minuti".to_owned(),
+            urgency: Urgency::High,
+        };
+
+        sink.emit(&notification, TimeSpan::of_minutes(4)).unwrap();
+        sink.update(TimeSpan::of_minutes(4)).unwrap();
+
+        let line: serde_json::Value = serde_json::from_str(&lines(sink)[0]).unwrap();
+        assert_eq!("🚗 00:04:00", line["text"]);
+        assert_eq!("Mancano 4 minuti", line["tooltip"]);
+        assert_eq!("high", line["class"]);
+    }
+}
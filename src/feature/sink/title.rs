@@ -0,0 +1,83 @@
+use std::io::Write;
+
+use error_stack::ResultExt;
+
+use super::{Sink, SinkError, SinkResult};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+/// Sink keeping the remaining time in the title of the terminal window (or tab), so that the
+/// countdown shows in the taskbar even when the terminal is not in front.
+pub struct TerminalTitleSink<W> {
+    out: W,
+    /// Title last set, `None` until the first tick
+    title: Option<String>,
+}
+
+impl<W: Write> TerminalTitleSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, title: None }
+    }
+
+    fn write(&mut self, sequence: &str) -> SinkResult<()> {
+        write!(self.out, "{sequence}")
+            .and_then(|_| self.out.flush())
+            .change_context(SinkError)
+            .attach("cannot set the terminal title")
+    }
+}
+
+impl<W: Write> Sink for TerminalTitleSink<W> {
+    fn emit(&mut self, _notification: &Notification, _remaining: TimeSpan) -> SinkResult<()> {
+        Ok(())
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        let title = format!("🚗 {remaining} - Rendezvous Coach");
+        if self.title.as_ref() == Some(&title) {
+            return Ok(());
+        }
+        if self.title.is_none() {
+            // Save the current title, to restore it at the end (xterm and compatibles)
+            self.write("\x1b[22;0t")?;
+        }
+        // OSC 0 sets both the icon name (used by some tab lists) and the window title
+        self.write(&format!("\x1b]0;{title}\x07"))?;
+        self.title = Some(title);
+        Ok(())
+    }
+
+    fn wants_updates(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self, _remaining: TimeSpan) -> SinkResult<()> {
+        if self.title.take().is_some() {
+            self.write("\x1b[23;0t")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_title_sink_sets_the_title_when_it_changes() {
+        let mut sink = TerminalTitleSink::new(Vec::new());
+
+        sink.update(TimeSpan::of_seconds(61)).unwrap();
+        sink.update(TimeSpan::of_seconds(61)).unwrap();
+        sink.update(TimeSpan::of_seconds(60)).unwrap();
+        sink.finish(TimeSpan::of_seconds(60)).unwrap();
+
+        assert_eq!(
+            "\x1b[22;0t\
+             \x1b]0;🚗 00:01:01 - Rendezvous Coach\x07\
+             \x1b]0;🚗 00:01:00 - Rendezvous Coach\x07\
+             \x1b[23;0t",
+            String::from_utf8(sink.out).unwrap()
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use error_stack::Report;
+
+use super::{Sink, SinkError, SinkResult, payload};
+use crate::plan::Notification;
+use crate::time::TimeSpan;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sink posting each notification as JSON to a URL, for home automation or chat systems.
+pub struct WebhookSink {
+    agent: ureq::Agent,
+    url: String,
+    /// Value of the `Authorization` header, if any
+    auth: Option<String>,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>, auth: Option<String>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            url: url.into(),
+            auth,
+        }
+    }
+}
+
+impl Sink for WebhookSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        let mut request = self.agent.post(&self.url);
+        if let Some(auth) = &self.auth {
+            request = request.set("Authorization", auth);
+        }
+        request
+            .send_json(payload(notification, remaining))
+            .map_err(|e| Report::new(SinkError).attach(e.to_string()))?;
+        Ok(())
+    }
+}
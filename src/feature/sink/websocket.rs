@@ -0,0 +1,144 @@
+//! The page at `/` shows the live countdown, connecting to the events broadcast at `/ws`:
+//! `state` at every tick, `notification` for each message (with the webhook fields) and `end`.
+
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use error_stack::Report;
+use tiny_http::{Header, Request, Response, Server};
+use tracing::{debug, warn};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use super::{Sink, SinkError, SinkResult, payload};
+use crate::plan::{Notification, Urgency};
+use crate::time::{TimeSpan, Timestamp};
+
+const PAGE: &str = include_str!("countdown.html");
+
+type Client = WebSocket<Box<dyn tiny_http::ReadWrite + Send>>;
+
+/// Sink broadcasting the countdown over WebSocket, with a page showing it, for a tablet or
+/// another screen in the house.
+pub struct WebSocketSink {
+    server: Arc<Server>,
+    clients: Arc<Mutex<Vec<Client>>>,
+    departure_time: Timestamp,
+    /// Last message, shown until the next one
+    message: String,
+    accepting: Option<JoinHandle<()>>,
+}
+
+impl WebSocketSink {
+    /// Serves the page and the events on `address` (e.g. `0.0.0.0:8081`).
+    pub fn bind(address: &str, departure_time: Timestamp) -> SinkResult<Self> {
+        let server = Server::http(address).map_err(|e| {
+            Report::new(SinkError).attach(format!("cannot listen on {address}: {e}"))
+        })?;
+        let server = Arc::new(server);
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepting = {
+            let server = server.clone();
+            let clients = clients.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if let Some(client) = serve(request) {
+                        clients.lock().expect("clients lock").push(client);
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            server,
+            clients,
+            departure_time,
+            message: String::new(),
+            accepting: Some(accepting),
+        })
+    }
+
+    /// Sends `event` to every client, forgetting the ones gone.
+    fn broadcast(&self, event: &str, mut fields: serde_json::Value) {
+        fields["event"] = event.into();
+        let text = fields.to_string();
+        let mut clients = self.clients.lock().expect("clients lock");
+        clients.retain_mut(|client| {
+            let sent = client.send(Message::Text(text.clone().into()));
+            if let Err(e) = &sent {
+                debug!("WebSocket client gone: {e}");
+            }
+            sent.is_ok()
+        });
+    }
+}
+
+/// Answers `request` with the page, or upgrades it to the WebSocket client returned.
+fn serve(request: Request) -> Option<Client> {
+    let key = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Sec-WebSocket-Key"))
+        .map(|h| tungstenite::handshake::derive_accept_key(h.value.as_bytes()));
+    let url = request.url().to_owned();
+    let result = match (url.as_str(), key) {
+        ("/ws", Some(accept)) => {
+            let response = Response::empty(101).with_header(
+                Header::from_bytes("Sec-WebSocket-Accept", accept).expect("valid header"),
+            );
+            let stream = request.upgrade("websocket", response);
+            return Some(WebSocket::from_raw_socket(stream, Role::Server, None));
+        }
+        ("/", _) => request.respond(Response::from_string(PAGE).with_header(
+            Header::from_bytes("Content-Type", "text/html; charset=utf-8").expect("valid header"),
+        )),
+        _ => request.respond(Response::empty(404)),
+    };
+    if let Err(e) = result {
+        warn!("cannot answer an HTTP request: {e}");
+    }
+    None
+}
+
+impl Sink for WebSocketSink {
+    fn emit(&mut self, notification: &Notification, remaining: TimeSpan) -> SinkResult<()> {
+        self.message = notification.message.clone();
+        self.broadcast("notification", payload(notification, remaining));
+        Ok(())
+    }
+
+    fn update(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        let fields = serde_json::json!({
+            "departure_time": self.departure_time.to_rfc3339(),
+            "remaining_seconds": remaining.total_secs(),
+            "remaining": remaining.to_string(),
+            "urgency": Urgency::for_remaining(&remaining).name(),
+            "message": self.message,
+        });
+        self.broadcast("state", fields);
+        Ok(())
+    }
+
+    fn wants_updates(&self) -> bool {
+        true
+    }
+
+    fn finish(&mut self, remaining: TimeSpan) -> SinkResult<()> {
+        self.broadcast(
+            "end",
+            serde_json::json!({ "remaining_seconds": remaining.total_secs() }),
+        );
+        for mut client in self.clients.lock().expect("clients lock").drain(..) {
+            let _ = client.close(None).and_then(|_| client.flush());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WebSocketSink {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(accepting) = self.accepting.take() {
+            let _ = accepting.join();
+        }
+    }
+}
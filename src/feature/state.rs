@@ -0,0 +1,201 @@
+//! State of the running session, saved to a file at every tick so that the countdown can be
+//! resumed where it left off after a crash or a reboot.
+
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, ResultExt};
+
+use crate::common::data_dir;
+use crate::plan::{Notification, Plan, Urgency};
+use crate::time::{TimeSpan, Timestamp};
+
+#[derive(Debug, thiserror::Error)]
+#[error("session state error")]
+pub struct StateError;
+
+pub type StateResult<T> = Result<T, Report<StateError>>;
+
+/// What is needed to resume a session.
+#[derive(Debug, Clone)]
+pub struct SessionState {
+    pub plan: Plan,
+    /// Notifications emitted so far, latest first
+    pub emitted: Vec<Notification>,
+    pub snoozes: u32,
+    pub snoozed_until: Option<Timestamp>,
+    pub muted: bool,
+}
+
+impl SessionState {
+    pub fn to_json(&self) -> serde_json::Value {
+        let emitted: Vec<_> = self
+            .emitted
+            .iter()
+            .map(|n| {
+                serde_json::json!({
+                    "time": n.time.to_rfc3339(),
+                    "message": n.message,
+                    "urgency": n.urgency.name(),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "rendezvous_time": self.plan.rendezvous_time.to_rfc3339(),
+            "trip_seconds": self.plan.trip_duration.total_secs(),
+            "emitted": emitted,
+            "snoozes": self.snoozes,
+            "snoozed_until": self.snoozed_until.map(|until| until.to_rfc3339()),
+            "muted": self.muted,
+        })
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> StateResult<Self> {
+        let timestamp = |value: &serde_json::Value| {
+            value
+                .as_str()
+                .ok_or(Report::new(StateError))
+                .and_then(|value| Timestamp::parse(value).change_context(StateError))
+        };
+        let plan = Plan {
+            rendezvous_time: timestamp(&json["rendezvous_time"])
+                .attach("invalid rendezvous time")?,
+            trip_duration: json["trip_seconds"]
+                .as_u64()
+                .map(TimeSpan::of_seconds)
+                .ok_or(StateError)
+                .attach("invalid trip duration")?,
+        };
+        let emitted = json["emitted"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|n| {
+                Ok(Notification {
+                    time: timestamp(&n["time"]).attach("invalid time of a notification")?,
+                    message: n["message"].as_str().unwrap_or_default().to_owned(),
+                    urgency: n["urgency"]
+                        .as_str()
+                        .and_then(Urgency::from_name)
+                        .unwrap_or(Urgency::Low),
+                })
+            })
+            .collect::<StateResult<_>>()?;
+        let snoozed_until = match &json["snoozed_until"] {
+            serde_json::Value::Null => None,
+            until => Some(timestamp(until).attach("invalid end of the snooze")?),
+        };
+        Ok(Self {
+            plan,
+            emitted,
+            snoozes: json["snoozes"].as_u64().unwrap_or_default() as u32,
+            snoozed_until,
+            muted: json["muted"].as_bool().unwrap_or_default(),
+        })
+    }
+}
+
+/// File the state of the session is saved to.
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+    /// Content last saved, not to write the same again at every tick
+    saved: Option<String>,
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            saved: None,
+        }
+    }
+
+    /// `session.json` in the data directory of the user, beside the history.
+    pub fn default_path() -> PathBuf {
+        data_dir().join("session.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `state`, unless unchanged since the last time. The file is replaced at once, never
+    /// left half written.
+    pub fn save(&mut self, state: &SessionState) -> StateResult<()> {
+        let content = state.to_json().to_string();
+        if self.saved.as_ref() == Some(&content) {
+            return Ok(());
+        }
+        // Only tried once for each change, not to fail again at every tick
+        self.saved = Some(content.clone());
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)
+                .change_context(StateError)
+                .attach(format!("cannot create {}", dir.display()))?;
+        }
+        let partial = self.path.with_extension("json.partial");
+        std::fs::write(&partial, content)
+            .and_then(|_| std::fs::rename(&partial, &self.path))
+            .change_context(StateError)
+            .attach(format!("cannot write {}", self.path.display()))
+    }
+
+    pub fn load(&self) -> StateResult<SessionState> {
+        let content = std::fs::read_to_string(&self.path)
+            .change_context(StateError)
+            .attach(format!("cannot read {}", self.path.display()))?;
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .change_context(StateError)
+            .attach(format!("invalid session state in {}", self.path.display()))?;
+        SessionState::from_json(&json)
+            .attach(format!("invalid session state in {}", self.path.display()))
+    }
+
+    /// Removes the file, once the session is over.
+    pub fn remove(&self) -> StateResult<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Report::new(e)
+                .change_context(StateError)
+                .attach(format!("cannot remove {}", self.path.display()))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_file_keeps_the_session() {
+        let path =
+            std::env::temp_dir().join(format!("rendezvous-state-{}.json", std::process::id()));
+        let mut file = StateFile::new(&path);
+        let state = SessionState {
+            plan: Plan {
+                rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+                trip_duration: TimeSpan::of_minutes(15),
+            },
+            emitted: vec![Notification {
+                time: Timestamp::new(2025, 10, 24, 19, 30, 00).unwrap(),
+                message: "Mancano 15 minuti".to_owned(),
+                urgency: Urgency::Medium,
+            }],
+            snoozes: 1,
+            snoozed_until: Some(Timestamp::new(2025, 10, 24, 19, 35, 00).unwrap()),
+            muted: false,
+        };
+
+        file.save(&state).unwrap();
+        let loaded = file.load().unwrap();
+        file.remove().unwrap();
+
+        assert_eq!(state.plan.departure_time(), loaded.plan.departure_time());
+        assert_eq!(state.emitted, loaded.emitted);
+        assert_eq!(1, loaded.snoozes);
+        assert_eq!(state.snoozed_until, loaded.snoozed_until);
+        assert!(!loaded.muted);
+        assert!(file.load().is_err());
+    }
+}
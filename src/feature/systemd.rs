@@ -0,0 +1,220 @@
+//! Generation of the systemd user units starting the coach before a recurring rendezvous.
+
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, ResultExt};
+
+use crate::time::TimeSpan;
+
+#[derive(Debug, thiserror::Error)]
+#[error("systemd error")]
+pub struct SystemdError;
+
+pub type SystemdResult<T> = Result<T, Report<SystemdError>>;
+
+/// A rendezvous repeating on some days, at the same time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Name of the units, e.g. `school`
+    pub profile: String,
+    /// Days of the week, in systemd calendar syntax (e.g. `Mon..Fri`), or every day
+    pub days: Option<String>,
+    /// Rendezvous time, from midnight
+    pub rendezvous: TimeSpan,
+    pub trip: TimeSpan,
+    /// How long before departure the coach starts
+    pub lead: TimeSpan,
+}
+
+impl Schedule {
+    /// Time of the day the coach starts at.
+    pub fn start(&self) -> SystemdResult<TimeSpan> {
+        let ahead = self.trip + self.lead;
+        if ahead > self.rendezvous {
+            return Err(Report::new(SystemdError).attach(format!(
+                "the coach would start the day before the rendezvous at {}",
+                self.rendezvous
+            )));
+        }
+        Ok(self.rendezvous - ahead)
+    }
+
+    fn unit_name(&self) -> String {
+        format!("rendezvous-coach-{}", self.profile)
+    }
+}
+
+/// A service running the coach for a schedule, and the timer starting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Units {
+    pub service_name: String,
+    pub service: String,
+    pub timer_name: String,
+    pub timer: String,
+}
+
+impl Units {
+    /// The units running `program` with `args` (besides the rendezvous and trip) for `schedule`.
+    pub fn new(schedule: &Schedule, program: &Path, args: &[String]) -> SystemdResult<Self> {
+        if schedule.profile.is_empty()
+            || !schedule
+                .profile
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Report::new(SystemdError).attach(format!(
+                "invalid profile `{}`, use letters, digits, - and _",
+                schedule.profile
+            )));
+        }
+        let start = schedule.start()?;
+        let name = schedule.unit_name();
+        let hhmm = |span: TimeSpan| format!("{:02}:{:02}", span.hours(), span.minutes());
+        let command = [
+            program.display().to_string(),
+            // Without a terminal
+            "--output".to_owned(),
+            "json".to_owned(),
+            "--rendezvous".to_owned(),
+            hhmm(schedule.rendezvous),
+            "--trip".to_owned(),
+            hhmm(schedule.trip),
+        ]
+        .iter()
+        .chain(args)
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+        let calendar = match &schedule.days {
+            Some(days) => format!("{days} {start}"),
+            None => format!("*-*-* {start}"),
+        };
+
+        let service = format!(
+            "[Unit]\n\
+             Description=Rendezvous coach ({profile})\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={command}\n",
+            profile = schedule.profile,
+        );
+        let timer = format!(
+            "[Unit]\n\
+             Description=Start the rendezvous coach ({profile}) {lead} before departure\n\
+             \n\
+             [Timer]\n\
+             OnCalendar={calendar}\n\
+             AccuracySec=1s\n\
+             Persistent=true\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            profile = schedule.profile,
+            lead = hhmm(schedule.lead),
+        );
+        Ok(Self {
+            service_name: format!("{name}.service"),
+            service,
+            timer_name: format!("{name}.timer"),
+            timer,
+        })
+    }
+
+    /// Writes the units to `dir`, returning their paths.
+    pub fn install(&self, dir: &Path) -> SystemdResult<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)
+            .change_context(SystemdError)
+            .attach(format!("cannot create {}", dir.display()))?;
+        [
+            (&self.service_name, &self.service),
+            (&self.timer_name, &self.timer),
+        ]
+        .into_iter()
+        .map(|(name, content)| {
+            let path = dir.join(name);
+            std::fs::write(&path, content)
+                .change_context(SystemdError)
+                .attach(format!("cannot write {}", path.display()))?;
+            Ok(path)
+        })
+        .collect()
+    }
+}
+
+/// The directory of the systemd user units, e.g. `~/.config/systemd/user`.
+pub fn user_unit_dir() -> SystemdResult<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("systemd").join("user"))
+        .ok_or_else(|| Report::new(SystemdError).attach("no configuration directory"))
+}
+
+/// `arg` as a word of an `ExecStart` command line.
+fn quote(arg: &str) -> String {
+    // Specifiers and variables would be expanded
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty()
+        && !escaped
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'))
+    {
+        return escaped;
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn school() -> Schedule {
+        Schedule {
+            profile: "school".to_owned(),
+            days: Some("Mon..Fri".to_owned()),
+            rendezvous: TimeSpan::new(8, 0, 0),
+            trip: TimeSpan::of_minutes(20),
+            lead: TimeSpan::of_hours(1),
+        }
+    }
+
+    #[test]
+    fn units_start_the_coach_ahead_of_departure() {
+        let args = ["--voice".to_owned(), "Alice (it)".to_owned()];
+        let units = Units::new(&school(), Path::new("/usr/bin/rendezvous-coach"), &args).unwrap();
+
+        assert_eq!("rendezvous-coach-school.service", units.service_name);
+        assert!(units.service.contains(
+            "ExecStart=/usr/bin/rendezvous-coach --output json --rendezvous 08:00 --trip 00:20 --voice \"Alice (it)\"\n"
+        ));
+        assert_eq!("rendezvous-coach-school.timer", units.timer_name);
+        assert!(units.timer.contains("OnCalendar=Mon..Fri 06:40:00\n"));
+    }
+
+    #[test]
+    fn units_for_a_schedule_starting_the_day_before() {
+        let schedule = Schedule {
+            rendezvous: TimeSpan::of_minutes(30),
+            ..school()
+        };
+
+        assert!(Units::new(&schedule, Path::new("rendezvous-coach"), &[]).is_err());
+    }
+
+    #[test]
+    fn units_for_an_invalid_profile() {
+        let schedule = Schedule {
+            profile: "../school".to_owned(),
+            ..school()
+        };
+
+        assert!(Units::new(&schedule, Path::new("rendezvous-coach"), &[]).is_err());
+    }
+
+    #[test]
+    fn quote_exec_start_words() {
+        assert_eq!("--volume", quote("--volume"));
+        assert_eq!("100%%", quote("100%"));
+        assert_eq!("\"a \\\"b\\\"\"", quote("a \"b\""));
+        assert_eq!("\"\"", quote(""));
+    }
+}
@@ -0,0 +1,93 @@
+//! Audio playback shared by the speaker backends
+
+use std::fs::File;
+use std::num::NonZero;
+use std::path::Path;
+use std::time::Duration;
+
+use error_stack::ResultExt;
+use rodio::source::SineWave;
+use rodio::{Decoder, DeviceSinkBuilder, MixerDeviceSink, Player, Source, buffer::SamplesBuffer};
+
+use super::{CancelWatch, SpeakerError, SpeakerResult};
+
+/// How often playback checks whether it is over
+const PLAYBACK_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Plays mono samples on the default output device, until the end or until cancelled.
+pub(crate) async fn play_samples(
+    samples: Vec<f32>,
+    sample_rate: u32,
+    volume: f32,
+    cancel: &CancelWatch,
+) -> SpeakerResult<()> {
+    let source = SamplesBuffer::new(
+        NonZero::new(1u16).unwrap(),
+        NonZero::new(sample_rate).unwrap(),
+        samples,
+    );
+    play(source, volume, cancel).await
+}
+
+/// Decodes an audio file and plays it on the default output device, until the end or until
+/// cancelled.
+pub(crate) async fn play_file(path: &Path, volume: f32, cancel: &CancelWatch) -> SpeakerResult<()> {
+    let file = File::open(path)
+        .change_context(SpeakerError)
+        .attach(format!("cannot open audio file: {}", path.display()))?;
+    let source = Decoder::try_from(file)
+        .change_context(SpeakerError)
+        .attach(format!("cannot decode audio file: {}", path.display()))?;
+    play(source, volume, cancel).await
+}
+
+/// Plays a short two-tone chime on the default output device, until the end or until cancelled.
+pub(crate) async fn play_builtin_chime(volume: f32, cancel: &CancelWatch) -> SpeakerResult<()> {
+    let (_handle, player) = open_player(volume)?;
+    for frequency in [880.0, 660.0] {
+        let tone_duration = Duration::from_millis(250);
+        player.append(
+            SineWave::new(frequency)
+                .take_duration(tone_duration)
+                .fade_out(tone_duration)
+                .amplify(0.4),
+        );
+    }
+    until_played(&player, cancel).await;
+    Ok(())
+}
+
+async fn play<S: Source + Send + 'static>(
+    source: S,
+    volume: f32,
+    cancel: &CancelWatch,
+) -> SpeakerResult<()> {
+    let (_handle, player) = open_player(volume)?;
+    player.append(source);
+    until_played(&player, cancel).await;
+    Ok(())
+}
+
+/// Waits until `player` has played everything, or stops it when cancelled.
+async fn until_played(player: &Player, cancel: &CancelWatch) {
+    let played = async {
+        while !player.empty() {
+            tokio::time::sleep(PLAYBACK_POLL_INTERVAL).await;
+        }
+    };
+    tokio::select! {
+        () = played => {}
+        () = cancel.cancelled() => player.stop(),
+    }
+}
+
+/// Opens the default output device; the player works as long as the device handle is alive.
+fn open_player(volume: f32) -> SpeakerResult<(MixerDeviceSink, Player)> {
+    let mut handle = DeviceSinkBuilder::open_default_sink()
+        .change_context(SpeakerError)
+        .attach("cannot open the audio output device")?;
+    handle.log_on_drop(false);
+    let player = Player::connect_new(handle.mixer());
+    player.set_volume(volume);
+    Ok((handle, player))
+}
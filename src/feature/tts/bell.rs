@@ -0,0 +1,129 @@
+use std::io::Write;
+use std::time::Duration;
+
+use error_stack::ResultExt;
+
+use super::{
+    CancelWatch, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance,
+    Voice,
+};
+use crate::plan::Urgency;
+
+const BELL: &[u8] = b"\x07";
+/// Pause between bells, so that terminals ring each one
+const BELL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Speaker ringing the terminal bell instead of speaking, more times as departure approaches.
+///
+/// Works over SSH and on headless machines, where there is neither audio nor a desktop.
+pub struct BellSpeaker<W> {
+    out: W,
+}
+
+impl<W: Write> BellSpeaker<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Rings the bell `times` times, stopping when cancelled.
+    async fn ring(&mut self, times: usize, cancel: &CancelWatch) -> SpeakerResult<()> {
+        for i in 0..times {
+            if i > 0 {
+                tokio::time::sleep(BELL_INTERVAL).await;
+            }
+            if cancel.is_cancelled() {
+                break;
+            }
+            self.out
+                .write_all(BELL)
+                .and_then(|_| self.out.flush())
+                .change_context(SpeakerError)
+                .attach("cannot ring the terminal bell")?;
+        }
+        Ok(())
+    }
+}
+
+/// How many times the bell rings for each urgency.
+fn bells(urgency: Urgency) -> usize {
+    match urgency {
+        Urgency::Low => 1,
+        Urgency::Medium => 2,
+        Urgency::High => 3,
+        Urgency::Critical => 5,
+    }
+}
+
+impl<W: Write> Speaker for BellSpeaker<W> {
+    fn speak<'a>(&'a mut self, _content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(self.ring(1, cancel))
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(self.ring(bells(utterance.urgency), cancel))
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        Ok(vec![])
+    }
+
+    fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+        Ok(())
+    }
+
+    fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feature::tts::CancelToken;
+    use crate::time::TimeSpan;
+
+    #[tokio::test]
+    async fn bell_speaker_rings_more_as_departure_approaches() {
+        let mut speaker = BellSpeaker::new(Vec::new());
+        let cancel = CancelWatch::never();
+
+        speaker
+            .announce(&Utterance::countdown("1h", TimeSpan::of_hours(1)), &cancel)
+            .await
+            .unwrap();
+        assert_eq!(b"\x07".to_vec(), speaker.out);
+
+        speaker.out.clear();
+        speaker
+            .announce(
+                &Utterance::countdown("4m", TimeSpan::of_minutes(4)),
+                &cancel,
+            )
+            .await
+            .unwrap();
+        assert_eq!(b"\x07\x07\x07".to_vec(), speaker.out);
+    }
+
+    #[tokio::test]
+    async fn bell_speaker_stops_ringing_when_cancelled() {
+        let mut speaker = BellSpeaker::new(Vec::new());
+        let token = CancelToken::default();
+        let cancel = token.watch();
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(BELL_INTERVAL / 2).await;
+            canceller.cancel();
+        });
+        speaker
+            .announce(&Utterance::countdown("go", TimeSpan::ZERO), &cancel)
+            .await
+            .unwrap();
+
+        assert_eq!(b"\x07".to_vec(), speaker.out);
+    }
+}
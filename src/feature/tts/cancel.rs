@@ -0,0 +1,101 @@
+//! Cancellation of the messages being spoken, e.g. when a more urgent one comes
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::sync::Notify;
+
+#[derive(Debug, Default)]
+struct Shared {
+    generation: AtomicU64,
+    cancelled: Notify,
+}
+
+/// Interrupts the messages being spoken with the watches it gave.
+///
+/// Only the messages already started when [`cancel`](Self::cancel) is called are interrupted:
+/// the following ones are spoken as usual.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    shared: Arc<Shared>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.shared.generation.fetch_add(1, Ordering::SeqCst);
+        self.shared.cancelled.notify_waiters();
+    }
+
+    /// Starts watching for cancellation, e.g. when a message starts playing.
+    pub fn watch(&self) -> CancelWatch {
+        CancelWatch {
+            token: self.clone(),
+            generation: self.shared.generation.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Tells whether a [`CancelToken`] has been cancelled since the watch started.
+#[derive(Debug, Clone)]
+pub struct CancelWatch {
+    token: CancelToken,
+    generation: u64,
+}
+
+impl CancelWatch {
+    /// A watch nobody can cancel, to speak a message until the end.
+    pub fn never() -> Self {
+        CancelToken::default().watch()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.shared.generation.load(Ordering::SeqCst) != self.generation
+    }
+
+    /// Resolves once cancelled, to race against the speech.
+    pub async fn cancelled(&self) {
+        loop {
+            // Woken by the cancellations from now on, even before being polled
+            let cancelled = self.token.shared.cancelled.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            cancelled.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn cancel_interrupts_only_the_started_watches() {
+        let token = CancelToken::default();
+        let started = token.watch();
+
+        token.cancel();
+
+        assert!(started.is_cancelled());
+        assert!(!token.watch().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn cancelled_resolves_on_cancel() {
+        let token = CancelToken::default();
+        let watch = token.watch();
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), watch.cancelled())
+            .await
+            .unwrap();
+        assert!(!CancelWatch::never().is_cancelled());
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use super::audio;
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+
+/// A short sound to catch the listeners' attention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chime {
+    /// Two-tone chime bundled with the application
+    Builtin,
+    /// Any audio file supported by the player (WAV, FLAC, MP3, Ogg Vorbis)
+    File(PathBuf),
+}
+
+impl Chime {
+    /// Plays the chime, until the end or until cancelled.
+    pub async fn play(&self, volume: f32, cancel: &CancelWatch) -> SpeakerResult<()> {
+        match self {
+            Chime::Builtin => audio::play_builtin_chime(volume, cancel).await,
+            Chime::File(path) => audio::play_file(path, volume, cancel).await,
+        }
+    }
+}
+
+/// Speaker playing a chime just before each message.
+pub struct ChimeSpeaker<S> {
+    inner: S,
+    chime: Chime,
+    volume: f32,
+}
+
+impl<S: Speaker> ChimeSpeaker<S> {
+    pub fn new(inner: S, chime: Chime) -> Self {
+        Self {
+            inner,
+            chime,
+            volume: SpeechOptions::default().volume,
+        }
+    }
+}
+
+impl<S: Speaker> Speaker for ChimeSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            self.chime.play(self.volume, cancel).await?;
+            self.inner.speak(content, cancel).await
+        })
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            self.chime.play(self.volume, cancel).await?;
+            self.inner.announce(utterance, cancel).await
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.volume = options.volume;
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
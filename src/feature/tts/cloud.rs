@@ -0,0 +1,292 @@
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use error_stack::{Report, ResultExt};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+
+use super::audio;
+use super::{
+    CancelWatch, Recorder, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions,
+    Voice,
+};
+
+const SYNTHESIZE_URL: &str = "https://texttospeech.googleapis.com/v1/text:synthesize";
+const VOICES_URL: &str = "https://texttospeech.googleapis.com/v1/voices";
+
+const DEFAULT_VOICE: &str = "it-IT-Standard-A";
+
+/// Speaker backed by the Google Cloud Text-to-Speech API.
+///
+/// Every message is synthesized only once per voice: the audio is cached on disk and replayed
+/// from there in subsequent sessions.
+pub struct CloudSpeaker {
+    api_key: String,
+    voice: String,
+    options: SpeechOptions,
+    cache: AudioCache,
+    recorder: Option<Recorder>,
+}
+
+impl CloudSpeaker {
+    pub fn new(api_key: String) -> SpeakerResult<Self> {
+        let cache = AudioCache::new(default_cache_dir())?;
+        info!("Using cloud TTS, audio cache in {}", cache.dir.display());
+        Ok(Self {
+            api_key,
+            voice: DEFAULT_VOICE.to_owned(),
+            options: SpeechOptions::default(),
+            cache,
+            recorder: None,
+        })
+    }
+
+    /// Records each message spoken.
+    pub fn with_recorder(self, recorder: Recorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..self
+        }
+    }
+
+    fn synthesize(&self, input: Input) -> SpeakerResult<Vec<u8>> {
+        let input = match input {
+            Input::Text(text) => serde_json::json!({ "text": text }),
+            Input::Ssml(ssml) => serde_json::json!({ "ssml": ssml }),
+        };
+        let body = serde_json::json!({
+            "input": input,
+            "voice": { "languageCode": language_code(&self.voice), "name": self.voice },
+            "audioConfig": {
+                "audioEncoding": "LINEAR16",
+                "speakingRate": self.options.rate.clamp(0.25, 4.0),
+                "pitch": self.options.semitones().clamp(-20.0, 20.0),
+            },
+        });
+        let response: SynthesizeResponse = ureq::post(SYNTHESIZE_URL)
+            .query("key", &self.api_key)
+            .send_json(body)
+            .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?
+            .into_json()
+            .change_context(SpeakerError)
+            .attach("invalid cloud TTS response")?;
+        BASE64
+            .decode(response.audio_content)
+            .change_context(SpeakerError)
+            .attach("invalid cloud TTS audio content")
+    }
+}
+
+impl CloudSpeaker {
+    /// Path of the cached audio for `input`, synthesizing it first if needed.
+    fn cached(&self, input: Input) -> SpeakerResult<PathBuf> {
+        let path = self
+            .cache
+            .path_for(&self.voice, &self.options, input.content());
+        if path.exists() {
+            debug!("Cloud TTS cache hit: {}", path.display());
+        } else {
+            let audio = self.synthesize(input)?;
+            self.cache.store(&path, &audio)?;
+        }
+        Ok(path)
+    }
+
+    async fn play(&self, input: Input<'_>, cancel: &CancelWatch) -> SpeakerResult<()> {
+        let path = self.cached(input)?;
+        if let Some(recorder) = &self.recorder
+            && let Err(report) = recorder.record_file(input.content(), &path)
+        {
+            warn!("cannot record `{}`: {report:?}", input.content());
+        }
+        audio::play_file(&path, self.options.volume, cancel).await
+    }
+}
+
+impl Speaker for CloudSpeaker {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(self.play(Input::Text(content), cancel))
+    }
+
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(self.play(Input::Ssml(ssml), cancel))
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        let response: VoicesResponse = ureq::get(VOICES_URL)
+            .query("key", &self.api_key)
+            .call()
+            .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?
+            .into_json()
+            .change_context(SpeakerError)
+            .attach("invalid cloud TTS response")?;
+        let voices = response
+            .voices
+            .into_iter()
+            .flat_map(|v| {
+                let name = v.name;
+                v.language_codes
+                    .into_iter()
+                    .map(move |l| Voice::new(name.clone(), l))
+            })
+            .collect();
+        Ok(voices)
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.voice = name.to_owned();
+        Ok(())
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.options = options;
+        Ok(())
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        Box::pin(async move { self.cached(Input::Text(content)).map(|_| ()) })
+    }
+}
+
+/// What to synthesize: plain text or SSML markup.
+#[derive(Clone, Copy)]
+enum Input<'a> {
+    Text(&'a str),
+    Ssml(&'a str),
+}
+
+impl Input<'_> {
+    fn content(&self) -> &str {
+        match self {
+            Input::Text(content) | Input::Ssml(content) => content,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SynthesizeResponse {
+    audio_content: String,
+}
+
+#[derive(serde::Deserialize)]
+struct VoicesResponse {
+    voices: Vec<VoiceDescription>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VoiceDescription {
+    name: String,
+    language_codes: Vec<String>,
+}
+
+/// Language code of a voice name, e.g. `it-IT` for `it-IT-Standard-A`.
+fn language_code(voice: &str) -> &str {
+    match voice.match_indices('-').nth(1) {
+        Some((idx, _)) => &voice[..idx],
+        None => voice,
+    }
+}
+
+fn default_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| {
+            std::env::var("HOME")
+                .map(PathBuf::from)
+                .unwrap_or_default()
+                .join(".cache")
+        })
+        .join("rendezvous-coach")
+        .join("tts")
+}
+
+/// On-disk cache of synthesized audio, keyed by voice, rate, pitch and text.
+struct AudioCache {
+    dir: PathBuf,
+}
+
+impl AudioCache {
+    fn new(dir: PathBuf) -> SpeakerResult<Self> {
+        std::fs::create_dir_all(&dir)
+            .change_context(SpeakerError)
+            .attach(format!("cannot create TTS cache dir: {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    fn path_for(&self, voice: &str, options: &SpeechOptions, content: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(voice.as_bytes());
+        hasher.update([0]);
+        // Volume is applied when playing, so it is not part of the key.
+        hasher.update(options.rate.to_le_bytes());
+        hasher.update(options.pitch.to_le_bytes());
+        hasher.update(content.as_bytes());
+        let key: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        self.dir.join(format!("{key}.wav"))
+    }
+
+    fn store(&self, path: &Path, audio: &[u8]) -> SpeakerResult<()> {
+        // Write to a temporary file first, so that an interrupted write never leaves a truncated
+        // entry in the cache.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, audio)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .change_context(SpeakerError)
+            .attach(format!("cannot write TTS cache entry: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> AudioCache {
+        AudioCache {
+            dir: PathBuf::from("/cache"),
+        }
+    }
+
+    #[test]
+    fn language_code_of_voice_name() {
+        assert_eq!("it-IT", language_code("it-IT-Standard-A"));
+        assert_eq!("en-US", language_code("en-US-Wavenet-D"));
+        assert_eq!("it-IT", language_code("it-IT"));
+    }
+
+    #[test]
+    fn cache_path_is_stable_for_the_same_voice_and_text() {
+        let options = SpeechOptions::default();
+        let first = cache().path_for("it-IT-Standard-A", &options, "Manca 1 minuto");
+        let second = cache().path_for("it-IT-Standard-A", &options, "Manca 1 minuto");
+
+        assert_eq!(first, second);
+        assert!(first.starts_with("/cache"));
+        assert_eq!(Some("wav"), first.extension().and_then(|e| e.to_str()));
+    }
+
+    #[test]
+    fn cache_path_depends_on_voice_options_and_text() {
+        let options = SpeechOptions::default();
+        let slower = SpeechOptions {
+            rate: 0.8,
+            ..options
+        };
+        let louder = SpeechOptions {
+            volume: 0.5,
+            ..options
+        };
+        let path = |voice, options, content| cache().path_for(voice, options, content);
+        let base = path("it-IT-Standard-A", &options, "Manca 1 minuto");
+
+        assert_ne!(base, path("it-IT-Standard-B", &options, "Manca 1 minuto"));
+        assert_ne!(base, path("it-IT-Standard-A", &options, "Mancano 2 minuti"));
+        assert_ne!(base, path("it-IT-Standard-A", &slower, "Manca 1 minuto"));
+        assert_eq!(base, path("it-IT-Standard-A", &louder, "Manca 1 minuto"));
+    }
+}
@@ -0,0 +1,228 @@
+use std::str::FromStr;
+
+use super::{
+    CancelWatch, Chime, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice,
+};
+use crate::plan::Urgency;
+
+/// One of the ways of delivering a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Chime,
+    Speech,
+    Bell,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "chime" => Ok(Output::Chime),
+            "speech" => Ok(Output::Speech),
+            "bell" => Ok(Output::Bell),
+            _ => Err(format!(
+                "invalid output `{input}`, must be chime, speech or bell"
+            )),
+        }
+    }
+}
+
+/// Outputs for each urgency level, delivered in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Composition {
+    low: Vec<Output>,
+    medium: Vec<Output>,
+    high: Vec<Output>,
+    critical: Vec<Output>,
+}
+
+impl Composition {
+    pub fn new(
+        low: Vec<Output>,
+        medium: Vec<Output>,
+        high: Vec<Output>,
+        critical: Vec<Output>,
+    ) -> Self {
+        Self {
+            low,
+            medium,
+            high,
+            critical,
+        }
+    }
+
+    pub fn outputs(&self, urgency: Urgency) -> &[Output] {
+        match urgency {
+            Urgency::Low => &self.low,
+            Urgency::Medium => &self.medium,
+            Urgency::High => &self.high,
+            Urgency::Critical => &self.critical,
+        }
+    }
+}
+
+impl FromStr for Composition {
+    type Err = String;
+
+    /// Parses four comma separated levels, from low to critical urgency, each made of outputs
+    /// joined by `+`, or `none` (e.g. `speech,speech,chime+speech,chime+speech+bell`).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let levels = input
+            .split(',')
+            .map(|level| match level.trim() {
+                "none" => Ok(vec![]),
+                level => level.split('+').map(str::parse).collect(),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match <[Vec<Output>; 4]>::try_from(levels) {
+            Ok([low, medium, high, critical]) => Ok(Self::new(low, medium, high, critical)),
+            Err(_) => Err("expected 4 levels: low, medium, high and critical urgency".to_owned()),
+        }
+    }
+}
+
+/// Speaker delivering each utterance through the outputs configured for its urgency.
+pub struct ComposedSpeaker<S, B> {
+    inner: S,
+    bell: B,
+    chime: Chime,
+    composition: Composition,
+    volume: f32,
+}
+
+impl<S: Speaker, B: Speaker> ComposedSpeaker<S, B> {
+    pub fn new(inner: S, bell: B, chime: Chime, composition: Composition) -> Self {
+        Self {
+            inner,
+            bell,
+            chime,
+            composition,
+            volume: SpeechOptions::default().volume,
+        }
+    }
+}
+
+impl<S: Speaker, B: Speaker> Speaker for ComposedSpeaker<S, B> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        self.inner.speak(content, cancel)
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            for output in self.composition.outputs(utterance.urgency) {
+                match output {
+                    Output::Chime => self.chime.play(self.volume, cancel).await?,
+                    Output::Speech => self.inner.announce(utterance, cancel).await?,
+                    Output::Bell => self.bell.announce(utterance, cancel).await?,
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.volume = options.volume;
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::TimeSpan;
+
+    #[derive(Default)]
+    struct RecordingSpeaker {
+        spoken: Vec<String>,
+    }
+
+    impl Speaker for RecordingSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                self.spoken.push(content.to_owned());
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn composition_parse() {
+        let composition: Composition = "speech, none,speech+bell,BELL+speech".parse().unwrap();
+
+        assert_eq!(
+            Composition::new(
+                vec![Output::Speech],
+                vec![],
+                vec![Output::Speech, Output::Bell],
+                vec![Output::Bell, Output::Speech],
+            ),
+            composition
+        );
+    }
+
+    #[test]
+    fn composition_parse_invalid() {
+        assert!("speech,speech,speech".parse::<Composition>().is_err());
+        assert!("speech,speech,speech,siren".parse::<Composition>().is_err());
+        assert!("speech,speech,speech+,bell".parse::<Composition>().is_err());
+    }
+
+    #[tokio::test]
+    async fn composed_speaker_delivers_the_outputs_of_each_urgency() {
+        let composition = "speech,none,bell,speech+bell".parse().unwrap();
+        let mut speaker = ComposedSpeaker::new(
+            RecordingSpeaker::default(),
+            RecordingSpeaker::default(),
+            Chime::Builtin,
+            composition,
+        );
+
+        for utterance in [
+            Utterance::countdown("low", TimeSpan::of_hours(1)),
+            Utterance::countdown("medium", TimeSpan::of_minutes(10)),
+            Utterance::countdown("high", TimeSpan::of_minutes(4)),
+            Utterance::countdown("critical", TimeSpan::ZERO),
+        ] {
+            speaker
+                .announce(&utterance, &CancelWatch::never())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(vec!["low", "critical"], speaker.inner.spoken);
+        assert_eq!(vec!["high", "critical"], speaker.bell.spoken);
+    }
+}
@@ -0,0 +1,190 @@
+use std::cmp::Reverse;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::audio;
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+use crate::time::TimeSpan;
+
+/// Sound to play once the remaining time drops to a threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoundCue {
+    pub threshold: TimeSpan,
+    pub file: PathBuf,
+}
+
+impl FromStr for SoundCue {
+    type Err = String;
+
+    /// Parses a remaining time and an audio file (e.g. `00:30=gentle.ogg`).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (threshold, file) = input
+            .split_once('=')
+            .ok_or_else(|| "expected HH:MM[:SS]=FILE".to_owned())?;
+        let threshold = TimeSpan::parse(threshold.trim())
+            .map_err(|_| format!("invalid remaining time `{threshold}`, expected HH:MM[:SS]"))?;
+        if file.trim().is_empty() {
+            return Err("missing sound file".to_owned());
+        }
+        Ok(Self {
+            threshold,
+            file: PathBuf::from(file.trim()),
+        })
+    }
+}
+
+/// Sound cues not played yet, from the farthest from departure to the closest.
+#[derive(Debug)]
+struct SoundCues {
+    cues: Vec<SoundCue>,
+    next: usize,
+}
+
+impl SoundCues {
+    fn new(mut cues: Vec<SoundCue>) -> Self {
+        cues.sort_by_key(|cue| Reverse(cue.threshold));
+        Self { cues, next: 0 }
+    }
+
+    /// The cue to play when `remaining` is left, if any threshold has just been reached.
+    ///
+    /// When several thresholds are reached at once, only the closest to departure is played.
+    fn reached(&mut self, remaining: &TimeSpan) -> Option<&SoundCue> {
+        let reached = self.cues[self.next..]
+            .iter()
+            .take_while(|cue| cue.threshold >= *remaining)
+            .count();
+        if reached == 0 {
+            return None;
+        }
+        self.next += reached;
+        self.cues.get(self.next - 1)
+    }
+}
+
+/// Speaker playing a sound when a remaining time update reaches one of the cue thresholds.
+///
+/// The cue is played before the message, or instead of it when `replace_speech` is set.
+pub struct SoundCueSpeaker<S> {
+    inner: S,
+    cues: SoundCues,
+    replace_speech: bool,
+    volume: f32,
+}
+
+impl<S: Speaker> SoundCueSpeaker<S> {
+    pub fn new(inner: S, cues: Vec<SoundCue>, replace_speech: bool) -> Self {
+        Self {
+            inner,
+            cues: SoundCues::new(cues),
+            replace_speech,
+            volume: SpeechOptions::default().volume,
+        }
+    }
+}
+
+impl<S: Speaker> Speaker for SoundCueSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        self.inner.speak(content, cancel)
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let cue = utterance
+                .remaining
+                .and_then(|remaining| self.cues.reached(&remaining));
+            if let Some(cue) = cue {
+                audio::play_file(&cue.file, self.volume, cancel).await?;
+                if self.replace_speech {
+                    return Ok(());
+                }
+            }
+            self.inner.announce(utterance, cancel).await
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.volume = options.volume;
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(threshold: TimeSpan, file: &str) -> SoundCue {
+        SoundCue {
+            threshold,
+            file: PathBuf::from(file),
+        }
+    }
+
+    fn file(cue: Option<&SoundCue>) -> Option<&str> {
+        cue.and_then(|cue| cue.file.to_str())
+    }
+
+    #[test]
+    fn sound_cue_parse() {
+        assert_eq!(
+            cue(TimeSpan::of_minutes(30), "gentle.ogg"),
+            "00:30=gentle.ogg".parse().unwrap()
+        );
+        assert_eq!(
+            cue(TimeSpan::of_seconds(90), "/sounds/alarm.wav"),
+            "00:01:30=/sounds/alarm.wav".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn sound_cue_parse_invalid() {
+        assert!("gentle.ogg".parse::<SoundCue>().is_err());
+        assert!("30=gentle.ogg".parse::<SoundCue>().is_err());
+        assert!("00:30=".parse::<SoundCue>().is_err());
+    }
+
+    #[test]
+    fn sound_cues_are_reached_once_in_order() {
+        let mut cues = SoundCues::new(vec![
+            cue(TimeSpan::ZERO, "alarm"),
+            cue(TimeSpan::of_minutes(30), "gentle"),
+        ]);
+
+        assert_eq!(None, file(cues.reached(&TimeSpan::of_minutes(40))));
+        assert_eq!(
+            Some("gentle"),
+            file(cues.reached(&TimeSpan::of_minutes(30)))
+        );
+        assert_eq!(None, file(cues.reached(&TimeSpan::of_minutes(20))));
+        assert_eq!(Some("alarm"), file(cues.reached(&TimeSpan::ZERO)));
+        assert_eq!(None, file(cues.reached(&TimeSpan::ZERO)));
+    }
+
+    #[test]
+    fn sound_cues_reached_together_play_the_closest_to_departure() {
+        let mut cues = SoundCues::new(vec![
+            cue(TimeSpan::of_minutes(30), "gentle"),
+            cue(TimeSpan::of_minutes(10), "louder"),
+            cue(TimeSpan::ZERO, "alarm"),
+        ]);
+
+        assert_eq!(Some("louder"), file(cues.reached(&TimeSpan::of_minutes(5))));
+        assert_eq!(Some("alarm"), file(cues.reached(&TimeSpan::ZERO)));
+    }
+}
@@ -0,0 +1,185 @@
+//! Media ducking through MPRIS, using `playerctl`
+
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use tracing::debug;
+
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+
+/// What to do with the playing media while a message is spoken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ducking {
+    Pause,
+    /// Lower the player volume to this level, from 0.0 to 1.0
+    Lower(f32),
+}
+
+impl FromStr for Ducking {
+    type Err = String;
+
+    /// Parses `pause`, or a volume level (e.g. `0.2`).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.eq_ignore_ascii_case("pause") {
+            return Ok(Ducking::Pause);
+        }
+        match input.parse::<f32>() {
+            Ok(volume) if (0.0..=1.0).contains(&volume) => Ok(Ducking::Lower(volume)),
+            _ => Err("expected `pause` or a volume between 0.0 and 1.0".to_owned()),
+        }
+    }
+}
+
+/// A player ducked while speaking, and how to restore it.
+struct Ducked {
+    player: String,
+    volume: Option<String>,
+}
+
+/// Speaker pausing or lowering the playing media players (e.g. music) while speaking, so that
+/// messages are not lost under them.
+///
+/// Players are controlled through `playerctl`; without it, messages are spoken as usual.
+pub struct DuckingSpeaker<S> {
+    inner: S,
+    ducking: Ducking,
+}
+
+impl<S: Speaker> DuckingSpeaker<S> {
+    pub fn new(inner: S, ducking: Ducking) -> Self {
+        Self { inner, ducking }
+    }
+}
+
+/// Ducks the players for as long as `speech` lasts, cancelled or not.
+fn ducked(ducking: Ducking, speech: SpeakerFuture<'_>) -> SpeakerFuture<'_> {
+    Box::pin(async move {
+        let ducked = duck(ducking);
+        let result = speech.await;
+        restore(&ducked);
+        result
+    })
+}
+
+impl<S: Speaker> Speaker for DuckingSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        ducked(self.ducking, self.inner.speak(content, cancel))
+    }
+
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        ducked(self.ducking, self.inner.speak_ssml(ssml, cancel))
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        ducked(self.ducking, self.inner.announce(utterance, cancel))
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
+
+fn duck(ducking: Ducking) -> Vec<Ducked> {
+    let Some(players) = playerctl(&["--list-all"]) else {
+        return vec![];
+    };
+    parse_players(&players)
+        .into_iter()
+        .filter(|player| {
+            playerctl(&["--player", player, "status"]).is_some_and(|s| s.trim() == "Playing")
+        })
+        .filter_map(|player| {
+            let volume = match ducking {
+                Ducking::Pause => {
+                    playerctl(&["--player", player, "pause"])?;
+                    None
+                }
+                Ducking::Lower(level) => {
+                    let volume = playerctl(&["--player", player, "volume"])?;
+                    playerctl(&["--player", player, "volume", &level.to_string()])?;
+                    Some(volume.trim().to_owned())
+                }
+            };
+            debug!("Ducked media player {player}");
+            Some(Ducked {
+                player: player.to_owned(),
+                volume,
+            })
+        })
+        .collect()
+}
+
+fn restore(ducked: &[Ducked]) {
+    for Ducked { player, volume } in ducked {
+        match volume {
+            Some(volume) => playerctl(&["--player", player, "volume", volume]),
+            None => playerctl(&["--player", player, "play"]),
+        };
+    }
+}
+
+/// Runs `playerctl`, returning its output when successful.
+fn playerctl(args: &[&str]) -> Option<String> {
+    let output = Command::new("playerctl")
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8(output.stdout).ok(),
+        Ok(output) => {
+            debug!("playerctl {args:?} failed: {}", output.status);
+            None
+        }
+        Err(e) => {
+            debug!("cannot run playerctl: {e}");
+            None
+        }
+    }
+}
+
+fn parse_players(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && *line != "No players found")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ducking_parse() {
+        assert_eq!(Ok(Ducking::Pause), "pause".parse());
+        assert_eq!(Ok(Ducking::Lower(0.2)), "0.2".parse());
+        assert!("1.5".parse::<Ducking>().is_err());
+        assert!("quiet".parse::<Ducking>().is_err());
+    }
+
+    #[test]
+    fn parse_players_skips_blank_lines() {
+        assert_eq!(
+            vec!["spotify", "firefox.instance_1_42"],
+            parse_players("spotify\nfirefox.instance_1_42\n\n")
+        );
+        assert!(parse_players("No players found\n").is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use error_stack::Report;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use super::{
+    CancelWatch, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance,
+    Voice, block_on,
+};
+
+/// How the speaker is doing, as seen by the last messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpeakerHealth {
+    /// Times the speaker has been restarted after a failure
+    pub restarts: u32,
+    /// Why the last message could not be spoken, if it could not
+    pub failure: Option<String>,
+}
+
+/// Shared view of the [`SpeakerHealth`], updated by a [`ResilientSpeaker`].
+#[derive(Debug, Clone, Default)]
+pub struct HealthMonitor(Arc<Mutex<SpeakerHealth>>);
+
+impl HealthMonitor {
+    pub fn current(&self) -> SpeakerHealth {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SpeakerHealth> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+type Factory<S> = Box<dyn FnMut() -> SpeakerResult<S> + Send>;
+type Job<S> = Arc<dyn Fn(&mut S, &CancelWatch) -> SpeakerResult<()> + Send + Sync>;
+
+/// Speaker restarting its backend when it fails or stops responding.
+///
+/// Each message is spoken on a separate thread, with a runtime of its own, so that a backend stuck
+/// in a blocking call can be left behind: when it errors or takes longer than `timeout`, the
+/// backend is dropped (or abandoned, if stuck), a new one is created by the factory with the same
+/// voice and options, and the message is tried once more.
+pub struct ResilientSpeaker<S> {
+    speaker: Option<Arc<Mutex<S>>>,
+    factory: Factory<S>,
+    timeout: Duration,
+    voice: Option<String>,
+    options: Option<SpeechOptions>,
+    health: HealthMonitor,
+}
+
+impl<S: Speaker + Send + 'static> ResilientSpeaker<S> {
+    pub fn new(
+        speaker: S,
+        factory: impl FnMut() -> SpeakerResult<S> + Send + 'static,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            speaker: Some(Arc::new(Mutex::new(speaker))),
+            factory: Box::new(factory),
+            timeout,
+            voice: None,
+            options: None,
+            health: HealthMonitor::default(),
+        }
+    }
+
+    pub fn health(&self) -> HealthMonitor {
+        self.health.clone()
+    }
+
+    /// The current backend, restarting it if the previous one failed.
+    fn speaker(&mut self) -> SpeakerResult<Arc<Mutex<S>>> {
+        if let Some(speaker) = &self.speaker {
+            return Ok(speaker.clone());
+        }
+        info!("Restarting the speaker");
+        self.health.lock().restarts += 1;
+        let mut speaker = (self.factory)()?;
+        if let Some(voice) = &self.voice {
+            speaker.set_voice(voice)?;
+        }
+        if let Some(options) = self.options {
+            speaker.set_options(options)?;
+        }
+        let speaker = Arc::new(Mutex::new(speaker));
+        self.speaker = Some(speaker.clone());
+        Ok(speaker)
+    }
+
+    async fn attempt(&mut self, job: &Job<S>, cancel: &CancelWatch) -> SpeakerResult<()> {
+        let speaker = self.speaker()?;
+        let job = job.clone();
+        let cancel = cancel.clone();
+        let (tx, rx) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("speech-attempt".to_owned())
+            .spawn(move || {
+                let mut speaker = speaker.lock().unwrap_or_else(PoisonError::into_inner);
+                let _ = tx.send(job(&mut *speaker, &cancel));
+            })
+            .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?;
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Err(_) => Err(Report::new(SpeakerError).attach(format!(
+                "the speaker did not respond within {}s",
+                self.timeout.as_secs()
+            ))),
+            Ok(Err(_)) => Err(Report::new(SpeakerError).attach("the speaker crashed")),
+        }
+    }
+
+    async fn run(&mut self, job: Job<S>, cancel: CancelWatch) -> SpeakerResult<()> {
+        let result = match self.attempt(&job, &cancel).await {
+            Ok(()) => Ok(()),
+            Err(report) => {
+                warn!("Speaker failed, restarting it: {report:?}");
+                self.speaker = None;
+                self.attempt(&job, &cancel).await
+            }
+        };
+        match &result {
+            Ok(()) => self.health.lock().failure = None,
+            Err(report) => {
+                self.speaker = None;
+                self.health.lock().failure = Some(failure_summary(report));
+            }
+        }
+        result
+    }
+}
+
+fn failure_summary(report: &Report<SpeakerError>) -> String {
+    report
+        .frames()
+        .find_map(|frame| {
+            frame
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| frame.downcast_ref::<&str>().map(|s| s.to_string()))
+        })
+        .unwrap_or_else(|| report.current_context().to_string())
+}
+
+impl<S: Speaker + Send + 'static> Speaker for ResilientSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        let content = content.to_owned();
+        let job: Job<S> = Arc::new(move |speaker: &mut S, cancel: &CancelWatch| {
+            block_on(speaker.speak(&content, cancel))
+        });
+        Box::pin(self.run(job, cancel.clone()))
+    }
+
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        let ssml = ssml.to_owned();
+        let job: Job<S> = Arc::new(move |speaker: &mut S, cancel: &CancelWatch| {
+            block_on(speaker.speak_ssml(&ssml, cancel))
+        });
+        Box::pin(self.run(job, cancel.clone()))
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        let utterance = utterance.clone();
+        let job: Job<S> = Arc::new(move |speaker: &mut S, cancel: &CancelWatch| {
+            block_on(speaker.announce(&utterance, cancel))
+        });
+        Box::pin(self.run(job, cancel.clone()))
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        match &self.speaker {
+            Some(speaker) => speaker
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .voices(),
+            None => Err(Report::new(SpeakerError).attach("the speaker is not running")),
+        }
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        let speaker = self.speaker()?;
+        speaker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_voice(name)?;
+        self.voice = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        let speaker = self.speaker()?;
+        speaker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_options(options)?;
+        self.options = Some(options);
+        Ok(())
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        let content = content.to_owned();
+        let job: Job<S> = Arc::new(move |speaker: &mut S, _cancel: &CancelWatch| {
+            block_on(speaker.prepare(&content))
+        });
+        Box::pin(self.run(job, CancelWatch::never()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// Speaker failing (or hanging) on the messages containing `fail` (or `hang`), when broken.
+    struct FlakySpeaker {
+        broken: bool,
+        voice: Option<String>,
+        spoken: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Speaker for FlakySpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                if self.broken && content.contains("fail") {
+                    return Err(Report::new(SpeakerError).attach("broken"));
+                }
+                if self.broken && content.contains("hang") {
+                    // Stuck for good, as a backend blocking its thread
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+                let voice = self.voice.clone().unwrap_or_default();
+                self.spoken
+                    .lock()
+                    .unwrap()
+                    .push(format!("{voice}:{content}"));
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+            self.voice = Some(name.to_owned());
+            Ok(())
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
+    }
+
+    fn flaky(broken: u32) -> (ResilientSpeaker<FlakySpeaker>, Arc<Mutex<Vec<String>>>) {
+        let spoken = Arc::new(Mutex::new(vec![]));
+        let created = Arc::new(AtomicU32::new(1));
+        let factory_spoken = spoken.clone();
+        let speaker = ResilientSpeaker::new(
+            FlakySpeaker {
+                broken: broken > 0,
+                voice: None,
+                spoken: spoken.clone(),
+            },
+            move || {
+                Ok(FlakySpeaker {
+                    broken: created.fetch_add(1, Ordering::SeqCst) < broken,
+                    voice: None,
+                    spoken: factory_spoken.clone(),
+                })
+            },
+            Duration::from_millis(200),
+        );
+        (speaker, spoken)
+    }
+
+    #[tokio::test]
+    async fn resilient_speaker_restarts_a_failing_speaker() {
+        let (mut speaker, spoken) = flaky(1);
+        let cancel = CancelWatch::never();
+        speaker.set_voice("paola").unwrap();
+
+        speaker.speak("fail once", &cancel).await.unwrap();
+        speaker.speak("ok", &cancel).await.unwrap();
+
+        assert_eq!(vec!["paola:fail once", "paola:ok"], *spoken.lock().unwrap());
+        assert_eq!(
+            SpeakerHealth {
+                restarts: 1,
+                failure: None
+            },
+            speaker.health().current()
+        );
+    }
+
+    #[tokio::test]
+    async fn resilient_speaker_restarts_a_hanging_speaker() {
+        let (mut speaker, spoken) = flaky(1);
+
+        speaker.speak("hang", &CancelWatch::never()).await.unwrap();
+
+        assert_eq!(vec![":hang"], *spoken.lock().unwrap());
+        assert_eq!(1, speaker.health().current().restarts);
+    }
+
+    #[tokio::test]
+    async fn resilient_speaker_reports_a_speaker_failing_after_restart() {
+        let (mut speaker, _) = flaky(2);
+        let cancel = CancelWatch::never();
+
+        assert!(speaker.speak("fail", &cancel).await.is_err());
+        assert_eq!(
+            Some("broken".to_owned()),
+            speaker.health().current().failure
+        );
+
+        speaker
+            .speak("fail again, but fixed", &cancel)
+            .await
+            .unwrap();
+        assert_eq!(None, speaker.health().current().failure);
+    }
+}
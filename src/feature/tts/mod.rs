@@ -1,11 +1,69 @@
-use std::num::NonZero;
-use std::path::{Path, PathBuf};
+//! Speaking the notifications: the [`Speaker`] trait, the speakers wrapping others to change how
+//! they speak, and the backends. Those playing audio (the model of sherpa-onnx, the cloud, the
+//! chimes and cues) need the `tts` feature.
+
+use std::pin::Pin;
 
 use error_stack::{Report, ResultExt};
+#[cfg(feature = "tts")]
 use indicatif::{ProgressBar, ProgressStyle};
-use rodio::{DeviceSinkBuilder, Player, buffer::SamplesBuffer};
-use sherpa_rs::tts::{VitsTts, VitsTtsConfig};
-use tracing::info;
+
+use crate::plan::Urgency;
+use crate::time::TimeSpan;
+
+#[cfg(feature = "tts")]
+mod audio;
+mod bell;
+mod cancel;
+#[cfg(feature = "tts")]
+mod chime;
+#[cfg(feature = "tts")]
+mod cloud;
+#[cfg(feature = "tts")]
+mod compose;
+#[cfg(feature = "tts")]
+mod cue;
+mod duck;
+mod health;
+mod pace;
+mod preprocess;
+mod print;
+mod queue;
+mod ramp;
+mod record;
+mod sapi;
+mod say;
+#[cfg(feature = "tts")]
+mod sherpa;
+mod silent;
+mod ssml;
+mod urgent;
+
+pub use bell::BellSpeaker;
+pub use cancel::{CancelToken, CancelWatch};
+#[cfg(feature = "tts")]
+pub use chime::{Chime, ChimeSpeaker};
+#[cfg(feature = "tts")]
+pub use cloud::CloudSpeaker;
+#[cfg(feature = "tts")]
+pub use compose::{ComposedSpeaker, Composition, Output};
+#[cfg(feature = "tts")]
+pub use cue::{SoundCue, SoundCueSpeaker};
+pub use duck::{Ducking, DuckingSpeaker};
+pub use health::{HealthMonitor, ResilientSpeaker, SpeakerHealth};
+pub use pace::{Pace, PacedSpeaker};
+pub use preprocess::{PreprocessingSpeaker, Preprocessor, Pronunciation};
+pub use print::PrintSpeaker;
+pub use queue::{QueuedSpeaker, SpeechActivity};
+pub use ramp::{RampedSpeaker, VolumeRamp};
+pub use record::Recorder;
+pub use sapi::SapiSpeaker;
+pub use say::SaySpeaker;
+#[cfg(feature = "tts")]
+pub use sherpa::TTSSpeaker;
+pub use silent::SilentSpeaker;
+pub use ssml::{escape_ssml, strip_ssml};
+pub use urgent::{SpeechProfile, UrgentSpeaker};
 
 #[derive(Debug, thiserror::Error)]
 #[error("TTS error")]
@@ -13,158 +71,396 @@ pub struct SpeakerError;
 
 pub type SpeakerResult<T> = Result<T, Report<SpeakerError>>;
 
-pub trait Speaker {
-    fn speak(&mut self, content: &str) -> SpeakerResult<()>;
+/// A message being spoken, or synthesized ahead of time, done when resolved.
+pub type SpeakerFuture<'a> = Pin<Box<dyn Future<Output = SpeakerResult<()>> + 'a>>;
+
+/// A voice offered by a speaker backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Voice {
+    pub name: String,
+    /// Language tag, e.g. `it-IT` or `it_IT`
+    pub language: String,
 }
 
-const MODEL_DIR_NAME: &str = "vits-piper-it_IT-paola-medium";
-const MODEL_ONNX: &str = "it_IT-paola-medium.onnx";
-const MODEL_URL: &str = "https://github.com/k2-fsa/sherpa-onnx/releases/download/tts-models/vits-piper-it_IT-paola-medium.tar.bz2";
-const MODEL_DOWNLOAD_BYTES: u64 = 67_221_173;
+impl Voice {
+    pub fn new(name: impl Into<String>, language: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            language: language.into(),
+        }
+    }
 
-pub struct TTSSpeaker {
-    tts: VitsTts,
+    /// Whether the voice speaks the given language (e.g. `it`), regardless of the region.
+    pub fn speaks(&self, language: &str) -> bool {
+        self.language
+            .split(['-', '_'])
+            .next()
+            .is_some_and(|l| l.eq_ignore_ascii_case(language))
+    }
 }
 
-impl TTSSpeaker {
-    pub fn new(model_path: Option<&Path>) -> SpeakerResult<Self> {
-        let model_dir = match model_path {
-            Some(p) => p.to_path_buf(),
-            None => {
-                let dir = default_model_dir();
-                ensure_model(&dir)?;
-                dir
-            }
-        };
+/// Picks the voice named `requested` if given, otherwise the first one speaking `language`.
+pub fn select_voice<'a>(
+    voices: &'a [Voice],
+    requested: Option<&str>,
+    language: &str,
+) -> Option<&'a Voice> {
+    match requested {
+        Some(name) => voices.iter().find(|v| v.name.eq_ignore_ascii_case(name)),
+        None => voices.iter().find(|v| v.speaks(language)),
+    }
+}
 
-        validate_model_dir(&model_dir)?;
-        info!("Loading TTS model from {}", model_dir.display());
-
-        let config = VitsTtsConfig {
-            model: path_str(model_dir.join(MODEL_ONNX)),
-            tokens: path_str(model_dir.join("tokens.txt")),
-            data_dir: path_str(model_dir.join("espeak-ng-data")),
-            lexicon: String::new(),
-            length_scale: 1.0,
-            noise_scale: 0.667,
-            noise_scale_w: 0.8,
-            ..Default::default()
-        };
+/// How messages are spoken, as factors relative to the backend defaults.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeechOptions {
+    /// Speaking rate, e.g. `0.8` is 20% slower
+    pub rate: f32,
+    /// Voice pitch, e.g. `1.2` is 20% higher
+    pub pitch: f32,
+    /// Playback volume, `1.0` being the full volume
+    pub volume: f32,
+}
 
-        let tts = VitsTts::new(config);
-        Ok(Self { tts })
+impl Default for SpeechOptions {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            pitch: 1.0,
+            volume: 1.0,
+        }
     }
 }
 
-impl Speaker for TTSSpeaker {
-    fn speak(&mut self, content: &str) -> SpeakerResult<()> {
-        let audio = self
-            .tts
-            .create(content, 0, 1.0)
-            .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?;
-
-        let samples = audio.samples;
-        let sample_rate = audio.sample_rate;
-
-        std::thread::spawn(move || {
-            let Ok(mut handle) = DeviceSinkBuilder::open_default_sink() else {
-                return;
-            };
-            handle.log_on_drop(false);
-            let player = Player::connect_new(handle.mixer());
-            let source = SamplesBuffer::new(
-                NonZero::new(1u16).unwrap(),
-                NonZero::new(sample_rate).unwrap(),
-                samples,
-            );
-            player.append(source);
-            player.sleep_until_end();
-        });
-
-        Ok(())
+impl SpeechOptions {
+    /// Pitch shift in semitones.
+    pub fn semitones(&self) -> f32 {
+        12.0 * self.pitch.log2()
     }
 }
 
-fn path_str(p: PathBuf) -> String {
-    p.to_string_lossy().into_owned()
+/// A message to be spoken, along with what it is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utterance {
+    pub text: String,
+    pub urgency: Urgency,
+    /// Time remaining before departure, for remaining time updates
+    pub remaining: Option<TimeSpan>,
+    /// The same message as SSML markup, for backends that support it
+    pub ssml: Option<String>,
+    pub pace: Pace,
 }
 
-fn default_model_dir() -> PathBuf {
-    dirs::data_local_dir()
-        .unwrap_or_else(|| {
-            std::env::var("HOME")
-                .map(PathBuf::from)
-                .unwrap_or_default()
-                .join(".local/share")
-        })
-        .join("rendezvous-coach")
-        .join("models")
-        .join(MODEL_DIR_NAME)
+impl Utterance {
+    /// A remaining time update, which a newer one makes obsolete.
+    pub fn countdown(text: impl Into<String>, remaining: TimeSpan) -> Self {
+        Self {
+            text: text.into(),
+            urgency: Urgency::for_remaining(&remaining),
+            remaining: Some(remaining),
+            ssml: None,
+            pace: Pace::Normal,
+        }
+    }
+
+    /// Any other message, which stays relevant until spoken.
+    pub fn message(text: impl Into<String>, urgency: Urgency) -> Self {
+        Self {
+            text: text.into(),
+            urgency,
+            remaining: None,
+            ssml: None,
+            pace: Pace::Normal,
+        }
+    }
+
+    pub fn with_ssml(self, ssml: Option<String>) -> Self {
+        Self { ssml, ..self }
+    }
+
+    pub fn with_pace(self, pace: Pace) -> Self {
+        Self { pace, ..self }
+    }
+
+    pub fn is_countdown(&self) -> bool {
+        self.remaining.is_some()
+    }
+
+    /// Whether this utterance is urgent enough to cut off `speaking`.
+    pub fn interrupts(&self, speaking: &Utterance) -> bool {
+        self.urgency >= Urgency::High && speaking.urgency < self.urgency
+    }
+
+    /// Whether a newer remaining time update makes this utterance pointless.
+    pub fn superseded_by(&self, newer: &Utterance) -> bool {
+        self.is_countdown() && newer.is_countdown() && self.urgency < Urgency::Critical
+    }
 }
 
-fn validate_model_dir(dir: &Path) -> SpeakerResult<()> {
-    for file in [MODEL_ONNX, "tokens.txt"] {
-        let p = dir.join(file);
-        if !p.exists() {
-            return Err(Report::new(SpeakerError)
-                .attach(format!("missing model file: {}", p.display())));
+/// Speaks the messages, asynchronously.
+///
+/// A message is spoken until the end, or until the [`CancelWatch`] it is given is cancelled,
+/// e.g. to make way for a more urgent one: the speech then resolves early, without an error.
+/// Synthesizing may still block, so the speech is best awaited on a thread of its own, as the
+/// [`QueuedSpeaker`] does. Choosing the voice and the options takes no time, and is synchronous.
+pub trait Speaker {
+    /// Speaks `content`, until done or cancelled.
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a>;
+
+    /// Speaks SSML markup, until done or cancelled.
+    ///
+    /// Backends that do not support SSML speak its plain text.
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move { self.speak(&strip_ssml(ssml), cancel).await })
+    }
+
+    /// Speaks an utterance, possibly taking into account what it is about.
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        match &utterance.ssml {
+            Some(ssml) => self.speak_ssml(ssml, cancel),
+            None => self.speak(&utterance.text, cancel),
         }
     }
-    let espeak = dir.join("espeak-ng-data");
-    if !espeak.exists() {
-        return Err(Report::new(SpeakerError)
-            .attach(format!("missing espeak-ng-data dir: {}", espeak.display())));
+
+    /// Voices available for this speaker.
+    fn voices(&self) -> SpeakerResult<Vec<Voice>>;
+
+    /// Selects the voice used for the next messages.
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()>;
+
+    /// Sets rate, pitch and volume for the next messages.
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()>;
+
+    /// Synthesizes `content` ahead of time, so that speaking it later is instant.
+    ///
+    /// Backends that cannot synthesize in advance do nothing.
+    fn prepare<'a>(&'a mut self, _content: &'a str) -> SpeakerFuture<'a> {
+        Box::pin(async { Ok(()) })
     }
-    Ok(())
 }
 
-fn ensure_model(model_dir: &Path) -> SpeakerResult<()> {
-    if model_dir.join(MODEL_ONNX).exists() {
-        return Ok(());
+impl<S: Speaker + ?Sized> Speaker for Box<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        (**self).speak(content, cancel)
     }
-    let parent = model_dir
-        .parent()
-        .ok_or_else(|| Report::new(SpeakerError).attach("invalid model dir path"))?;
-    std::fs::create_dir_all(parent)
+
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        (**self).speak_ssml(ssml, cancel)
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        (**self).announce(utterance, cancel)
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        (**self).voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        (**self).set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        (**self).set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        (**self).prepare(content)
+    }
+}
+
+/// Where the messages are announced without waiting for them to be spoken, e.g. a
+/// [`QueuedSpeaker`].
+pub trait Announcer {
+    /// Hands `utterance` over to be spoken, returning right away.
+    fn announce(&mut self, utterance: &Utterance) -> SpeakerResult<()>;
+}
+
+/// Waits for `speech` on the current thread, which must not be running a runtime already, e.g. a
+/// thread of its own.
+pub(crate) fn block_on(speech: SpeakerFuture<'_>) -> SpeakerResult<()> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
         .change_context(SpeakerError)
-        .attach("cannot create model cache dir")?;
-    download_model(parent)
+        .attach("cannot start the runtime of the speech")?
+        .block_on(speech)
 }
 
-fn download_model(dest_parent: &Path) -> SpeakerResult<()> {
-    eprintln!("First run: downloading TTS model (~67 MB) ...");
+/// Prepares all the given messages, showing the progress on the terminal.
+#[cfg(feature = "tts")]
+pub async fn prepare_all<S: Speaker + ?Sized>(
+    speaker: &mut S,
+    messages: &[&str],
+) -> SpeakerResult<()> {
+    let mut unique: Vec<&str> = messages.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
 
-    let pb = ProgressBar::new(MODEL_DOWNLOAD_BYTES);
+    eprintln!("Synthesizing {} messages ...", unique.len());
+    let pb = ProgressBar::new(unique.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("  {bar:40.cyan/blue} {bytes}/{total_bytes} ({eta})")
+            .template("  {bar:40.cyan/blue} {pos}/{len} ({eta})")
             .unwrap()
             .progress_chars("=>-"),
     );
+    for message in pb.wrap_iter(unique.into_iter()) {
+        speaker.prepare(message).await?;
+    }
+    pb.finish_and_clear();
+    Ok(())
+}
 
-    let response = ureq::AgentBuilder::new()
-        .timeout(std::time::Duration::from_secs(600))
-        .build()
-        .get(MODEL_URL)
-        .call()
-        .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if let Some(len) = response
-        .header("content-length")
-        .and_then(|v| v.parse::<u64>().ok())
-    {
-        pb.set_length(len);
+    #[cfg(feature = "tts")]
+    #[derive(Default)]
+    struct RecordingSpeaker {
+        prepared: Vec<String>,
     }
 
-    let reader = pb.wrap_read(response.into_reader());
-    let bz = bzip2::read::BzDecoder::new(reader);
-    let mut archive = tar::Archive::new(bz);
+    #[cfg(feature = "tts")]
+    impl Speaker for RecordingSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            _content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async { Ok(()) })
+        }
 
-    archive
-        .unpack(dest_parent)
-        .change_context(SpeakerError)
-        .attach("cannot extract TTS model")?;
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
 
-    pb.finish_with_message("done");
-    Ok(())
+        fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                self.prepared.push(content.to_owned());
+                Ok(())
+            })
+        }
+    }
+
+    #[cfg(feature = "tts")]
+    #[tokio::test]
+    async fn prepare_all_prepares_each_message_once() {
+        let mut speaker = RecordingSpeaker::default();
+
+        prepare_all(&mut speaker, &["b", "a", "b", "a", "c"])
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["a", "b", "c"], speaker.prepared);
+    }
+
+    #[test]
+    fn speech_options_semitones() {
+        let options = |pitch| SpeechOptions {
+            pitch,
+            ..Default::default()
+        };
+
+        assert_eq!(0.0, options(1.0).semitones());
+        assert_eq!(12.0, options(2.0).semitones());
+        assert_eq!(-12.0, options(0.5).semitones());
+    }
+
+    #[test]
+    fn urgent_utterances_interrupt_less_urgent_ones() {
+        let summary = Utterance::message("Riepilogo", Urgency::Low);
+        let five = Utterance::countdown("Mancano 5 minuti", TimeSpan::of_minutes(5));
+        let four = Utterance::countdown("Mancano 4 minuti", TimeSpan::of_minutes(4));
+        let go = Utterance::countdown("Ora di partire!", TimeSpan::ZERO);
+
+        assert!(go.interrupts(&summary));
+        assert!(go.interrupts(&four));
+        assert!(four.interrupts(&five));
+        assert!(!five.interrupts(&summary));
+        assert!(!go.interrupts(&go));
+    }
+
+    #[test]
+    fn countdown_is_superseded_by_a_newer_countdown() {
+        let older = Utterance::countdown("Mancano 5 minuti", TimeSpan::of_minutes(5));
+        let newer = Utterance::countdown("Mancano 4 minuti", TimeSpan::of_minutes(4));
+
+        assert!(older.superseded_by(&newer));
+    }
+
+    #[test]
+    fn critical_countdown_is_never_superseded() {
+        let older = Utterance::countdown("Ora di partire!", TimeSpan::ZERO);
+        let newer = Utterance::countdown("Ora di partire!", TimeSpan::ZERO);
+
+        assert!(!older.superseded_by(&newer));
+    }
+
+    #[test]
+    fn other_messages_are_never_superseded() {
+        let countdown = Utterance::countdown("Mancano 5 minuti", TimeSpan::of_minutes(5));
+        let message = Utterance::message("Partenza spostata", Urgency::Medium);
+
+        assert!(!message.superseded_by(&countdown));
+        assert!(!countdown.superseded_by(&message));
+    }
+
+    fn voices() -> Vec<Voice> {
+        vec![
+            Voice::new("Samantha", "en_US"),
+            Voice::new("Alice", "it_IT"),
+            Voice::new("it-IT-Standard-A", "it-IT"),
+        ]
+    }
+
+    #[test]
+    fn voice_speaks_language_regardless_of_region() {
+        assert!(Voice::new("Alice", "it_IT").speaks("it"));
+        assert!(Voice::new("it-IT-Standard-A", "it-IT").speaks("IT"));
+        assert!(!Voice::new("Samantha", "en_US").speaks("it"));
+    }
+
+    #[test]
+    fn select_voice_prefers_the_coach_language() {
+        let voices = voices();
+
+        let selected = select_voice(&voices, None, "it");
+
+        assert_eq!(Some(&voices[1]), selected);
+    }
+
+    #[test]
+    fn select_voice_by_name() {
+        let voices = voices();
+
+        let selected = select_voice(&voices, Some("samantha"), "it");
+
+        assert_eq!(Some(&voices[0]), selected);
+    }
+
+    #[test]
+    fn select_voice_without_matches() {
+        let voices = voices();
+
+        assert_eq!(None, select_voice(&voices, Some("Paola"), "it"));
+        assert_eq!(None, select_voice(&voices, None, "de"));
+    }
 }
@@ -0,0 +1,154 @@
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+
+/// Hint on how fast a message should be spoken, relative to the configured speech rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Pace {
+    /// Clearly enunciated, e.g. for the final call
+    Slow,
+    #[default]
+    Normal,
+    /// Brisk, e.g. for routine updates
+    Fast,
+}
+
+impl Pace {
+    pub fn factor(&self) -> f32 {
+        match self {
+            Pace::Slow => 0.85,
+            Pace::Normal => 1.0,
+            Pace::Fast => 1.15,
+        }
+    }
+}
+
+/// Speaker honoring the [`Pace`] of each utterance, by adjusting the speech rate.
+pub struct PacedSpeaker<S> {
+    inner: S,
+    options: SpeechOptions,
+}
+
+impl<S: Speaker> PacedSpeaker<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            options: SpeechOptions::default(),
+        }
+    }
+}
+
+impl<S: Speaker> Speaker for PacedSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        self.inner.speak(content, cancel)
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        if utterance.pace == Pace::Normal {
+            return self.inner.announce(utterance, cancel);
+        }
+        Box::pin(async move {
+            self.inner.set_options(SpeechOptions {
+                rate: self.options.rate * utterance.pace.factor(),
+                ..self.options
+            })?;
+            let result = self.inner.announce(utterance, cancel).await;
+            self.inner.set_options(self.options)?;
+            result
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.options = options;
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::TimeSpan;
+
+    #[derive(Default)]
+    struct RateSpeaker {
+        rate: f32,
+        spoken: Vec<(String, f32)>,
+    }
+
+    impl Speaker for RateSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                self.spoken.push((content.to_owned(), self.rate));
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+            self.rate = options.rate;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn paced_speaker_adjusts_the_rate_of_each_utterance() {
+        let mut speaker = PacedSpeaker::new(RateSpeaker::default());
+        let cancel = CancelWatch::never();
+        speaker
+            .set_options(SpeechOptions {
+                rate: 2.0,
+                ..Default::default()
+            })
+            .unwrap();
+
+        speaker
+            .announce(
+                &Utterance::countdown("go", TimeSpan::ZERO).with_pace(Pace::Slow),
+                &cancel,
+            )
+            .await
+            .unwrap();
+        speaker
+            .announce(
+                &Utterance::countdown("1h", TimeSpan::of_hours(1)).with_pace(Pace::Fast),
+                &cancel,
+            )
+            .await
+            .unwrap();
+        speaker.speak("plain", &cancel).await.unwrap();
+
+        assert_eq!(
+            vec![
+                ("go".to_owned(), 1.7),
+                ("1h".to_owned(), 2.3),
+                ("plain".to_owned(), 2.0)
+            ],
+            speaker.inner.spoken
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+
+/// A word and how to pronounce it instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pronunciation {
+    pub word: String,
+    pub spoken: String,
+}
+
+impl FromStr for Pronunciation {
+    type Err = String;
+
+    /// Parses `WORD=SPOKEN` (e.g. `Dott=Dottore`).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once('=') {
+            Some((word, spoken)) if !word.trim().is_empty() => Ok(Self {
+                word: word.trim().to_owned(),
+                spoken: spoken.trim().to_owned(),
+            }),
+            _ => Err("expected WORD=SPOKEN".to_owned()),
+        }
+    }
+}
+
+/// Turns display text into text pleasant to hear: strips emoji and symbols, normalizes numbers
+/// and times, expands abbreviations and applies the pronunciation overrides.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    /// Word joining hours and minutes, e.g. `e` for `18 e 30`
+    time_connector: String,
+    /// Replacements of whole words, by lowercase word
+    words: HashMap<String, String>,
+}
+
+impl Preprocessor {
+    /// Preprocessor with the abbreviations of a language (e.g. `it`).
+    pub fn for_language(language: &str) -> Self {
+        let (time_connector, abbreviations): (&str, &[(&str, &str)]) = match language {
+            "it" => (
+                "e",
+                &[
+                    ("h", "ore"),
+                    ("min", "minuti"),
+                    ("sec", "secondi"),
+                    ("km", "chilometri"),
+                ],
+            ),
+            _ => (
+                "",
+                &[
+                    ("h", "hours"),
+                    ("min", "minutes"),
+                    ("sec", "seconds"),
+                    ("km", "kilometers"),
+                ],
+            ),
+        };
+        let mut preprocessor = Self {
+            time_connector: time_connector.to_owned(),
+            words: HashMap::new(),
+        };
+        for (word, spoken) in abbreviations {
+            preprocessor.pronounce(word, spoken);
+        }
+        preprocessor
+    }
+
+    /// Pronounces `word` (regardless of case) as `spoken`.
+    pub fn pronounce(&mut self, word: &str, spoken: &str) {
+        self.words.insert(word.to_lowercase(), spoken.to_owned());
+    }
+
+    pub fn process(&self, text: &str) -> String {
+        let cleaned: String = text
+            .chars()
+            .map(|c| if is_speakable(c) { c } else { ' ' })
+            .collect();
+        cleaned
+            .split_whitespace()
+            .map(|token| self.process_token(token))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn process_token(&self, token: &str) -> String {
+        let mut processed = String::with_capacity(token.len());
+        let mut rest = token;
+        while let Some(start) = rest.find(|c: char| c.is_alphanumeric()) {
+            processed.push_str(&rest[..start]);
+            let word_len = rest[start..]
+                .find(|c: char| !c.is_alphanumeric() && c != ':')
+                .unwrap_or(rest.len() - start);
+            let word = &rest[start..start + word_len];
+            processed.push_str(&self.process_word(word));
+            rest = &rest[start + word_len..];
+        }
+        processed.push_str(rest);
+        processed
+    }
+
+    fn process_word(&self, word: &str) -> String {
+        if let Some(time) = self.time(word) {
+            return time;
+        }
+        let core = word.trim_end_matches(':');
+        let suffix = &word[core.len()..];
+        let spoken = match self.words.get(&core.to_lowercase()) {
+            Some(spoken) => spoken,
+            None if is_number(core) => normalize_number(core),
+            None => core,
+        };
+        format!("{spoken}{suffix}")
+    }
+
+    /// Times of day as hours and minutes (e.g. `18:05` as `18 e 5`).
+    fn time(&self, word: &str) -> Option<String> {
+        let parts: Vec<&str> = word.split(':').collect();
+        if !(2..=3).contains(&parts.len()) || !parts.iter().all(|p| is_number(p)) {
+            return None;
+        }
+        let hours = normalize_number(parts[0]);
+        let minutes = normalize_number(parts[1]);
+        Some(match (minutes, self.time_connector.as_str()) {
+            ("0", _) => hours.to_owned(),
+            (minutes, "") => format!("{hours} {minutes}"),
+            (minutes, connector) => format!("{hours} {connector} {minutes}"),
+        })
+    }
+}
+
+fn is_speakable(c: char) -> bool {
+    c.is_alphanumeric() || c.is_whitespace() || ".,;:!?'\"-()%".contains(c)
+}
+
+fn is_number(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Drops the leading zeros, which are read out by some engines.
+fn normalize_number(number: &str) -> &str {
+    match number.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    }
+}
+
+/// Speaker preprocessing the text of each message before speaking it (see [`Preprocessor`]).
+///
+/// SSML markup is spoken as is.
+pub struct PreprocessingSpeaker<S> {
+    inner: S,
+    preprocessor: Preprocessor,
+}
+
+impl<S: Speaker> PreprocessingSpeaker<S> {
+    pub fn new(inner: S, preprocessor: Preprocessor) -> Self {
+        Self {
+            inner,
+            preprocessor,
+        }
+    }
+}
+
+impl<S: Speaker> Speaker for PreprocessingSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let content = self.preprocessor.process(content);
+            self.inner.speak(&content, cancel).await
+        })
+    }
+
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        self.inner.speak_ssml(ssml, cancel)
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let utterance = Utterance {
+                text: self.preprocessor.process(&utterance.text),
+                ..utterance.clone()
+            };
+            self.inner.announce(&utterance, cancel).await
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let content = self.preprocessor.process(content);
+            self.inner.prepare(&content).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_strips_emoji_and_symbols() {
+        let preprocessor = Preprocessor::for_language("it");
+
+        assert_eq!(
+            "Partenza alle 18 e 30!",
+            preprocessor.process("🚗 Partenza alle 18:30! ⏰")
+        );
+    }
+
+    #[test]
+    fn process_normalizes_numbers_and_times() {
+        let preprocessor = Preprocessor::for_language("it");
+
+        assert_eq!(
+            "Mancano 5 minuti, alle 9 e 5 o alle 10",
+            preprocessor.process("Mancano 05 min, alle 09:05 o alle 10:00")
+        );
+        assert_eq!(
+            "Leave at 9 5",
+            Preprocessor::for_language("en").process("Leave at 09:05")
+        );
+    }
+
+    #[test]
+    fn process_applies_pronunciations_to_whole_words_only() {
+        let mut preprocessor = Preprocessor::for_language("it");
+        preprocessor.pronounce("Dott", "Dottore");
+
+        assert_eq!(
+            "Il Dottore arriva, Dottrina no.",
+            preprocessor.process("Il dott arriva, Dottrina no.")
+        );
+    }
+
+    #[test]
+    fn pronunciation_parse() {
+        assert_eq!(
+            Pronunciation {
+                word: "Dott".to_owned(),
+                spoken: "Dottore".to_owned()
+            },
+            "Dott=Dottore".parse().unwrap()
+        );
+        assert!("Dottore".parse::<Pronunciation>().is_err());
+        assert!("=Dottore".parse::<Pronunciation>().is_err());
+    }
+}
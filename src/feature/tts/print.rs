@@ -0,0 +1,87 @@
+use std::io::Write;
+
+use chrono::Local;
+use error_stack::ResultExt;
+
+use super::{
+    CancelWatch, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance,
+    Voice,
+};
+
+/// Speaker writing what would be spoken, with a timestamp, instead of producing audio.
+pub struct PrintSpeaker<W> {
+    out: W,
+}
+
+impl<W: Write> PrintSpeaker<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn print(&mut self, line: &str) -> SpeakerResult<()> {
+        let time = Local::now().format("%H:%M:%S");
+        writeln!(self.out, "[{time}] {line}")
+            .and_then(|_| self.out.flush())
+            .change_context(SpeakerError)
+            .attach("cannot print the message")
+    }
+}
+
+impl<W: Write> Speaker for PrintSpeaker<W> {
+    fn speak<'a>(&'a mut self, content: &'a str, _cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move { self.print(content) })
+    }
+
+    fn speak_ssml<'a>(&'a mut self, ssml: &'a str, _cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move { self.print(ssml) })
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        _cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let content = utterance.ssml.as_ref().unwrap_or(&utterance.text);
+            self.print(&format!("({:?}) {content}", utterance.urgency))
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        Ok(vec![])
+    }
+
+    fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+        Ok(())
+    }
+
+    fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::TimeSpan;
+
+    #[tokio::test]
+    async fn print_speaker_writes_timestamped_lines() {
+        let mut speaker = PrintSpeaker::new(Vec::new());
+        let cancel = CancelWatch::never();
+
+        speaker.speak("Ciao", &cancel).await.unwrap();
+        speaker
+            .announce(
+                &Utterance::countdown("Mancano 4 minuti", TimeSpan::of_minutes(4)),
+                &cancel,
+            )
+            .await
+            .unwrap();
+
+        let printed = String::from_utf8(speaker.out).unwrap();
+        let lines: Vec<_> = printed.lines().map(|l| &l[11..]).collect();
+        assert_eq!(vec!["Ciao", "(High) Mancano 4 minuti"], lines);
+        assert!(printed.starts_with('['));
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use error_stack::Report;
+use tracing::{debug, error};
+
+use super::{
+    Announcer, CancelToken, CancelWatch, Speaker, SpeakerError, SpeakerResult, SpeechOptions,
+    Utterance, Voice,
+};
+use crate::plan::Urgency;
+
+type Reply<T> = Sender<SpeakerResult<T>>;
+
+enum Request {
+    Speak(Utterance),
+    Voices(Reply<Vec<Voice>>),
+    SetVoice(String, Reply<()>),
+    SetOptions(SpeechOptions, Reply<()>),
+    Prepare(String, Reply<()>),
+}
+
+#[derive(Default)]
+struct Pending {
+    requests: VecDeque<Request>,
+    /// The utterance the worker is speaking, if any
+    speaking: Option<Utterance>,
+    closed: bool,
+}
+
+/// Requests waiting for the worker.
+#[derive(Default)]
+struct Queue {
+    pending: Mutex<Pending>,
+    available: Condvar,
+    /// Interrupts the utterance being spoken
+    cancel: CancelToken,
+}
+
+impl Queue {
+    fn push(&self, request: Request) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Request::Speak(newer) = &request {
+            pending.requests.retain(|r| match r {
+                Request::Speak(older) if older.superseded_by(newer) => {
+                    debug!("Dropping stale message `{}`", older.text);
+                    false
+                }
+                _ => true,
+            });
+            if let Some(speaking) = &pending.speaking
+                && newer.interrupts(speaking)
+            {
+                debug!("Interrupting `{}`", speaking.text);
+                self.cancel.cancel();
+            }
+        }
+        pending.requests.push_back(request);
+        self.available.notify_one();
+    }
+
+    /// Waits for the next request, or `None` when closed and drained.
+    ///
+    /// The request comes with the watch cancelling it, started before anything can interrupt it.
+    fn pop(&self) -> Option<(Request, CancelWatch)> {
+        let mut pending = self.pending.lock().unwrap();
+        loop {
+            if let Some(request) = pending.requests.pop_front() {
+                pending.speaking = match &request {
+                    Request::Speak(utterance) => Some(utterance.clone()),
+                    _ => None,
+                };
+                return Some((request, self.cancel.watch()));
+            }
+            pending.speaking = None;
+            if pending.closed {
+                return None;
+            }
+            pending = self.available.wait(pending).unwrap();
+        }
+    }
+
+    fn close(&self) {
+        self.pending.lock().unwrap().closed = true;
+        self.available.notify_one();
+    }
+}
+
+/// Shared view of whether a [`QueuedSpeaker`] is speaking.
+#[derive(Clone)]
+pub struct SpeechActivity(Arc<Queue>);
+
+impl SpeechActivity {
+    /// Whether a message is being spoken.
+    pub fn is_speaking(&self) -> bool {
+        self.0.pending.lock().unwrap().speaking.is_some()
+    }
+}
+
+/// Speaks on a background worker, so that [`announce`](Announcer::announce) returns immediately.
+///
+/// Messages are spoken in order, one at a time, except for remaining time updates: a new one
+/// replaces those still waiting to be spoken (see [`Utterance::superseded_by`]). An urgent
+/// message cuts off a less urgent one being spoken (see [`Utterance::interrupts`]). Since nobody
+/// waits for them, errors while speaking are only logged. All the other operations wait for the
+/// worker to complete them.
+pub struct QueuedSpeaker {
+    queue: Arc<Queue>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl QueuedSpeaker {
+    pub fn new<S: Speaker + Send + 'static>(speaker: S) -> Self {
+        let queue = Arc::new(Queue::default());
+        let worker_queue = queue.clone();
+        let worker = std::thread::Builder::new()
+            .name("speech".to_owned())
+            .spawn(move || work(speaker, &worker_queue))
+            .expect("cannot spawn the speech worker");
+        Self {
+            queue,
+            worker: Some(worker),
+        }
+    }
+
+    pub fn activity(&self) -> SpeechActivity {
+        SpeechActivity(self.queue.clone())
+    }
+
+    /// Waits until all the queued messages have been spoken.
+    pub fn finish(mut self) {
+        self.queue.close();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+
+    fn call<T>(&self, request: impl FnOnce(Reply<T>) -> Request) -> SpeakerResult<T> {
+        let (tx, rx) = mpsc::channel();
+        self.queue.push(request(tx));
+        rx.recv()
+            .map_err(|_| Report::new(SpeakerError).attach("the speech worker has stopped"))?
+    }
+
+    /// Speaks `content` as a message of low urgency.
+    pub fn speak(&mut self, content: &str) -> SpeakerResult<()> {
+        self.announce(&Utterance::message(content, Urgency::Low))
+    }
+
+    pub fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.call(Request::Voices)
+    }
+
+    pub fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.call(|reply| Request::SetVoice(name.to_owned(), reply))
+    }
+
+    pub fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.call(|reply| Request::SetOptions(options, reply))
+    }
+
+    pub fn prepare(&mut self, content: &str) -> SpeakerResult<()> {
+        self.call(|reply| Request::Prepare(content.to_owned(), reply))
+    }
+}
+
+impl Drop for QueuedSpeaker {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+impl Announcer for QueuedSpeaker {
+    fn announce(&mut self, utterance: &Utterance) -> SpeakerResult<()> {
+        self.queue.push(Request::Speak(utterance.clone()));
+        Ok(())
+    }
+}
+
+fn work<S: Speaker>(mut speaker: S, queue: &Queue) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("cannot start the runtime of the speech: {e}");
+            return;
+        }
+    };
+    // Replies are dropped silently if the caller is gone: there is nobody left to tell.
+    while let Some((request, cancel)) = queue.pop() {
+        match request {
+            Request::Speak(utterance) => {
+                if let Err(report) = runtime.block_on(speaker.announce(&utterance, &cancel)) {
+                    error!("cannot speak `{}`: {report:?}", utterance.text);
+                }
+            }
+            Request::Voices(reply) => {
+                let _ = reply.send(speaker.voices());
+            }
+            Request::SetVoice(name, reply) => {
+                let _ = reply.send(speaker.set_voice(&name));
+            }
+            Request::SetOptions(options, reply) => {
+                let _ = reply.send(speaker.set_options(options));
+            }
+            Request::Prepare(content, reply) => {
+                let _ = reply.send(runtime.block_on(speaker.prepare(&content)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::feature::tts::SpeakerFuture;
+    use crate::time::TimeSpan;
+
+    #[derive(Clone, Default)]
+    struct SlowSpeaker {
+        spoken: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Speaker for SlowSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                self.spoken.lock().unwrap().push(content.to_owned());
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![Voice::new("test", "it-IT")])
+        }
+
+        fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+            Err(Report::new(SpeakerError).attach(format!("unknown voice {name}")))
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn finish_waits_for_the_messages_in_order() {
+        let speaker = SlowSpeaker::default();
+        let mut queued = QueuedSpeaker::new(speaker.clone());
+
+        queued.speak("1").unwrap();
+        queued.speak("2").unwrap();
+        queued.speak("3").unwrap();
+        queued.finish();
+
+        assert_eq!(vec!["1", "2", "3"], *speaker.spoken.lock().unwrap());
+    }
+
+    /// Speaker waiting to be released before completing each message.
+    struct GatedSpeaker {
+        started: Sender<()>,
+        gate: mpsc::Receiver<()>,
+        spoken: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Speaker for GatedSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                let _ = self.started.send(());
+                let _ = self.gate.recv();
+                self.spoken.lock().unwrap().push(content.to_owned());
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn speak_does_not_wait_for_the_speech() {
+        let (started_tx, started) = mpsc::channel();
+        let (release, gate) = mpsc::channel();
+        let spoken = Arc::new(Mutex::new(vec![]));
+        let mut queued = QueuedSpeaker::new(GatedSpeaker {
+            started: started_tx,
+            gate,
+            spoken: spoken.clone(),
+        });
+
+        // All handed over while the first one is still being spoken
+        for i in 0..5 {
+            queued.speak(&format!("{i}")).unwrap();
+        }
+        started.recv().unwrap();
+        assert!(spoken.lock().unwrap().is_empty());
+
+        for _ in 0..5 {
+            release.send(()).unwrap();
+        }
+        queued.finish();
+        assert_eq!(vec!["0", "1", "2", "3", "4"], *spoken.lock().unwrap());
+    }
+
+    #[test]
+    fn stale_countdown_messages_are_dropped() {
+        let (started_tx, started) = mpsc::channel();
+        let (release, gate) = mpsc::channel();
+        let spoken = Arc::new(Mutex::new(vec![]));
+        let mut queued = QueuedSpeaker::new(GatedSpeaker {
+            started: started_tx,
+            gate,
+            spoken: spoken.clone(),
+        });
+
+        queued
+            .announce(&Utterance::countdown("6", TimeSpan::of_minutes(6)))
+            .unwrap();
+        started.recv().unwrap();
+        queued
+            .announce(&Utterance::countdown("5", TimeSpan::of_minutes(5)))
+            .unwrap();
+        queued
+            .announce(&Utterance::message("note", Urgency::Medium))
+            .unwrap();
+        queued
+            .announce(&Utterance::countdown("4", TimeSpan::of_minutes(4)))
+            .unwrap();
+        queued
+            .announce(&Utterance::countdown("go", TimeSpan::ZERO))
+            .unwrap();
+        queued
+            .announce(&Utterance::countdown("go", TimeSpan::ZERO))
+            .unwrap();
+        for _ in 0..4 {
+            release.send(()).unwrap();
+        }
+        queued.finish();
+
+        assert_eq!(vec!["6", "note", "go", "go"], *spoken.lock().unwrap());
+    }
+
+    #[test]
+    fn activity_tells_when_speaking() {
+        let (started_tx, started) = mpsc::channel();
+        let (release, gate) = mpsc::channel();
+        let mut queued = QueuedSpeaker::new(GatedSpeaker {
+            started: started_tx,
+            gate,
+            spoken: Arc::default(),
+        });
+        let activity = queued.activity();
+        assert!(!activity.is_speaking());
+
+        queued.speak("1").unwrap();
+        started.recv().unwrap();
+        assert!(activity.is_speaking());
+
+        release.send(()).unwrap();
+        queued.finish();
+        assert!(!activity.is_speaking());
+    }
+
+    /// Speaker taking half a second per message, unless interrupted.
+    struct InterruptibleSpeaker {
+        started: Sender<()>,
+        spoken: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Speaker for InterruptibleSpeaker {
+        fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                let _ = self.started.send(());
+                let outcome = tokio::select! {
+                    () = tokio::time::sleep(Duration::from_millis(500)) => "",
+                    () = cancel.cancelled() => " (interrupted)",
+                };
+                self.spoken
+                    .lock()
+                    .unwrap()
+                    .push(format!("{content}{outcome}"));
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn urgent_messages_interrupt_less_urgent_ones() {
+        let (started_tx, started) = mpsc::channel();
+        let spoken = Arc::new(Mutex::new(vec![]));
+        let mut queued = QueuedSpeaker::new(InterruptibleSpeaker {
+            started: started_tx,
+            spoken: spoken.clone(),
+        });
+
+        queued
+            .announce(&Utterance::message("summary", Urgency::Low))
+            .unwrap();
+        started.recv().unwrap();
+        queued
+            .announce(&Utterance::countdown("go", TimeSpan::ZERO))
+            .unwrap();
+        started.recv().unwrap();
+        queued
+            .announce(&Utterance::message("note", Urgency::Medium))
+            .unwrap();
+        queued.finish();
+
+        assert_eq!(
+            vec!["summary (interrupted)", "go", "note"],
+            *spoken.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn other_operations_return_the_worker_results() {
+        let mut queued = QueuedSpeaker::new(SlowSpeaker::default());
+
+        assert_eq!(vec![Voice::new("test", "it-IT")], queued.voices().unwrap());
+        assert!(queued.set_voice("missing").is_err());
+        assert!(queued.set_options(SpeechOptions::default()).is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+use std::str::FromStr;
+
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+use crate::plan::Urgency;
+
+/// Volume for each urgency level, relative to the configured speech volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VolumeRamp {
+    low: f32,
+    medium: f32,
+    high: f32,
+    critical: f32,
+}
+
+impl VolumeRamp {
+    pub fn new(low: f32, medium: f32, high: f32, critical: f32) -> Self {
+        Self {
+            low,
+            medium,
+            high,
+            critical,
+        }
+    }
+
+    pub fn volume(&self, urgency: Urgency) -> f32 {
+        match urgency {
+            Urgency::Low => self.low,
+            Urgency::Medium => self.medium,
+            Urgency::High => self.high,
+            Urgency::Critical => self.critical,
+        }
+    }
+}
+
+impl FromStr for VolumeRamp {
+    type Err = String;
+
+    /// Parses four comma separated volumes, from low to critical urgency (e.g. `0.4,0.6,0.8,1`).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let volumes = input
+            .split(',')
+            .map(|v| match v.trim().parse::<f32>() {
+                Ok(volume) if (0.0..=1.0).contains(&volume) => Ok(volume),
+                _ => Err(format!("invalid volume `{v}`, must be between 0.0 and 1.0")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match volumes[..] {
+            [low, medium, high, critical] => Ok(Self::new(low, medium, high, critical)),
+            _ => Err("expected 4 volumes: low, medium, high and critical urgency".to_owned()),
+        }
+    }
+}
+
+/// Speaker adjusting the volume of each utterance according to its urgency.
+pub struct RampedSpeaker<S> {
+    inner: S,
+    ramp: VolumeRamp,
+    options: SpeechOptions,
+}
+
+impl<S: Speaker> RampedSpeaker<S> {
+    pub fn new(inner: S, ramp: VolumeRamp) -> Self {
+        Self {
+            inner,
+            ramp,
+            options: SpeechOptions::default(),
+        }
+    }
+}
+
+impl<S: Speaker> Speaker for RampedSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            self.inner.set_options(self.options)?;
+            self.inner.speak(content, cancel).await
+        })
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            self.inner.set_options(SpeechOptions {
+                volume: self.options.volume * self.ramp.volume(utterance.urgency),
+                ..self.options
+            })?;
+            self.inner.announce(utterance, cancel).await
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.options = options;
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::TimeSpan;
+
+    #[derive(Default)]
+    struct VolumeSpeaker {
+        volume: f32,
+        spoken: Vec<(String, f32)>,
+    }
+
+    impl Speaker for VolumeSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                self.spoken.push((content.to_owned(), self.volume));
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+            Ok(())
+        }
+
+        fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+            self.volume = options.volume;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn volume_ramp_parse() {
+        let ramp: VolumeRamp = "0.4, 0.6,0.8,1".parse().unwrap();
+
+        assert_eq!(VolumeRamp::new(0.4, 0.6, 0.8, 1.0), ramp);
+    }
+
+    #[test]
+    fn volume_ramp_parse_invalid() {
+        assert!("0.4,0.6,0.8".parse::<VolumeRamp>().is_err());
+        assert!("0.4,0.6,0.8,1.5".parse::<VolumeRamp>().is_err());
+        assert!("0.4,0.6,loud,1".parse::<VolumeRamp>().is_err());
+    }
+
+    #[tokio::test]
+    async fn ramped_speaker_scales_the_volume_by_urgency() {
+        let ramp = VolumeRamp::new(0.25, 0.5, 0.75, 1.0);
+        let cancel = CancelWatch::never();
+        let mut speaker = RampedSpeaker::new(VolumeSpeaker::default(), ramp);
+        speaker
+            .set_options(SpeechOptions {
+                volume: 0.8,
+                ..Default::default()
+            })
+            .unwrap();
+
+        speaker
+            .announce(&Utterance::countdown("low", TimeSpan::of_hours(1)), &cancel)
+            .await
+            .unwrap();
+        speaker
+            .announce(&Utterance::countdown("critical", TimeSpan::ZERO), &cancel)
+            .await
+            .unwrap();
+        speaker.speak("plain", &cancel).await.unwrap();
+
+        assert_eq!(
+            vec![
+                ("low".to_owned(), 0.2),
+                ("critical".to_owned(), 0.8),
+                ("plain".to_owned(), 0.8),
+            ],
+            speaker.inner.spoken
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use chrono::Local;
+use error_stack::ResultExt;
+
+use super::{SpeakerError, SpeakerResult};
+
+const INDEX_FILE: &str = "index.tsv";
+
+/// Records the synthesized messages of a session as WAV files, one per message, in a directory
+/// named after the session start time.
+///
+/// The `index.tsv` file lists the recordings in order, each with the spoken text. Clones share
+/// the same session.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    dir: PathBuf,
+    count: Arc<AtomicU32>,
+}
+
+impl Recorder {
+    pub fn new(parent: &Path) -> SpeakerResult<Self> {
+        let dir = parent.join(Local::now().format("%Y%m%d-%H%M%S").to_string());
+        std::fs::create_dir_all(&dir)
+            .change_context(SpeakerError)
+            .attach(format!("cannot create recording dir: {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            count: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path for the next recording, after adding it to the index.
+    pub(crate) fn next_path(&self, content: &str) -> SpeakerResult<PathBuf> {
+        let n = self.count.fetch_add(1, Ordering::SeqCst) + 1;
+        let name = format!("{n:03}-{}.wav", Local::now().format("%H%M%S"));
+        let index = self.dir.join(INDEX_FILE);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index)
+            .and_then(|mut file| writeln!(file, "{name}\t{}", content.replace(['\t', '\n'], " ")))
+            .change_context(SpeakerError)
+            .attach(format!("cannot write recording index: {}", index.display()))?;
+        Ok(self.dir.join(name))
+    }
+
+    pub(crate) fn record_samples(
+        &self,
+        content: &str,
+        samples: &[f32],
+        sample_rate: u32,
+    ) -> SpeakerResult<()> {
+        let path = self.next_path(content)?;
+        File::create(&path)
+            .and_then(|file| write_wav(BufWriter::new(file), samples, sample_rate))
+            .change_context(SpeakerError)
+            .attach(format!("cannot write recording: {}", path.display()))
+    }
+
+    pub(crate) fn record_file(&self, content: &str, audio: &Path) -> SpeakerResult<()> {
+        let path = self.next_path(content)?;
+        std::fs::copy(audio, &path)
+            .map(|_| ())
+            .change_context(SpeakerError)
+            .attach(format!("cannot write recording: {}", path.display()))
+    }
+}
+
+/// Writes mono samples as 16-bit PCM WAV.
+fn write_wav<W: Write>(mut out: W, samples: &[f32], sample_rate: u32) -> std::io::Result<()> {
+    let data_len = (samples.len() * 2) as u32;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // PCM
+    out.write_all(&1u16.to_le_bytes())?; // mono
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_wav_writes_a_pcm_header_and_the_samples() {
+        let mut wav = vec![];
+
+        write_wav(&mut wav, &[0.0, 1.0, -1.0], 22050).unwrap();
+
+        assert_eq!(44 + 6, wav.len());
+        assert_eq!(b"RIFF", &wav[0..4]);
+        assert_eq!(b"WAVE", &wav[8..12]);
+        assert_eq!(22050u32.to_le_bytes(), wav[24..28]);
+        assert_eq!(6u32.to_le_bytes(), wav[40..44]);
+        assert_eq!([0, 0, 0xff, 0x7f, 0x01, 0x80], wav[44..]);
+    }
+
+    #[test]
+    fn recorder_numbers_the_recordings_and_indexes_them() {
+        let parent = std::env::temp_dir().join(format!("rendezvous-record-{}", std::process::id()));
+        let recorder = Recorder::new(&parent).unwrap();
+
+        recorder
+            .record_samples("Manca 1 minuto", &[0.0], 16000)
+            .unwrap();
+        recorder
+            .clone()
+            .record_samples("Ora di partire!", &[0.0], 16000)
+            .unwrap();
+
+        let index = std::fs::read_to_string(recorder.dir().join(INDEX_FILE)).unwrap();
+        let lines: Vec<_> = index.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("001-") && lines[0].ends_with(".wav\tManca 1 minuto"));
+        assert!(lines[1].starts_with("002-") && lines[1].ends_with(".wav\tOra di partire!"));
+        for line in lines {
+            let name = line.split('\t').next().unwrap();
+            assert!(recorder.dir().join(name).exists());
+        }
+        std::fs::remove_dir_all(parent).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use std::process::{Command, Stdio};
+
+use error_stack::{Report, ResultExt};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use super::{
+    CancelWatch, Recorder, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions,
+    Voice,
+};
+
+const POWERSHELL_COMMAND: &str = "powershell";
+/// Creates the synthesizer as `$s`
+const SYNTHESIZER: &str = "Add-Type -AssemblyName System.Speech; \
+     $s = New-Object System.Speech.Synthesis.SpeechSynthesizer;";
+/// Lists the voices, one per line with the name and the language separated by a tab
+const LIST_VOICES: &str = "$s.GetInstalledVoices() | \
+     ForEach-Object { $_.VoiceInfo.Name + \"`t\" + $_.VoiceInfo.Culture.Name }";
+
+/// Speaker backed by the speech synthesizer of Windows (SAPI), through PowerShell.
+///
+/// The pitch cannot be changed, only the rate and the volume.
+pub struct SapiSpeaker {
+    voice: Option<String>,
+    options: SpeechOptions,
+    recorder: Option<Recorder>,
+}
+
+impl SapiSpeaker {
+    pub fn new() -> SpeakerResult<Self> {
+        let voices =
+            list_voices().attach("cannot run the speech synthesizer (is this Windows?)")?;
+        if voices.is_empty() {
+            return Err(Report::new(SpeakerError).attach("no SAPI voice installed"));
+        }
+        info!("Using Windows SAPI speaker");
+        Ok(Self {
+            voice: None,
+            options: SpeechOptions::default(),
+            recorder: None,
+        })
+    }
+
+    /// Records each message spoken.
+    pub fn with_recorder(self, recorder: Recorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..self
+        }
+    }
+
+    /// Script speaking the text read from the standard input, to the WAV file at `output` if
+    /// given, else to the speakers.
+    fn script(&self, output: Option<&str>) -> String {
+        let mut script = SYNTHESIZER.to_owned();
+        if let Some(voice) = &self.voice {
+            script.push_str(&format!(" $s.SelectVoice({});", quote(voice)));
+        }
+        script.push_str(&format!(
+            " $s.Rate = {}; $s.Volume = {};",
+            sapi_rate(self.options.rate),
+            (self.options.volume.clamp(0.0, 1.0) * 100.0).round()
+        ));
+        if let Some(output) = output {
+            script.push_str(&format!(" $s.SetOutputToWaveFile({});", quote(output)));
+        }
+        script.push_str(" $s.Speak([Console]::In.ReadToEnd())");
+        script
+    }
+}
+
+impl Speaker for SapiSpeaker {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            run_powershell(&self.script(None), content, cancel).await?;
+            if let Some(recorder) = &self.recorder {
+                // SAPI cannot play and save at once, so the message is synthesized again.
+                let path = recorder.next_path(content)?;
+                let script = self.script(Some(&path.display().to_string()));
+                if let Err(report) = run_powershell(&script, content, &CancelWatch::never()).await {
+                    warn!("cannot record `{content}`: {report:?}");
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        list_voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.voice = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.options = options;
+        Ok(())
+    }
+}
+
+/// Rate of SAPI, from -10 (a third of the normal speed) to 10 (three times as fast).
+fn sapi_rate(rate: f32) -> i32 {
+    (10.0 * rate.max(f32::MIN_POSITIVE).ln() / 3f32.ln())
+        .round()
+        .clamp(-10.0, 10.0) as i32
+}
+
+/// `value` as a PowerShell string literal.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn list_voices() -> SpeakerResult<Vec<Voice>> {
+    let output = Command::new(POWERSHELL_COMMAND)
+        .args(["-NoProfile", "-NonInteractive", "-Command"])
+        .arg(format!("{SYNTHESIZER} {LIST_VOICES}"))
+        .stderr(Stdio::null())
+        .output()
+        .change_context(SpeakerError)
+        .attach("cannot list the SAPI voices")?;
+    if !output.status.success() {
+        return Err(
+            Report::new(SpeakerError).attach(format!("PowerShell exited with {}", output.status))
+        );
+    }
+    let listing = String::from_utf8_lossy(&output.stdout);
+    Ok(listing.lines().filter_map(parse_voice_line).collect())
+}
+
+/// Runs `script` with `input` on its standard input until it exits, or kills it when cancelled.
+async fn run_powershell(script: &str, input: &str, cancel: &CancelWatch) -> SpeakerResult<()> {
+    let mut child = tokio::process::Command::new(POWERSHELL_COMMAND)
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .change_context(SpeakerError)
+        .attach("cannot run PowerShell")?;
+    // Closed once written, for the script to read to the end
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .await
+            .change_context(SpeakerError)
+            .attach("cannot pass the message to PowerShell")?;
+    }
+    let exited = tokio::select! {
+        status = child.wait() => Some(status),
+        () = cancel.cancelled() => None,
+    };
+    let Some(status) = exited else {
+        let _ = child.kill().await;
+        return Ok(());
+    };
+    let status = status
+        .change_context(SpeakerError)
+        .attach("cannot wait for PowerShell")?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Report::new(SpeakerError).attach(format!("PowerShell exited with {status}")))
+    }
+}
+
+/// Parses a line of the voices listed, e.g. `Microsoft Elsa Desktop<TAB>it-IT`.
+fn parse_voice_line(line: &str) -> Option<Voice> {
+    let (name, language) = line.trim_end().split_once('\t')?;
+    let name = name.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(Voice::new(name, language.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speaker(voice: Option<&str>, options: SpeechOptions) -> SapiSpeaker {
+        SapiSpeaker {
+            voice: voice.map(str::to_owned),
+            options,
+            recorder: None,
+        }
+    }
+
+    #[test]
+    fn script_with_voice_rate_and_volume() {
+        let options = SpeechOptions {
+            rate: 3.0,
+            volume: 0.5,
+            ..Default::default()
+        };
+
+        let script = speaker(Some("Microsoft Elsa's"), options).script(None);
+
+        assert!(script.contains("$s.SelectVoice('Microsoft Elsa''s');"));
+        assert!(script.contains("$s.Rate = 10; $s.Volume = 50;"));
+        assert!(script.ends_with("$s.Speak([Console]::In.ReadToEnd())"));
+    }
+
+    #[test]
+    fn sapi_rate_is_logarithmic() {
+        assert_eq!(0, sapi_rate(1.0));
+        assert_eq!(-10, sapi_rate(1.0 / 3.0));
+        assert_eq!(6, sapi_rate(2.0));
+        assert_eq!(10, sapi_rate(5.0));
+    }
+
+    #[test]
+    fn parse_voice_line_with_name_and_language() {
+        assert_eq!(
+            Some(Voice::new("Microsoft Elsa Desktop", "it-IT")),
+            parse_voice_line("Microsoft Elsa Desktop\tit-IT\r")
+        );
+        assert_eq!(None, parse_voice_line("Microsoft Elsa Desktop"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::process::{Command, Stdio};
+
+use error_stack::{Report, ResultExt};
+use tracing::{info, warn};
+
+use super::{
+    CancelWatch, Recorder, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions,
+    Voice,
+};
+
+const SAY_COMMAND: &str = "say";
+/// Default rate of `say`, in words per minute
+const DEFAULT_WPM: f32 = 175.0;
+
+/// Speaker backed by the macOS `say` command.
+pub struct SaySpeaker {
+    voice: Option<String>,
+    options: SpeechOptions,
+    recorder: Option<Recorder>,
+}
+
+impl SaySpeaker {
+    pub fn new() -> SpeakerResult<Self> {
+        let status = Command::new(SAY_COMMAND)
+            .args(["-v", "?"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .change_context(SpeakerError)
+            .attach("cannot run the `say` command (is this macOS?)")?;
+        if !status.success() {
+            return Err(Report::new(SpeakerError).attach(format!("`say` exited with {status}")));
+        }
+        info!("Using macOS `say` speaker");
+        Ok(Self {
+            voice: None,
+            options: SpeechOptions::default(),
+            recorder: None,
+        })
+    }
+
+    /// Records each message spoken.
+    pub fn with_recorder(self, recorder: Recorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..self
+        }
+    }
+
+    fn command_args(&self, content: &str) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(voice) = &self.voice {
+            args.push("-v".to_owned());
+            args.push(voice.clone());
+        }
+        if self.options.rate != 1.0 {
+            args.push("-r".to_owned());
+            args.push((DEFAULT_WPM * self.options.rate).round().to_string());
+        }
+        args.push("--".to_owned());
+        args.push(format!("{}{content}", self.embedded_commands()));
+        args
+    }
+
+    /// Embedded speech commands for the pitch and volume, which have no command line options.
+    fn embedded_commands(&self) -> String {
+        let mut commands = String::new();
+        let semitones = self.options.semitones().round();
+        if semitones != 0.0 {
+            commands.push_str(&format!("[[pbas {semitones:+}]] "));
+        }
+        if self.options.volume != 1.0 {
+            let volume = self.options.volume.clamp(0.0, 1.0);
+            commands.push_str(&format!("[[volm {volume}]] "));
+        }
+        commands
+    }
+}
+
+impl Speaker for SaySpeaker {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            run_say(self.command_args(content), cancel).await?;
+            if let Some(recorder) = &self.recorder {
+                // `say` cannot play and save at once, so the message is synthesized again.
+                let path = recorder.next_path(content)?;
+                let mut args = vec![
+                    "-o".to_owned(),
+                    path.display().to_string(),
+                    "--file-format=WAVE".to_owned(),
+                    "--data-format=LEI16@22050".to_owned(),
+                ];
+                args.extend(self.command_args(content));
+                if let Err(report) = run_say(args, &CancelWatch::never()).await {
+                    warn!("cannot record `{content}`: {report:?}");
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        let output = Command::new(SAY_COMMAND)
+            .args(["-v", "?"])
+            .output()
+            .change_context(SpeakerError)
+            .attach("cannot list the `say` voices")?;
+        let listing = String::from_utf8_lossy(&output.stdout);
+        Ok(listing.lines().filter_map(parse_voice_line).collect())
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.voice = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.options = options;
+        Ok(())
+    }
+}
+
+/// Runs `say` until it exits, or kills it when cancelled or given up on (e.g. timed out), not to
+/// talk over the next message.
+async fn run_say(args: Vec<String>, cancel: &CancelWatch) -> SpeakerResult<()> {
+    let mut child = tokio::process::Command::new(SAY_COMMAND)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Killed and reaped in the background when the speech is dropped before it exits
+        .kill_on_drop(true)
+        .spawn()
+        .change_context(SpeakerError)
+        .attach("cannot run the `say` command")?;
+    let exited = tokio::select! {
+        status = child.wait() => Some(status),
+        () = cancel.cancelled() => None,
+    };
+    let Some(status) = exited else {
+        let _ = child.kill().await;
+        return Ok(());
+    };
+    let status = status
+        .change_context(SpeakerError)
+        .attach("cannot wait for `say`")?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Report::new(SpeakerError).attach(format!("`say` exited with {status}")))
+    }
+}
+
+/// Parses a line of `say -v ?`, e.g. `Alice               it_IT    # Ciao! Mi chiamo Alice.`
+fn parse_voice_line(line: &str) -> Option<Voice> {
+    let description = line.split('#').next()?.trim_end();
+    let (name, language) = description.rsplit_once(char::is_whitespace)?;
+    let name = name.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(Voice::new(name, language))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speaker(voice: Option<&str>, options: SpeechOptions) -> SaySpeaker {
+        SaySpeaker {
+            voice: voice.map(str::to_owned),
+            options,
+            recorder: None,
+        }
+    }
+
+    #[test]
+    fn command_args_with_defaults() {
+        let args = speaker(None, SpeechOptions::default()).command_args("Ora di partire!");
+
+        assert_eq!(vec!["--", "Ora di partire!"], args);
+    }
+
+    #[test]
+    fn command_args_with_voice_and_rate() {
+        let options = SpeechOptions {
+            rate: 0.8,
+            ..Default::default()
+        };
+
+        let args = speaker(Some("Alice"), options).command_args("Manca 1 minuto");
+
+        assert_eq!(
+            vec!["-v", "Alice", "-r", "140", "--", "Manca 1 minuto"],
+            args
+        );
+    }
+
+    #[test]
+    fn command_args_with_pitch_and_volume() {
+        let options = SpeechOptions {
+            pitch: 2.0,
+            volume: 0.5,
+            ..Default::default()
+        };
+
+        let args = speaker(None, options).command_args("Manca 1 minuto");
+
+        assert_eq!(vec!["--", "[[pbas +12]] [[volm 0.5]] Manca 1 minuto"], args);
+    }
+
+    #[test]
+    fn parse_voice_line_with_simple_name() {
+        let voice = parse_voice_line("Alice               it_IT    # Ciao! Mi chiamo Alice.");
+
+        assert_eq!(Some(Voice::new("Alice", "it_IT")), voice);
+    }
+
+    #[test]
+    fn parse_voice_line_with_name_containing_spaces() {
+        let voice = parse_voice_line("Bad News            en_US    # The light you see at the end");
+
+        assert_eq!(Some(Voice::new("Bad News", "en_US")), voice);
+    }
+
+    #[test]
+    fn parse_voice_line_without_language() {
+        assert_eq!(None, parse_voice_line("Alice"));
+        assert_eq!(None, parse_voice_line(""));
+    }
+}
@@ -0,0 +1,221 @@
+//! Speech synthesized on the machine by a VITS model of sherpa-onnx, downloaded on the first run.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, ResultExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use sherpa_rs::tts::{VitsTts, VitsTtsConfig};
+use tracing::{info, warn};
+
+use super::{
+    CancelWatch, Recorder, Speaker, SpeakerError, SpeakerFuture, SpeakerResult, SpeechOptions,
+    Voice, audio,
+};
+
+const MODEL_DIR_NAME: &str = "vits-piper-it_IT-paola-medium";
+const MODEL_VOICE: &str = "paola";
+const MODEL_LANGUAGE: &str = "it_IT";
+const MODEL_ONNX: &str = "it_IT-paola-medium.onnx";
+const MODEL_URL: &str = "https://github.com/k2-fsa/sherpa-onnx/releases/download/tts-models/vits-piper-it_IT-paola-medium.tar.bz2";
+const MODEL_DOWNLOAD_BYTES: u64 = 67_221_173;
+
+pub struct TTSSpeaker {
+    tts: VitsTts,
+    options: SpeechOptions,
+    prepared: HashMap<String, (Vec<f32>, u32)>,
+    recorder: Option<Recorder>,
+}
+
+impl TTSSpeaker {
+    pub fn new(model_path: Option<&Path>) -> SpeakerResult<Self> {
+        let model_dir = match model_path {
+            Some(p) => p.to_path_buf(),
+            None => {
+                let dir = default_model_dir();
+                ensure_model(&dir)?;
+                dir
+            }
+        };
+
+        validate_model_dir(&model_dir)?;
+        info!("Loading TTS model from {}", model_dir.display());
+
+        let config = VitsTtsConfig {
+            model: path_str(model_dir.join(MODEL_ONNX)),
+            tokens: path_str(model_dir.join("tokens.txt")),
+            data_dir: path_str(model_dir.join("espeak-ng-data")),
+            lexicon: String::new(),
+            length_scale: 1.0,
+            noise_scale: 0.667,
+            noise_scale_w: 0.8,
+            ..Default::default()
+        };
+
+        let tts = VitsTts::new(config);
+        Ok(Self {
+            tts,
+            options: SpeechOptions::default(),
+            prepared: HashMap::new(),
+            recorder: None,
+        })
+    }
+
+    /// Records each message spoken.
+    pub fn with_recorder(self, recorder: Recorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..self
+        }
+    }
+
+    /// Synthesizes `content`, returning the samples and the sample rate to play them at.
+    ///
+    /// The model cannot change the pitch, so it is shifted by playing the samples at a
+    /// proportionally higher (or lower) sample rate, synthesizing them slower (or faster) to
+    /// compensate.
+    fn synthesize(&mut self, content: &str) -> SpeakerResult<(Vec<f32>, u32)> {
+        let speed = self.options.rate / self.options.pitch;
+        let audio = self
+            .tts
+            .create(content, 0, speed)
+            .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?;
+        let sample_rate = (audio.sample_rate as f32 * self.options.pitch).round() as u32;
+        Ok((audio.samples, sample_rate))
+    }
+}
+
+impl Speaker for TTSSpeaker {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let (samples, sample_rate) = match self.prepared.get(content) {
+                Some(audio) => audio.clone(),
+                None => self.synthesize(content)?,
+            };
+            if let Some(recorder) = &self.recorder
+                && let Err(report) = recorder.record_samples(content, &samples, sample_rate)
+            {
+                warn!("cannot record `{content}`: {report:?}");
+            }
+            audio::play_samples(samples, sample_rate, self.options.volume, cancel).await
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        Ok(vec![Voice::new(MODEL_VOICE, MODEL_LANGUAGE)])
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        if name.eq_ignore_ascii_case(MODEL_VOICE) {
+            Ok(())
+        } else {
+            Err(Report::new(SpeakerError).attach(format!(
+                "the TTS model only provides the voice `{MODEL_VOICE}`"
+            )))
+        }
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        // Volume is applied when playing, the prepared audio is still good if only that changed.
+        if options.rate != self.options.rate || options.pitch != self.options.pitch {
+            self.prepared.clear();
+        }
+        self.options = options;
+        Ok(())
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            if !self.prepared.contains_key(content) {
+                let audio = self.synthesize(content)?;
+                self.prepared.insert(content.to_owned(), audio);
+            }
+            Ok(())
+        })
+    }
+}
+
+fn path_str(p: PathBuf) -> String {
+    p.to_string_lossy().into_owned()
+}
+
+fn default_model_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| {
+            std::env::var("HOME")
+                .map(PathBuf::from)
+                .unwrap_or_default()
+                .join(".local/share")
+        })
+        .join("rendezvous-coach")
+        .join("models")
+        .join(MODEL_DIR_NAME)
+}
+
+fn validate_model_dir(dir: &Path) -> SpeakerResult<()> {
+    for file in [MODEL_ONNX, "tokens.txt"] {
+        let p = dir.join(file);
+        if !p.exists() {
+            return Err(
+                Report::new(SpeakerError).attach(format!("missing model file: {}", p.display()))
+            );
+        }
+    }
+    let espeak = dir.join("espeak-ng-data");
+    if !espeak.exists() {
+        return Err(Report::new(SpeakerError)
+            .attach(format!("missing espeak-ng-data dir: {}", espeak.display())));
+    }
+    Ok(())
+}
+
+fn ensure_model(model_dir: &Path) -> SpeakerResult<()> {
+    if model_dir.join(MODEL_ONNX).exists() {
+        return Ok(());
+    }
+    let parent = model_dir
+        .parent()
+        .ok_or_else(|| Report::new(SpeakerError).attach("invalid model dir path"))?;
+    std::fs::create_dir_all(parent)
+        .change_context(SpeakerError)
+        .attach("cannot create model cache dir")?;
+    download_model(parent)
+}
+
+fn download_model(dest_parent: &Path) -> SpeakerResult<()> {
+    eprintln!("First run: downloading TTS model (~67 MB) ...");
+
+    let pb = ProgressBar::new(MODEL_DOWNLOAD_BYTES);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  {bar:40.cyan/blue} {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+
+    let response = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(600))
+        .build()
+        .get(MODEL_URL)
+        .call()
+        .map_err(|e| Report::new(SpeakerError).attach(e.to_string()))?;
+
+    if let Some(len) = response
+        .header("content-length")
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        pb.set_length(len);
+    }
+
+    let reader = pb.wrap_read(response.into_reader());
+    let bz = bzip2::read::BzDecoder::new(reader);
+    let mut archive = tar::Archive::new(bz);
+
+    archive
+        .unpack(dest_parent)
+        .change_context(SpeakerError)
+        .attach("cannot extract TTS model")?;
+
+    pb.finish_with_message("done");
+    Ok(())
+}
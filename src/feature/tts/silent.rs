@@ -0,0 +1,89 @@
+use std::io::Write;
+use std::process::Stdio;
+
+use tokio::process::Command;
+use tracing::debug;
+
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Voice};
+
+/// Fallback for machines where speech is not available: rings the terminal bell and shows each
+/// message as a desktop notification, when possible.
+#[derive(Debug, Default)]
+pub struct SilentSpeaker;
+
+impl Speaker for SilentSpeaker {
+    fn speak<'a>(&'a mut self, content: &'a str, _cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        Box::pin(async move {
+            let mut stdout = std::io::stdout();
+            if let Err(e) = stdout.write_all(b"\x07").and_then(|_| stdout.flush()) {
+                debug!("cannot ring the terminal bell: {e}");
+            }
+            match notification_command(content)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await
+            {
+                Ok(status) if !status.success() => debug!("desktop notification failed: {status}"),
+                Ok(_) => {}
+                Err(e) => debug!("cannot show a desktop notification: {e}"),
+            }
+            Ok(())
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        Ok(vec![])
+    }
+
+    fn set_voice(&mut self, _name: &str) -> SpeakerResult<()> {
+        Ok(())
+    }
+
+    fn set_options(&mut self, _options: SpeechOptions) -> SpeakerResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(content: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command.args([
+        "-e",
+        "on run argv",
+        "-e",
+        "display notification (item 1 of argv) with title \"rendezvous-coach\"",
+        "-e",
+        "end run",
+        content,
+    ]);
+    command
+}
+
+#[cfg(not(target_os = "macos"))]
+fn notification_command(content: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args([
+        "--app-name",
+        "rendezvous-coach",
+        "rendezvous-coach",
+        content,
+    ]);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_command_passes_the_message_as_a_single_argument() {
+        let command = notification_command("Mancano 5 minuti \"davvero\"");
+
+        assert_eq!(
+            Some("Mancano 5 minuti \"davvero\"".as_ref()),
+            command.as_std().get_args().last()
+        );
+    }
+}
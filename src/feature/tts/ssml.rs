@@ -0,0 +1,78 @@
+//! Helpers for SSML (Speech Synthesis Markup Language) messages
+
+/// Escapes plain text, to embed it in SSML markup.
+pub fn escape_ssml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Plain text of SSML markup, for backends that do not support it.
+pub fn strip_ssml(ssml: &str) -> String {
+    let mut text = String::with_capacity(ssml.len());
+    let mut rest = ssml;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        // Pauses separate words, other tags only wrap them.
+        if rest[start + 1..].starts_with("break") {
+            text.push(' ');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_ssml_keeps_the_text_only() {
+        assert_eq!(
+            "Ora di partire!",
+            strip_ssml("<speak><emphasis level=\"strong\">Ora di partire!</emphasis></speak>")
+        );
+        assert_eq!(
+            "Mancano 5 minuti",
+            strip_ssml("<speak>Mancano<break time=\"300ms\"/>5 minuti</speak>")
+        );
+    }
+
+    #[test]
+    fn strip_ssml_unescapes_entities() {
+        assert_eq!(
+            "Tom & Jerry <3",
+            strip_ssml("<speak>Tom &amp; Jerry &lt;3</speak>")
+        );
+    }
+
+    #[test]
+    fn escape_ssml_round_trips_through_strip() {
+        let text = "Mancano <5 minuti> & \"basta\"";
+
+        assert_eq!(
+            text,
+            strip_ssml(&format!("<speak>{}</speak>", escape_ssml(text)))
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use super::{CancelWatch, Speaker, SpeakerFuture, SpeakerResult, SpeechOptions, Utterance, Voice};
+use crate::plan::Urgency;
+
+/// How urgent notifications are spoken, overriding the normal voice and options when set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpeechProfile {
+    pub voice: Option<String>,
+    pub rate: Option<f32>,
+    pub pitch: Option<f32>,
+}
+
+impl SpeechProfile {
+    pub fn is_empty(&self) -> bool {
+        self.voice.is_none() && self.rate.is_none() && self.pitch.is_none()
+    }
+
+    fn apply(&self, options: SpeechOptions) -> SpeechOptions {
+        SpeechOptions {
+            rate: self.rate.unwrap_or(options.rate),
+            pitch: self.pitch.unwrap_or(options.pitch),
+            ..options
+        }
+    }
+}
+
+/// Speaker switching to a distinct profile for high and critical urgency notifications, so that
+/// the final calls stand out from the routine updates.
+pub struct UrgentSpeaker<S> {
+    inner: S,
+    profile: SpeechProfile,
+    voice: Option<String>,
+    options: SpeechOptions,
+}
+
+impl<S: Speaker> UrgentSpeaker<S> {
+    pub fn new(inner: S, profile: SpeechProfile) -> Self {
+        Self {
+            inner,
+            profile,
+            voice: None,
+            options: SpeechOptions::default(),
+        }
+    }
+
+    async fn announce_urgent(
+        &mut self,
+        utterance: &Utterance,
+        cancel: &CancelWatch,
+    ) -> SpeakerResult<()> {
+        if let Some(voice) = &self.profile.voice {
+            self.inner.set_voice(voice)?;
+        }
+        self.inner.set_options(self.profile.apply(self.options))?;
+        self.inner.announce(utterance, cancel).await
+    }
+
+    /// Goes back to the normal voice and options.
+    fn restore(&mut self) -> SpeakerResult<()> {
+        // Without a voice chosen explicitly, the backend default is left in place.
+        if let (Some(_), Some(voice)) = (&self.profile.voice, &self.voice) {
+            self.inner.set_voice(voice)?;
+        }
+        self.inner.set_options(self.options)
+    }
+}
+
+impl<S: Speaker> Speaker for UrgentSpeaker<S> {
+    fn speak<'a>(&'a mut self, content: &'a str, cancel: &'a CancelWatch) -> SpeakerFuture<'a> {
+        self.inner.speak(content, cancel)
+    }
+
+    fn announce<'a>(
+        &'a mut self,
+        utterance: &'a Utterance,
+        cancel: &'a CancelWatch,
+    ) -> SpeakerFuture<'a> {
+        if utterance.urgency < Urgency::High || self.profile.is_empty() {
+            return self.inner.announce(utterance, cancel);
+        }
+        Box::pin(async move {
+            let result = self.announce_urgent(utterance, cancel).await;
+            self.restore()?;
+            result
+        })
+    }
+
+    fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+        self.inner.voices()
+    }
+
+    fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+        self.inner.set_voice(name)?;
+        self.voice = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+        self.options = options;
+        self.inner.set_options(options)
+    }
+
+    fn prepare<'a>(&'a mut self, content: &'a str) -> SpeakerFuture<'a> {
+        self.inner.prepare(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::TimeSpan;
+
+    #[derive(Default)]
+    struct ProfileSpeaker {
+        voice: String,
+        rate: f32,
+        spoken: Vec<(String, String, f32)>,
+    }
+
+    impl Speaker for ProfileSpeaker {
+        fn speak<'a>(
+            &'a mut self,
+            content: &'a str,
+            _cancel: &'a CancelWatch,
+        ) -> SpeakerFuture<'a> {
+            Box::pin(async move {
+                self.spoken
+                    .push((content.to_owned(), self.voice.clone(), self.rate));
+                Ok(())
+            })
+        }
+
+        fn voices(&self) -> SpeakerResult<Vec<Voice>> {
+            Ok(vec![])
+        }
+
+        fn set_voice(&mut self, name: &str) -> SpeakerResult<()> {
+            self.voice = name.to_owned();
+            Ok(())
+        }
+
+        fn set_options(&mut self, options: SpeechOptions) -> SpeakerResult<()> {
+            self.rate = options.rate;
+            Ok(())
+        }
+    }
+
+    fn spoken(text: &str, voice: &str, rate: f32) -> (String, String, f32) {
+        (text.to_owned(), voice.to_owned(), rate)
+    }
+
+    #[tokio::test]
+    async fn urgent_speaker_uses_the_profile_for_urgent_notifications_only() {
+        let profile = SpeechProfile {
+            voice: Some("alarm".to_owned()),
+            rate: Some(0.8),
+            pitch: None,
+        };
+        let mut speaker = UrgentSpeaker::new(ProfileSpeaker::default(), profile);
+        let cancel = CancelWatch::never();
+        speaker.set_voice("calm").unwrap();
+        speaker.set_options(SpeechOptions::default()).unwrap();
+
+        speaker
+            .announce(
+                &Utterance::countdown("10", TimeSpan::of_minutes(10)),
+                &cancel,
+            )
+            .await
+            .unwrap();
+        speaker
+            .announce(&Utterance::countdown("4", TimeSpan::of_minutes(4)), &cancel)
+            .await
+            .unwrap();
+        speaker
+            .announce(&Utterance::countdown("go", TimeSpan::ZERO), &cancel)
+            .await
+            .unwrap();
+        speaker.speak("plain", &cancel).await.unwrap();
+
+        assert_eq!(
+            vec![
+                spoken("10", "calm", 1.0),
+                spoken("4", "alarm", 0.8),
+                spoken("go", "alarm", 0.8),
+                spoken("plain", "calm", 1.0),
+            ],
+            speaker.inner.spoken
+        );
+    }
+
+    #[tokio::test]
+    async fn urgent_speaker_keeps_the_voice_when_the_profile_has_none() {
+        let profile = SpeechProfile {
+            rate: Some(0.7),
+            ..Default::default()
+        };
+        let mut speaker = UrgentSpeaker::new(ProfileSpeaker::default(), profile);
+        speaker.set_voice("calm").unwrap();
+        speaker.set_options(SpeechOptions::default()).unwrap();
+
+        speaker
+            .announce(
+                &Utterance::countdown("go", TimeSpan::ZERO),
+                &CancelWatch::never(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(vec![spoken("go", "calm", 0.7)], speaker.inner.spoken);
+    }
+}
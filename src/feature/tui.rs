@@ -0,0 +1,555 @@
+//! Widgets of the terminal interface.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::Widget,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::plan::Urgency;
+
+/// A color for each urgency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByUrgency {
+    pub low: Color,
+    pub medium: Color,
+    pub high: Color,
+    pub critical: Color,
+}
+
+impl ByUrgency {
+    /// The same color whatever the urgency.
+    pub const fn all(color: Color) -> Self {
+        Self {
+            low: color,
+            medium: color,
+            high: color,
+            critical: color,
+        }
+    }
+
+    pub fn get(&self, urgency: Urgency) -> Color {
+        match urgency {
+            Urgency::Low => self.low,
+            Urgency::Medium => self.medium,
+            Urgency::High => self.high,
+            Urgency::Critical => self.critical,
+        }
+    }
+
+    fn get_mut(&mut self, urgency: Urgency) -> &mut Color {
+        match urgency {
+            Urgency::Low => &mut self.low,
+            Urgency::Medium => &mut self.medium,
+            Urgency::High => &mut self.high,
+            Urgency::Critical => &mut self.critical,
+        }
+    }
+}
+
+/// Colors of the terminal interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Departure time in the title
+    pub title: Color,
+    /// Gauge, by the urgency of the remaining time
+    pub gauge: ByUrgency,
+    /// Remaining time, by its urgency
+    pub countdown: ByUrgency,
+    /// Time of the notifications
+    pub time: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Messages, by their urgency
+    pub messages: ByUrgency,
+    /// Gauge and counter past departure, with --overdue
+    pub overdue: Color,
+}
+
+impl Theme {
+    /// Green, turning yellow then red as departure approaches.
+    pub const DEFAULT: Self = Self {
+        title: Color::Green,
+        gauge: ByUrgency {
+            low: Color::Green,
+            medium: Color::Yellow,
+            high: Color::Red,
+            critical: Color::Red,
+        },
+        countdown: ByUrgency {
+            low: Color::Green,
+            medium: Color::Yellow,
+            high: Color::Red,
+            critical: Color::Red,
+        },
+        time: Color::Gray,
+        warning: Color::Yellow,
+        error: Color::Red,
+        messages: ByUrgency {
+            low: Color::Reset,
+            medium: Color::Reset,
+            high: Color::Yellow,
+            critical: Color::Red,
+        },
+        overdue: Color::Magenta,
+    };
+
+    /// Blues and cyans.
+    pub const OCEAN: Self = Self {
+        title: Color::Cyan,
+        gauge: ByUrgency {
+            low: Color::Cyan,
+            medium: Color::Blue,
+            high: Color::LightMagenta,
+            critical: Color::LightMagenta,
+        },
+        countdown: ByUrgency {
+            low: Color::LightCyan,
+            medium: Color::LightBlue,
+            high: Color::LightMagenta,
+            critical: Color::LightMagenta,
+        },
+        time: Color::DarkGray,
+        warning: Color::LightYellow,
+        error: Color::LightRed,
+        messages: ByUrgency {
+            low: Color::Reset,
+            medium: Color::LightBlue,
+            high: Color::LightCyan,
+            critical: Color::LightMagenta,
+        },
+        overdue: Color::LightRed,
+    };
+
+    /// Dark colors, for terminals with a light background.
+    pub const LIGHT: Self = Self {
+        title: Color::Blue,
+        gauge: ByUrgency {
+            low: Color::Blue,
+            medium: Color::Indexed(130),
+            high: Color::Red,
+            critical: Color::Red,
+        },
+        countdown: ByUrgency {
+            low: Color::Blue,
+            medium: Color::Indexed(130),
+            high: Color::Red,
+            critical: Color::Red,
+        },
+        time: Color::DarkGray,
+        warning: Color::Indexed(130),
+        error: Color::Red,
+        messages: ByUrgency {
+            low: Color::Reset,
+            medium: Color::Blue,
+            high: Color::Indexed(130),
+            critical: Color::Red,
+        },
+        overdue: Color::Magenta,
+    };
+
+    /// The colors of the terminal, e.g. with `NO_COLOR`.
+    pub const MONO: Self = Self {
+        title: Color::Reset,
+        gauge: ByUrgency::all(Color::Reset),
+        countdown: ByUrgency::all(Color::Reset),
+        time: Color::Reset,
+        warning: Color::Reset,
+        error: Color::Reset,
+        messages: ByUrgency::all(Color::Reset),
+        overdue: Color::Reset,
+    };
+
+    /// Names of the themes, as given to [`Theme::named`].
+    pub const NAMES: [&str; 4] = ["default", "ocean", "light", "mono"];
+
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::DEFAULT),
+            "ocean" => Some(Self::OCEAN),
+            "light" => Some(Self::LIGHT),
+            "mono" => Some(Self::MONO),
+            _ => None,
+        }
+    }
+
+    /// Color of the messages of `urgency`.
+    pub fn urgency(&self, urgency: Urgency) -> Color {
+        self.messages.get(urgency)
+    }
+
+    /// Sets the color of the element `key`: `title`, `time`, `warning`, `error`, `overdue`, the
+    /// messages of an urgency (e.g. `high`), or the gauge or countdown, for every urgency
+    /// (`gauge`) or one (`gauge.high`).
+    pub fn set(&mut self, key: &str, color: Color) -> Result<(), String> {
+        let unknown = || {
+            format!(
+                "unknown element `{key}`, expected title, time, warning, error, overdue, low, \
+                 medium, high, critical, gauge[.URGENCY] or countdown[.URGENCY]"
+            )
+        };
+        let (element, urgency) = match key.split_once('.') {
+            Some((element, urgency)) => {
+                (element, Some(parse_urgency(urgency).ok_or_else(unknown)?))
+            }
+            None => (key, None),
+        };
+        match (element, urgency) {
+            ("gauge", None) => self.gauge = ByUrgency::all(color),
+            ("gauge", Some(urgency)) => *self.gauge.get_mut(urgency) = color,
+            ("countdown", None) => self.countdown = ByUrgency::all(color),
+            ("countdown", Some(urgency)) => *self.countdown.get_mut(urgency) = color,
+            ("title", None) => self.title = color,
+            ("time", None) => self.time = color,
+            ("warning", None) => self.warning = color,
+            ("error", None) => self.error = color,
+            ("overdue", None) => self.overdue = color,
+            (name, None) => {
+                *self
+                    .messages
+                    .get_mut(parse_urgency(name).ok_or_else(unknown)?) = color
+            }
+            _ => return Err(unknown()),
+        }
+        Ok(())
+    }
+}
+
+fn parse_urgency(name: &str) -> Option<Urgency> {
+    match name {
+        "low" => Some(Urgency::Low),
+        "medium" => Some(Urgency::Medium),
+        "high" => Some(Urgency::High),
+        "critical" => Some(Urgency::Critical),
+        _ => None,
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Symbol and name of `urgency`, telling it apart without colors, e.g. `● HIGH`.
+pub fn urgency_marker(urgency: Urgency) -> &'static str {
+    match urgency {
+        Urgency::Low => "○ low",
+        Urgency::Medium => "◐ medium",
+        Urgency::High => "● HIGH",
+        Urgency::Critical => "‼ GO",
+    }
+}
+
+/// Symbols of the terminal interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Departure
+    pub car: &'static str,
+    /// Remaining time
+    pub alarm: &'static str,
+    /// Before a message
+    pub arrow: &'static str,
+    pub clock: &'static str,
+    /// Elapsed time
+    pub stopwatch: &'static str,
+    pub muted: &'static str,
+    pub paused: &'static str,
+    pub snoozed: &'static str,
+    /// Time to the next notification
+    pub hourglass: &'static str,
+    pub speaking: &'static str,
+    /// Speech backend
+    pub voice: &'static str,
+    pub warning: &'static str,
+    /// Sink delivering
+    pub ok: &'static str,
+    /// Sink failing
+    pub failed: &'static str,
+}
+
+impl Glyphs {
+    pub const EMOJI: Self = Self {
+        car: "🚗",
+        alarm: "⏰",
+        arrow: "➡",
+        clock: "🕐",
+        stopwatch: "⏱",
+        muted: "🔇",
+        paused: "⏸",
+        snoozed: "💤",
+        hourglass: "⏳",
+        speaking: "🔊",
+        voice: "🗣",
+        warning: "⚠",
+        ok: "✓",
+        failed: "✗",
+    };
+
+    /// Plain ASCII, for the fonts and terminals showing the emoji as double-width boxes, which
+    /// break the layout.
+    pub const ASCII: Self = Self {
+        car: "[>]",
+        alarm: "(!)",
+        arrow: "->",
+        clock: "@",
+        stopwatch: "(+)",
+        muted: "(muted)",
+        paused: "||",
+        snoozed: "zZ",
+        hourglass: ">>",
+        speaking: "<))",
+        voice: "tts:",
+        warning: "!",
+        ok: "ok",
+        failed: "x",
+    };
+}
+
+/// Wraps `text` on lines at most `width` columns wide, between the words (or within those too
+/// long for a line), cutting it with an ellipsis after `max_lines`.
+pub fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let separator = usize::from(!line.is_empty());
+        if line.width() + separator + word.width() <= width {
+            if separator > 0 {
+                line.push(' ');
+            }
+            line.push_str(word);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            if line.width() + c.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    let max_lines = max_lines.max(1);
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        while !last.is_empty() && last.width() + 1 > width {
+            last.pop();
+        }
+        last.truncate(last.trim_end().len());
+        last.push('…');
+    }
+    lines
+}
+
+/// Marks along a gauge where some moments fall, e.g. the upcoming notifications, on the line
+/// below it.
+pub struct Markers {
+    /// Where the line of the gauge starts, after its label
+    offset: u16,
+    /// Position of each mark, as the ratio of the gauge filled at that moment
+    marks: Vec<(f64, Style)>,
+}
+
+impl Markers {
+    pub fn new(offset: u16) -> Self {
+        Self {
+            offset,
+            marks: Vec::new(),
+        }
+    }
+
+    pub fn mark(mut self, ratio: f64, style: Style) -> Self {
+        self.marks.push((ratio, style));
+        self
+    }
+}
+
+impl Widget for Markers {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width.saturating_sub(self.offset);
+        if width == 0 || area.height == 0 {
+            return;
+        }
+        for (ratio, style) in self.marks {
+            let column = (ratio.clamp(0.0, 1.0) * f64::from(width - 1)).round() as u16;
+            buf[(area.x + self.offset + column, area.y)]
+                .set_symbol("▲")
+                .set_style(style);
+        }
+    }
+}
+
+/// Height of the big characters, in lines.
+pub const BIG_HEIGHT: u16 = 5;
+
+/// Glyph of a character in big, one string per line, `None` when there is none.
+fn glyph(c: char) -> Option<[&'static str; BIG_HEIGHT as usize]> {
+    let glyph = match c {
+        '0' => ["█████", "█   █", "█   █", "█   █", "█████"],
+        '1' => ["   █ ", "  ██ ", "   █ ", "   █ ", "  ███"],
+        '2' => ["█████", "    █", "█████", "█    ", "█████"],
+        '3' => ["█████", "    █", " ████", "    █", "█████"],
+        '4' => ["█   █", "█   █", "█████", "    █", "    █"],
+        '5' => ["█████", "█    ", "█████", "    █", "█████"],
+        '6' => ["█████", "█    ", "█████", "█   █", "█████"],
+        '7' => ["█████", "    █", "   █ ", "  █  ", "  █  "],
+        '8' => ["█████", "█   █", "█████", "█   █", "█████"],
+        '9' => ["█████", "█   █", "█████", "    █", "█████"],
+        ':' => ["   ", " █ ", "   ", " █ ", "   "],
+        '-' | '−' => ["     ", "     ", "█████", "     ", "     "],
+        ' ' => ["  ", "  ", "  ", "  ", "  "],
+        _ => return None,
+    };
+    Some(glyph)
+}
+
+/// Lines of `text` in big characters, one space apart; the characters without a big glyph are
+/// skipped.
+pub fn big_lines(text: &str) -> [String; BIG_HEIGHT as usize] {
+    let mut lines: [String; BIG_HEIGHT as usize] = Default::default();
+    for (i, glyph) in text.chars().filter_map(glyph).enumerate() {
+        for (line, row) in lines.iter_mut().zip(glyph) {
+            if i > 0 {
+                line.push(' ');
+            }
+            line.push_str(row);
+        }
+    }
+    lines
+}
+
+/// Text in big characters, centered in its area, e.g. the countdown to be read from across the
+/// room.
+///
+/// Falls back to the plain text when the area is too small.
+pub struct BigText {
+    text: String,
+    style: Style,
+}
+
+impl BigText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: Style::default(),
+        }
+    }
+
+    pub fn style(self, style: Style) -> Self {
+        Self { style, ..self }
+    }
+}
+
+impl Widget for BigText {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines = big_lines(&self.text);
+        let width = lines[0].chars().count() as u16;
+        if area.width < width || area.height < BIG_HEIGHT {
+            let [line] = Layout::vertical([Constraint::Length(1)])
+                .flex(Flex::Center)
+                .areas(area);
+            Line::styled(self.text, self.style)
+                .centered()
+                .render(line, buf);
+            return;
+        }
+        let [text_area] = Layout::vertical([Constraint::Length(BIG_HEIGHT)])
+            .flex(Flex::Center)
+            .areas(area);
+        for (line, y) in lines.iter().zip(text_area.y..) {
+            let row = Rect::new(text_area.x, y, text_area.width, 1);
+            Line::styled(line.as_str(), self.style)
+                .centered()
+                .render(row, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_between_the_words() {
+        let message = "Mancano 45 minuti alla partenza";
+
+        assert_eq!(vec![message], wrap(message, 80, 3));
+        assert_eq!(
+            vec!["Mancano 45", "minuti alla", "partenza"],
+            wrap(message, 12, 3)
+        );
+        assert_eq!(vec!["Mancano 45", "minuti alla…"], wrap(message, 12, 2));
+    }
+
+    #[test]
+    fn wrap_counts_the_wide_characters() {
+        assert_eq!(vec!["🚗🚗", "🚗"], wrap("🚗🚗🚗", 4, 3));
+        assert_eq!(vec!["🚗…"], wrap("🚗🚗🚗", 4, 1));
+    }
+
+    #[test]
+    fn theme_set_colors() {
+        let mut theme = Theme::named("ocean").unwrap();
+
+        theme.set("gauge", Color::Yellow).unwrap();
+        theme.set("gauge.critical", Color::Red).unwrap();
+        theme.set("critical", Color::Rgb(255, 0, 0)).unwrap();
+
+        assert_eq!(Color::Yellow, theme.gauge.get(Urgency::Medium));
+        assert_eq!(Color::Red, theme.gauge.get(Urgency::Critical));
+        assert_eq!(Color::Rgb(255, 0, 0), theme.urgency(Urgency::Critical));
+        assert!(theme.set("background", Color::Black).is_err());
+        assert!(theme.set("gauge.urgent", Color::Black).is_err());
+        assert!(theme.set("title.high", Color::Black).is_err());
+        assert!(Theme::named("neon").is_none());
+    }
+
+    #[test]
+    fn markers_along_the_gauge() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 15, 1));
+
+        Markers::new(5)
+            .mark(0.0, Style::default())
+            .mark(0.5, Style::default())
+            .mark(1.0, Style::default())
+            .render(buf.area, &mut buf);
+
+        let line: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert_eq!("     ▲    ▲   ▲", line);
+    }
+
+    #[test]
+    fn big_lines_of_a_time() {
+        let lines = big_lines("1:05");
+
+        assert_eq!(
+            [
+                "   █      █████ █████",
+                "  ██   █  █   █ █    ",
+                "   █      █   █ █████",
+                "   █   █  █   █     █",
+                "  ███     █████ █████",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn big_text_falls_back_to_plain_text_when_narrow() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 5));
+
+        BigText::new("00:15:00").render(buf.area, &mut buf);
+
+        let screen: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("00:15:00"));
+    }
+}
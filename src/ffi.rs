@@ -0,0 +1,206 @@
+//! C ABI of the planner, for native apps (e.g. on mobile) to embed the same coaching: a plan from
+//! the rendezvous and the trip, the notifications planned for it, and the messages of the coach.
+//!
+//! The times are seconds since the Unix epoch. What is returned is owned by the caller, and given
+//! back to the matching `rc_*_free` function.
+
+use std::ffi::{CString, c_char};
+
+use crate::feature::coach::{Coach, DefaultItCoach};
+use crate::plan::{Plan, Urgency};
+use crate::time::{TimeSpan, Timestamp};
+
+/// A rendezvous and the trip to it.
+pub struct RcPlan(Plan);
+
+/// A notification planned.
+#[repr(C)]
+pub struct RcNotification {
+    /// When it is due
+    pub time: i64,
+    /// Time remaining before departure then
+    pub remaining_secs: u64,
+    /// 0 (low) to 3 (critical)
+    pub urgency: u8,
+    /// What the coach says, in UTF-8
+    pub message: *mut c_char,
+}
+
+/// The notifications planned, in time order.
+#[repr(C)]
+pub struct RcSchedule {
+    pub items: *mut RcNotification,
+    pub len: usize,
+}
+
+impl RcSchedule {
+    const EMPTY: Self = Self {
+        items: std::ptr::null_mut(),
+        len: 0,
+    };
+}
+
+/// A plan for the rendezvous at `rendezvous_time`, `trip_secs` away, or null when the time or
+/// the departure is out of range.
+#[unsafe(no_mangle)]
+pub extern "C" fn rc_plan_new(rendezvous_time: i64, trip_secs: u64) -> *mut RcPlan {
+    let Ok(rendezvous_time) = Timestamp::from_unix(rendezvous_time) else {
+        return std::ptr::null_mut();
+    };
+    let trip_duration = TimeSpan::of_seconds(trip_secs);
+    // Checked once here, not to panic across the C ABI when the departure is computed later
+    if rendezvous_time.checked_sub(trip_duration).is_none() {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(RcPlan(Plan {
+        rendezvous_time,
+        trip_duration,
+    })))
+}
+
+/// Frees a plan from [`rc_plan_new`].
+///
+/// # Safety
+///
+/// `plan` is null, or from [`rc_plan_new`] and not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rc_plan_free(plan: *mut RcPlan) {
+    if !plan.is_null() {
+        drop(unsafe { Box::from_raw(plan) });
+    }
+}
+
+/// The departure time of `plan`.
+///
+/// # Safety
+///
+/// `plan` is from [`rc_plan_new`] and not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rc_plan_departure(plan: *const RcPlan) -> i64 {
+    let plan = unsafe { &(*plan).0 };
+    plan.departure_time().to_unix()
+}
+
+/// The notifications planned from `now` to departure, empty when `now` is out of range.
+///
+/// # Safety
+///
+/// `plan` is from [`rc_plan_new`] and not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rc_plan_schedule(plan: *const RcPlan, now: i64) -> RcSchedule {
+    let plan = unsafe { &(*plan).0 };
+    let Ok(now) = Timestamp::from_unix(now) else {
+        return RcSchedule::EMPTY;
+    };
+    let Ok(mut notifications) = plan.notifications(&now, &DefaultItCoach) else {
+        return RcSchedule::EMPTY;
+    };
+    // Planned backward from departure
+    notifications.reverse();
+    let departure = plan.departure_time();
+    let items: Box<[RcNotification]> = notifications
+        .into_iter()
+        .map(|n| RcNotification {
+            time: n.time.to_unix(),
+            remaining_secs: departure.time_span_from(&n.time).total_secs(),
+            urgency: urgency_code(n.urgency),
+            message: c_string(n.message),
+        })
+        .collect();
+    let len = items.len();
+    RcSchedule {
+        items: Box::into_raw(items).cast(),
+        len,
+    }
+}
+
+/// Frees a schedule from [`rc_plan_schedule`], with its messages.
+///
+/// # Safety
+///
+/// `schedule` is from [`rc_plan_schedule`], not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rc_schedule_free(schedule: RcSchedule) {
+    if schedule.items.is_null() {
+        return;
+    }
+    let items = std::ptr::slice_from_raw_parts_mut(schedule.items, schedule.len);
+    for item in unsafe { Box::from_raw(items) } {
+        unsafe { rc_string_free(item.message) };
+    }
+}
+
+/// What the coach says with `remaining_secs` left before departure, e.g. `Mancano 5 minuti`.
+#[unsafe(no_mangle)]
+pub extern "C" fn rc_remaining_message(remaining_secs: u64) -> *mut c_char {
+    c_string(DefaultItCoach.remaining_time_message(&TimeSpan::of_seconds(remaining_secs)))
+}
+
+/// Frees a string returned by the functions above.
+///
+/// # Safety
+///
+/// `string` is null, or returned by these functions and not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rc_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+fn urgency_code(urgency: Urgency) -> u8 {
+    match urgency {
+        Urgency::Low => 0,
+        Urgency::Medium => 1,
+        Urgency::High => 2,
+        Urgency::Critical => 3,
+    }
+}
+
+/// `string` for C, without the NUL characters it cannot hold.
+fn c_string(string: String) -> *mut c_char {
+    CString::new(string.replace('\0', ""))
+        .unwrap_or_default()
+        .into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    #[test]
+    fn schedule_through_the_c_abi() {
+        let rendezvous = Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap();
+        let now = Timestamp::new(2025, 10, 24, 19, 35, 00).unwrap();
+        let plan = rc_plan_new(rendezvous.to_unix(), 15 * 60);
+
+        unsafe {
+            assert_eq!(now.to_unix() + 10 * 60, rc_plan_departure(plan));
+            let schedule = rc_plan_schedule(plan, now.to_unix());
+            let items = std::slice::from_raw_parts(schedule.items, schedule.len);
+            assert_eq!(now.to_unix(), items[0].time);
+            assert_eq!(10 * 60, items[0].remaining_secs);
+            let last = &items[schedule.len - 1];
+            assert_eq!((0, 3), (last.remaining_secs, last.urgency));
+            assert_eq!(
+                "Ora di partire!",
+                CStr::from_ptr(last.message).to_str().unwrap()
+            );
+            rc_schedule_free(schedule);
+            rc_plan_free(plan);
+        }
+    }
+
+    #[test]
+    fn plan_out_of_range_is_null() {
+        let rendezvous = Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap().to_unix();
+
+        assert!(rc_plan_new(rendezvous, u64::MAX).is_null());
+        assert!(rc_plan_new(rendezvous, i64::MAX as u64 / 1000 + 1).is_null());
+        // Within the durations, but before the earliest date
+        assert!(rc_plan_new(rendezvous, 10_000_000_000_000).is_null());
+        assert!(rc_plan_new(i64::MAX, 0).is_null());
+    }
+}
@@ -1,7 +1,11 @@
+use std::path::PathBuf;
+
 use error_stack::{fmt::ColorMode, Report};
 use owo_colors::OwoColorize;
+use tracing::level_filters::LevelFilter;
 
 use crate::error::Suggestion;
+use crate::feature::logs::RecentLogs;
 
 pub fn error_reporting() {
     Report::set_color_mode(ColorMode::Color);
@@ -16,15 +20,53 @@ pub fn error_reporting() {
     });
 }
 
-pub fn tracing() {
+/// Where and how the events are logged.
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    /// Least level logged, else as told by `RUST_LOG`
+    pub level: Option<LevelFilter>,
+    /// File the events are written to rather than the standard error, a new one each day with
+    /// the date appended, e.g. `rendezvous.log.2025-10-24`
+    pub file: Option<PathBuf>,
+    /// One JSON object per event and line, e.g. for a log collector
+    pub json: bool,
+}
+
+/// Sets up the logging as told by `options`, returning the last events from info up, kept for the
+/// terminal interface.
+pub fn tracing(options: &LogOptions) -> RecentLogs {
     use tracing_error::ErrorLayer;
-    use tracing_subscriber::EnvFilter;
     use tracing_subscriber::layer::SubscriberExt;
     use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::{EnvFilter, Layer, Registry};
 
+    let filter = match options.level {
+        Some(level) => EnvFilter::default().add_directive(level.into()),
+        None => EnvFilter::builder().from_env_lossy(),
+    };
+    let layer = tracing_subscriber::fmt::layer();
+    let output: Box<dyn Layer<Registry> + Send + Sync> = match &options.file {
+        None if options.json => layer.json().boxed(),
+        None => layer.pretty().boxed(),
+        Some(path) => {
+            let dir = path.parent().unwrap_or(std::path::Path::new("."));
+            let name = path.file_name().unwrap_or("rendezvous-coach.log".as_ref());
+            let layer = layer
+                .with_ansi(false)
+                .with_writer(tracing_appender::rolling::daily(dir, name));
+            if options.json {
+                layer.json().boxed()
+            } else {
+                layer.boxed()
+            }
+        }
+    };
+
+    let recent_logs = RecentLogs::new(100);
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer().pretty())
-        .with(EnvFilter::builder().from_env_lossy())
+        .with(output.with_filter(filter))
+        .with(recent_logs.clone().with_filter(LevelFilter::INFO))
         .with(ErrorLayer::default())
         .init();
-}
\ No newline at end of file
+    recent_logs
+}
@@ -1,6 +1,12 @@
+#[cfg(feature = "tui")]
+pub mod app;
 pub mod common;
+pub mod engine;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod init;
 pub mod time;
 pub mod feature;
-pub mod plan;
\ No newline at end of file
+pub mod plan;
+pub mod session;
@@ -5,28 +5,81 @@ use crate::{
     time::{TimeSpan, Timestamp},
 };
 
-#[derive(Debug, thiserror::Error)]
-#[error("planning error")]
-pub struct PlanError;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PlanError {
+    #[error("cannot plan the notifications")]
+    Notifications,
+}
 
 pub type PlanResult<T> = Result<T, Report<PlanError>>;
 
+/// How pressing a notification is, given the time remaining before departure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Urgency {
+    /// Half an hour or more to go
+    Low,
+    /// Less than half an hour to go
+    Medium,
+    /// Less than 5 minutes to go
+    High,
+    /// Time to leave
+    Critical,
+}
+
+impl Urgency {
+    pub fn for_remaining(remaining_time: &TimeSpan) -> Self {
+        if remaining_time.is_zero() {
+            Urgency::Critical
+        } else if remaining_time < &TimeSpan::of_minutes(5) {
+            Urgency::High
+        } else if remaining_time < &TimeSpan::of_minutes(30) {
+            Urgency::Medium
+        } else {
+            Urgency::Low
+        }
+    }
+
+    /// Lowercase name, e.g. `critical`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Urgency::Low => "low",
+            Urgency::Medium => "medium",
+            Urgency::High => "high",
+            Urgency::Critical => "critical",
+        }
+    }
+
+    /// The urgency of a name, as given by [`Urgency::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Urgency::Low,
+            Urgency::Medium,
+            Urgency::High,
+            Urgency::Critical,
+        ]
+        .into_iter()
+        .find(|urgency| urgency.name() == name)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Notification {
     pub time: Timestamp,
     pub message: String,
+    pub urgency: Urgency,
 }
 
 impl Clone for Notification {
     fn clone(&self) -> Self {
         Self {
-            time: self.time.clone(),
+            time: self.time,
             message: self.message.clone(),
+            urgency: self.urgency,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Plan {
     pub rendezvous_time: Timestamp,
     pub trip_duration: TimeSpan,
@@ -55,6 +108,7 @@ impl Plan {
             let notification = Notification {
                 time: time_cursor,
                 message: coach.remaining_time_message(&remaining_time),
+                urgency: Urgency::for_remaining(&remaining_time),
             };
             notifications.push(notification);
 
@@ -81,9 +135,21 @@ mod tests {
 
     struct TestCoach;
     impl Coach for TestCoach {
+        fn language(&self) -> &str {
+            "en"
+        }
+
         fn remaining_time_message(&self, remaining_time: &TimeSpan) -> String {
             format!("remaining: {:?}", remaining_time)
         }
+
+        fn departure_changed_message(&self, departure_time: &Timestamp) -> String {
+            format!("departure: {}", departure_time.to_hh_mm())
+        }
+
+        fn abandoned_message(&self, remaining_time: &TimeSpan) -> String {
+            format!("abandoned: {:?}", remaining_time)
+        }
     }
 
     fn notification_go(rendezvous_time: Timestamp) -> Notification {
@@ -94,9 +160,31 @@ mod tests {
         Notification {
             time: rendezvous_time - time_span,
             message: TestCoach.remaining_time_message(&time_span),
+            urgency: Urgency::for_remaining(&time_span),
         }
     }
 
+    #[test]
+    fn urgency_for_remaining_time() {
+        assert_eq!(Urgency::Critical, Urgency::for_remaining(&TimeSpan::ZERO));
+        assert_eq!(
+            Urgency::High,
+            Urgency::for_remaining(&TimeSpan::new(0, 4, 59))
+        );
+        assert_eq!(
+            Urgency::Medium,
+            Urgency::for_remaining(&TimeSpan::of_minutes(5))
+        );
+        assert_eq!(
+            Urgency::Medium,
+            Urgency::for_remaining(&TimeSpan::new(0, 29, 59))
+        );
+        assert_eq!(
+            Urgency::Low,
+            Urgency::for_remaining(&TimeSpan::of_minutes(30))
+        );
+    }
+
     #[test]
     fn departure_time() {
         let plan = Plan {
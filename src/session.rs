@@ -0,0 +1,600 @@
+//! A session of the coach, with or without the terminal interface: the countdown, the sinks and
+//! the history it feeds, and the commands of other programs driving it.
+
+use std::fs::File;
+#[cfg(feature = "control")]
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use error_stack::ResultExt;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::engine::{Clock, Engine, SystemClock};
+use crate::error::{AppError, AppResult};
+use crate::feature::coach::Coach;
+#[cfg(feature = "control")]
+use crate::feature::control::{ControlCommand, ControlRequest, ControlServer};
+#[cfg(feature = "history")]
+use crate::feature::history::{History, SessionEnd, SessionId};
+use crate::feature::sink::{Dispatcher, SessionLog};
+use crate::feature::state::StateFile;
+use crate::feature::tts::{Announcer, Utterance};
+#[cfg(feature = "control")]
+use crate::plan::Notification;
+use crate::plan::{Plan, Urgency};
+use crate::time::{TimeSpan, Timestamp};
+
+/// The runtime a session runs on: a single thread, the speech, the sinks and the control server
+/// having their own, with the drivers of the time and of the signals interrupting the session.
+pub fn runtime() -> AppResult<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .change_context(AppError::Other)
+        .attach("cannot start the runtime")
+}
+
+/// Starts the session of the command line again in the background, answering on `socket` (added
+/// to the options unless `socket_given` already), so that it goes on once the terminal is closed.
+/// The id of the process.
+#[cfg(feature = "control")]
+pub fn detach(socket: &Path, socket_given: bool) -> AppResult<u32> {
+    if cfg!(not(unix)) {
+        return Err(AppError::Session)
+            .attach("detaching needs a Unix socket to attach to, not available on this platform");
+    }
+    let program = std::env::current_exe().change_context(AppError::Other)?;
+    let mut command = std::process::Command::new(program);
+    command
+        .args(std::env::args_os().skip(1).filter(|arg| arg != "--detach"))
+        .arg("--headless")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    if !socket_given {
+        command.arg("--control-socket").arg(socket);
+    }
+    // Out of the process group of the terminal, not hung up along with it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let child = command
+        .spawn()
+        .change_context(AppError::Session)
+        .attach("cannot start the session in the background")?;
+    Ok(child.id())
+}
+
+/// Without the control server, no request ever comes.
+#[cfg(not(feature = "control"))]
+pub enum ControlRequest {}
+
+#[cfg(feature = "control")]
+fn notification_json(n: &Notification) -> serde_json::Value {
+    serde_json::json!({
+        "time": n.time.to_rfc3339(),
+        "message": n.message,
+        "urgency": n.urgency.name(),
+    })
+}
+
+pub struct Session<C> {
+    /// The countdown, with the notifications planned and emitted
+    pub engine: Engine<C>,
+    /// Where the notifications are delivered besides the terminal and the speaker
+    pub sinks: Dispatcher,
+    /// Where the notifications are logged, with the outcome on each channel
+    pub session_log: Option<SessionLog<File>>,
+    /// Where the session is stored
+    #[cfg(feature = "history")]
+    pub history: Option<(History, SessionId)>,
+    /// Where the state of the session is saved at every tick, to resume it after a crash
+    pub state_file: Option<StateFile>,
+    #[cfg(feature = "control")]
+    pub control: Option<ControlServer>,
+    /// How much later the push command moves the rendezvous by default
+    pub push_increment: TimeSpan,
+    /// Set on SIGINT or SIGTERM, abandoning the session
+    pub interrupted: Arc<AtomicBool>,
+    pub exit: bool,
+}
+
+impl<C: Coach> Session<C> {
+    pub fn new(plan: &Plan, coach: C, max_messages: usize) -> AppResult<Self> {
+        let now = Timestamp::now().change_context(AppError::Other)?;
+        let engine = Engine::new(plan, coach, max_messages, now)
+            .change_context(AppError::InvalidRendezvous)?;
+        Ok(Self::with_engine(engine))
+    }
+
+    /// A session counting down with `engine`, e.g. started at another time than now.
+    pub fn with_engine(engine: Engine<C>) -> Self {
+        Self {
+            engine,
+            sinks: Dispatcher::default(),
+            session_log: None,
+            #[cfg(feature = "history")]
+            history: None,
+            state_file: None,
+            #[cfg(feature = "control")]
+            control: None,
+            push_increment: TimeSpan::of_minutes(5),
+            interrupted: Arc::default(),
+            exit: false,
+        }
+    }
+
+    /// Pushes the rendezvous `span` later.
+    pub fn push(&mut self, span: TimeSpan, now: &Timestamp) {
+        if let Err(report) = self.engine.push(span, now) {
+            warn!("cannot push the rendezvous: {report:?}");
+        }
+    }
+
+    /// Ticks the engine at the time of `clock`, logging, storing and saving what was emitted.
+    /// Returns the time of the tick.
+    pub fn tick<S: Announcer>(
+        &mut self,
+        clock: &impl Clock,
+        speaker: &mut S,
+    ) -> AppResult<Timestamp> {
+        let tick = self
+            .engine
+            .tick(clock, &mut self.sinks, speaker)
+            .change_context(AppError::Other)?;
+        self.exit |= tick.over;
+        if let Some(emitted) = tick.emitted {
+            let n = &emitted.notification;
+            if let Some(log) = &mut self.session_log
+                && let Err(report) = log.record(n, emitted.remaining, &emitted.outcomes)
+            {
+                warn!("cannot log `{}`: {report:?}", n.message);
+            }
+            #[cfg(feature = "history")]
+            if let Some((history, session)) = &self.history
+                && let Err(report) = history.record_notification(*session, n, emitted.remaining)
+            {
+                warn!("cannot store `{}` in the history: {report:?}", n.message);
+            }
+            emitted.announced.change_context(AppError::Speech)?;
+        }
+        if let Some(file) = &mut self.state_file
+            && let Err(report) = file.save(&self.engine.state())
+        {
+            warn!("cannot save the state of the session: {report:?}");
+        }
+        Ok(tick.now)
+    }
+
+    /// Counts down without the terminal interface, controlled only through the control server,
+    /// whose commands are carried out as they come.
+    pub async fn run_headless<S: Announcer>(&mut self, speaker: &mut S) -> AppResult<()> {
+        let tick = tokio::time::sleep(Duration::ZERO);
+        tokio::pin!(tick);
+        let mut ticking = true;
+        let interruption = interruption();
+        tokio::pin!(interruption);
+        loop {
+            tokio::select! {
+                () = &mut tick, if ticking => {
+                    let now = self.tick(&SystemClock, speaker)?;
+                    if self.engine.remaining_time(&now) == TimeSpan::ZERO && !self.engine.overdue {
+                        self.exit = true;
+                    }
+                    match self.until_next_tick(&now) {
+                        Some(wait) => tick.as_mut().reset(Instant::now() + wait),
+                        None => ticking = false,
+                    }
+                }
+                request = self.next_request() => {
+                    self.answer(request)?;
+                    // Acted upon by a tick right away, e.g. speaking now
+                    tick.as_mut().reset(Instant::now());
+                    ticking = true;
+                }
+                () = &mut interruption => self.interrupted.store(true, Ordering::Relaxed),
+            }
+            self.exit |= self.interrupted.load(Ordering::Relaxed);
+
+            if self.exit {
+                let now = Timestamp::now().change_context(AppError::Other)?;
+                self.finish(speaker, &now);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// How long until the next tick: at the next whole second when a sink keeps up with the
+    /// remaining time, otherwise when the engine has something to do next, if ever, rather than
+    /// waking up every second for nothing.
+    pub fn until_next_tick(&self, now: &Timestamp) -> Option<Duration> {
+        if self.sinks.wants_updates() {
+            return Some(Timestamp::until_next_second());
+        }
+        let deadline = self.engine.next_deadline(now)?;
+        // `now` being the start of the current second
+        match deadline.time_span_from(now).total_secs() {
+            0 => Some(Duration::ZERO),
+            secs => Some(Duration::from_secs(secs - 1) + Timestamp::until_next_second()),
+        }
+    }
+
+    /// Ends the session, with a farewell when it is abandoned before departure.
+    pub fn finish<S: Announcer>(&mut self, speaker: &mut S, now: &Timestamp) {
+        let remaining = self.engine.finish(now);
+        self.sinks.finish(remaining);
+        if !remaining.is_zero() {
+            let farewell = self.engine.coach.abandoned_message(&remaining);
+            info!("Abandoned at T-{remaining}");
+            if let Err(report) = speaker.announce(&Utterance::message(
+                &farewell,
+                Urgency::for_remaining(&remaining),
+            )) {
+                warn!("cannot speak the farewell: {report:?}");
+            }
+        }
+        #[cfg(feature = "history")]
+        if let Some((history, session)) = &self.history {
+            let end = if remaining.is_zero() {
+                SessionEnd::Departed
+            } else {
+                SessionEnd::Abandoned
+            };
+            if let Err(report) = history.finish(*session, now, end) {
+                warn!("cannot store the end of the session in the history: {report:?}");
+            }
+        }
+        // Kept when interrupted by a signal, e.g. on shutdown, to resume the session
+        if let Some(file) = &self.state_file
+            && !self.interrupted.load(Ordering::Relaxed)
+            && let Err(report) = file.remove()
+        {
+            warn!("cannot remove the state of the session: {report:?}");
+        }
+    }
+
+    /// Catches up after the process was stopped (e.g. suspended): the notifications gone by are
+    /// dropped, as they would be stale, and the remaining time is spoken instead.
+    pub fn resync(&mut self, now: &Timestamp) {
+        let missed = self.engine.resync(now);
+        if missed > 0 {
+            info!("Resumed, skipping {missed} notifications gone by");
+        }
+    }
+
+    #[cfg(feature = "control")]
+    pub fn status(&self, now: &Timestamp) -> serde_json::Value {
+        serde_json::json!({
+            "departure_time": self.engine.departure_time.to_rfc3339(),
+            "remaining_seconds": self.engine.remaining_time(now).total_secs(),
+            "urgency": Urgency::for_remaining(&self.engine.remaining_time(now)).name(),
+            "muted": self.engine.muted,
+            "paused": self.engine.paused,
+            "snoozed_until": self
+                .engine
+                .snoozed_until
+                .filter(|until| until > now)
+                .map(|until| until.to_rfc3339()),
+            "next_notification": self.engine.notifications.pending.next().map(notification_json),
+            "emitted": self
+                .engine
+                .notifications
+                .emitted
+                .iter()
+                .map(notification_json)
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Carries out a command received from another program, answering with the resulting status.
+    #[cfg(feature = "control")]
+    pub fn handle_command(
+        &mut self,
+        command: ControlCommand,
+        now: &Timestamp,
+    ) -> serde_json::Value {
+        match command {
+            ControlCommand::Status => {}
+            ControlCommand::Snooze(span) => {
+                self.engine.snoozed_until = Some(*now + span);
+                self.engine.snoozes += 1;
+                #[cfg(feature = "history")]
+                if let Some((history, session)) = &self.history
+                    && let Err(report) = history.record_snooze(*session)
+                {
+                    warn!("cannot store the snooze in the history: {report:?}");
+                }
+            }
+            ControlCommand::Mute => self.engine.muted = true,
+            ControlCommand::Unmute => {
+                self.engine.muted = false;
+                self.engine.snoozed_until = None;
+            }
+            ControlCommand::SpeakNow => self.engine.update_requested = true,
+            ControlCommand::Push(span) => self.push(span.unwrap_or(self.push_increment), now),
+            ControlCommand::Quit => self.exit = true,
+        }
+        self.status(now)
+    }
+
+    /// Waits for the next request to the control server, forever without one.
+    pub async fn next_request(&mut self) -> ControlRequest {
+        #[cfg(feature = "control")]
+        if let Some(control) = &mut self.control
+            && let Some(request) = control.recv().await
+        {
+            return request;
+        }
+        std::future::pending().await
+    }
+
+    /// Carries out `request`, answering with the resulting status.
+    #[cfg(feature = "control")]
+    pub fn answer(&mut self, request: ControlRequest) -> AppResult<()> {
+        let now = Timestamp::now().change_context(AppError::Other)?;
+        let response = self.handle_command(request.command, &now);
+        request.reply(response);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "control"))]
+    pub fn answer(&mut self, request: ControlRequest) -> AppResult<()> {
+        match request {}
+    }
+}
+
+/// Resolves on SIGINT, or SIGTERM on Unix, which only set [`Session::interrupted`] otherwise,
+/// without waking up the countdown.
+async fn interruption() {
+    let interrupt = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = interrupt => {}
+        () = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::engine::tests::{FakeClock, RecordingSpeaker};
+    use crate::feature::coach::DefaultItCoach;
+    use crate::feature::sink::{SinkFilter, TerminalTitleSink};
+    use crate::feature::state::SessionState;
+    use crate::plan::Notification;
+
+    #[test]
+    fn session_resumes_where_it_left_off() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut session = Session::new(&plan, DefaultItCoach, 5).unwrap();
+        let emitted = Notification {
+            time: Timestamp::now().unwrap() - TimeSpan::of_minutes(5),
+            message: "Mancano 50 minuti".to_owned(),
+            urgency: Urgency::Low,
+        };
+
+        session.engine.restore(SessionState {
+            plan: plan.clone(),
+            emitted: vec![emitted.clone()],
+            snoozes: 2,
+            snoozed_until: None,
+            muted: true,
+        });
+
+        let saved = session.engine.state();
+        assert_eq!(vec![emitted], saved.emitted);
+        assert_eq!(2, saved.snoozes);
+        assert!(saved.muted);
+        // Not spoken while muted
+        assert!(!session.engine.update_requested);
+    }
+
+    #[test]
+    fn session_resync_drops_the_notifications_gone_by() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut session = Session::new(&plan, DefaultItCoach, 5).unwrap();
+        let resumed = Timestamp::now().unwrap() + TimeSpan::of_minutes(20);
+
+        session.resync(&resumed);
+
+        assert!(!session.engine.notifications.pending.is_empty());
+        assert!(
+            session
+                .engine
+                .notifications
+                .pending
+                .iter()
+                .all(|n| n.time >= resumed)
+        );
+        assert!(session.engine.update_requested);
+    }
+
+    #[cfg(feature = "control")]
+    #[test]
+    fn session_snooze_silences_for_a_while() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut session = Session::new(&plan, DefaultItCoach, 5).unwrap();
+        let now = Timestamp::new(2025, 10, 24, 17, 30, 00).unwrap();
+
+        let status = session.handle_command(ControlCommand::Snooze(TimeSpan::of_minutes(5)), &now);
+
+        assert!(session.engine.is_silenced(&(now + TimeSpan::of_minutes(4))));
+        assert!(!session.engine.is_silenced(&(now + TimeSpan::of_minutes(5))));
+        assert_eq!(900, status["remaining_seconds"]);
+        assert!(status["snoozed_until"].is_string());
+    }
+
+    #[cfg(feature = "control")]
+    #[test]
+    fn session_unmute_command_ends_the_snooze() {
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 18, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut session = Session::new(&plan, DefaultItCoach, 5).unwrap();
+        let now = Timestamp::new(2025, 10, 24, 17, 30, 00).unwrap();
+
+        session.handle_command(ControlCommand::Mute, &now);
+        session.handle_command(ControlCommand::Snooze(TimeSpan::of_minutes(5)), &now);
+        assert!(session.engine.is_silenced(&now));
+
+        session.handle_command(ControlCommand::Unmute, &now);
+        assert!(!session.engine.is_silenced(&now));
+    }
+
+    #[test]
+    fn session_ticks_by_the_clock_until_departure() {
+        let start = Timestamp::new(2025, 10, 24, 19, 40, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(start));
+        let engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+        let mut session = Session::with_engine(engine);
+        let path =
+            std::env::temp_dir().join(format!("rendezvous-session-{}.json", std::process::id()));
+        session.state_file = Some(StateFile::new(&path));
+        let mut speaker = RecordingSpeaker::default();
+
+        // Up to 19:45:01, the tick after the last notification
+        let mut now = start;
+        for _ in 0..=5 * 60 + 1 {
+            now = session.tick(&clock, &mut speaker).unwrap();
+            clock.advance(TimeSpan::of_seconds(1));
+        }
+        let saved = StateFile::new(&path).load().unwrap();
+        session.finish(&mut speaker, &now);
+
+        assert!(session.exit);
+        assert_eq!(6, speaker.spoken.len());
+        assert_eq!(6, saved.emitted.len());
+        assert!(StateFile::new(&path).load().is_err());
+    }
+
+    #[cfg(feature = "control")]
+    #[test]
+    fn session_snoozed_by_a_command_speaks_again_when_it_ends() {
+        let start = Timestamp::new(2025, 10, 24, 19, 40, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(start));
+        let engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+        let mut session = Session::with_engine(engine);
+        let mut speaker = RecordingSpeaker::default();
+
+        session.handle_command(ControlCommand::Snooze(TimeSpan::of_minutes(5)), &start);
+        for _ in 0..=5 * 60 {
+            session.tick(&clock, &mut speaker).unwrap();
+            clock.advance(TimeSpan::of_seconds(1));
+        }
+
+        // Only the one at 19:45, when the snooze is over
+        assert_eq!(6, session.engine.notifications.emitted.len());
+        assert_eq!(1, speaker.spoken.len());
+        assert_eq!(1, session.engine.snoozes);
+    }
+
+    #[test]
+    fn session_headless_runs_until_departure_on_the_session_runtime() {
+        let now = Timestamp::now().unwrap();
+        let plan = Plan {
+            rendezvous_time: now + TimeSpan::of_minutes(15) + TimeSpan::of_seconds(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let engine = Engine::new(&plan, DefaultItCoach, 5, now).unwrap();
+        let mut session = Session::with_engine(engine);
+        let mut speaker = RecordingSpeaker::default();
+
+        // Waiting for departure along with the signals, which need their driver
+        runtime()
+            .unwrap()
+            .block_on(session.run_headless(&mut speaker))
+            .unwrap();
+
+        assert!(session.exit);
+        assert_eq!(
+            Some("Ora di partire!"),
+            speaker.spoken.last().map(String::as_str)
+        );
+    }
+
+    #[cfg(all(unix, feature = "control"))]
+    #[tokio::test]
+    async fn session_headless_quits_on_a_command_right_away() {
+        use crate::feature::control::request;
+
+        let plan = Plan {
+            rendezvous_time: Timestamp::now().unwrap() + TimeSpan::of_hours(1),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let mut session = Session::new(&plan, DefaultItCoach, 5).unwrap();
+        let path = std::env::temp_dir().join(format!("rendezvous-headless-{}", std::process::id()));
+        let mut control = ControlServer::new();
+        control.listen_unix(&path).unwrap();
+        session.control = Some(control);
+        let client = std::thread::spawn(move || request(&path, "quit"));
+        let mut speaker = RecordingSpeaker::default();
+
+        session.run_headless(&mut speaker).await.unwrap();
+
+        assert!(session.exit);
+        assert!(client.join().unwrap().is_ok());
+        let farewell = speaker.spoken.last().unwrap();
+        assert!(farewell.starts_with("Appuntamento abbandonato a 4"));
+    }
+
+    #[test]
+    fn session_ticks_every_second_only_for_the_sinks_keeping_up() {
+        let start = Timestamp::new(2025, 10, 24, 19, 40, 00).unwrap();
+        let plan = Plan {
+            rendezvous_time: Timestamp::new(2025, 10, 24, 20, 00, 00).unwrap(),
+            trip_duration: TimeSpan::of_minutes(15),
+        };
+        let clock = FakeClock(Cell::new(start));
+        let engine = Engine::new(&plan, DefaultItCoach, 10, start).unwrap();
+        let mut session = Session::with_engine(engine);
+        let mut speaker = RecordingSpeaker::default();
+
+        assert_eq!(Some(Duration::ZERO), session.until_next_tick(&start));
+        let now = session.tick(&clock, &mut speaker).unwrap();
+        // Up to the next notification, at 19:41
+        let wait = session.until_next_tick(&now).unwrap();
+        assert!(wait > Duration::from_secs(59) && wait <= Duration::from_secs(60));
+
+        let title = TerminalTitleSink::new(Vec::new());
+        session.sinks.add("title", SinkFilter::ALL, Box::new(title));
+        assert!(session.until_next_tick(&now).unwrap() <= Duration::from_secs(1));
+    }
+}
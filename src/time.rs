@@ -6,9 +6,20 @@ use chrono::prelude::*;
 use chrono::{TimeDelta, TimeZone};
 use error_stack::{Report, ResultExt};
 
-#[derive(Debug, thiserror::Error)]
-#[error("time error")]
-pub struct TimeError;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TimeError {
+    /// Not in any of the formats of a time or a date and time
+    #[error("invalid time format")]
+    InvalidFormat,
+    /// In the format, but not a time of the calendar or of the local time zone
+    #[error("no such time")]
+    NonExistent,
+    /// Twice in the local time zone, e.g. when the clocks go back
+    #[error("ambiguous time")]
+    Ambiguous,
+    #[error("cannot read the clock")]
+    Clock,
+}
 
 pub type TimeResult<T> = Result<T, Report<TimeError>>;
 
@@ -19,8 +30,14 @@ pub struct TimeSpan(u64);
 
 impl Debug for TimeSpan {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let nt = NaiveTime::from_num_seconds_from_midnight_opt(self.0 as u32, 0).unwrap();
-        write!(f, "{}", nt.format("%H:%M:%S"))
+        // The hours going on past 24, e.g. for a rendezvous on another day
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            self.hours(),
+            self.minutes(),
+            self.seconds()
+        )
     }
 }
 
@@ -74,7 +91,7 @@ impl TimeSpan {
     pub fn parse(input: &str) -> TimeResult<TimeSpan> {
         let time = NaiveTime::parse_from_str(input, "%H:%M:%S")
             .or(NaiveTime::parse_from_str(input, "%H:%M"))
-            .change_context(TimeError)
+            .change_context(TimeError::InvalidFormat)
             .attach("invalid time")?;
         let delta = time.signed_duration_since(NaiveTime::MIN);
         let span = TimeSpan(delta.abs().num_seconds() as u64);
@@ -121,7 +138,7 @@ impl Display for Time {
 impl Time {
     pub fn new(hour: u32, min: u32, sec: u32) -> TimeResult<Self> {
         let naive = NaiveTime::from_hms_opt(hour, min, sec)
-            .ok_or(Report::new(TimeError))
+            .ok_or(Report::new(TimeError::NonExistent))
             .attach("invalid time")?;
         Ok(Self(naive))
     }
@@ -129,7 +146,7 @@ impl Time {
     pub fn parse(input: &str) -> TimeResult<Self> {
         let naive = NaiveTime::parse_from_str(input, "%H:%M:%S")
             .or(NaiveTime::parse_from_str(input, "%H:%M"))
-            .change_context(TimeError)
+            .change_context(TimeError::InvalidFormat)
             .attach("invalid time")?;
         Ok(Self(naive))
     }
@@ -172,40 +189,102 @@ impl Timestamp {
     pub fn now() -> TimeResult<Timestamp> {
         let ts = Local::now()
             .with_nanosecond(0)
-            .ok_or(Report::new(TimeError))
+            .ok_or(Report::new(TimeError::Clock))
             .attach("cannot determine the date and time")?;
         Ok(Timestamp(ts))
     }
 
+    /// Time left until the next whole second, when [`Timestamp::now`] changes.
+    pub fn until_next_second() -> std::time::Duration {
+        let elapsed = Local::now().timestamp_subsec_nanos().min(999_999_999);
+        std::time::Duration::from_nanos(u64::from(1_000_000_000 - elapsed))
+    }
+
     pub fn parse_today_time(input: &str) -> TimeResult<Timestamp> {
         let parsed_time = Time::parse(input)?;
         let now = Timestamp::now()?;
         now.with_time(&parsed_time)
     }
 
+    /// Parses a local date and time, e.g. `2025-10-24 20:00`, or a timestamp in RFC 3339 format.
+    pub fn parse(input: &str) -> TimeResult<Timestamp> {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(input) {
+            return Ok(Self(ts.with_timezone(&Local)));
+        }
+        let naive = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S")
+            .or(NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M"))
+            .change_context(TimeError::InvalidFormat)
+            .attach("invalid date and time")?;
+        match Local.from_local_datetime(&naive) {
+            LocalResult::Single(ldt) => Ok(Self(ldt)),
+            LocalResult::Ambiguous(_, _) => {
+                Err(TimeError::Ambiguous).attach("ambiguous time for the date")
+            }
+            LocalResult::None => Err(TimeError::NonExistent).attach("invalid date/time"),
+        }
+    }
+
     pub fn new(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> TimeResult<Self> {
         let date = NaiveDate::from_ymd_opt(year, month, day)
-            .ok_or(TimeError)
+            .ok_or(TimeError::NonExistent)
             .attach("invalid date")?;
         let time = NaiveTime::from_hms_opt(hour, min, sec)
-            .ok_or(TimeError)
+            .ok_or(TimeError::NonExistent)
             .attach("invalid time")?;
         let date_time = NaiveDateTime::new(date, time);
         match Local.from_local_datetime(&date_time) {
             LocalResult::Single(ldt) => Ok(Self(ldt)),
-            LocalResult::Ambiguous(_, _) => Err(TimeError).attach("ambiguous time for the date"),
-            LocalResult::None => Err(TimeError).attach("invalid date/time"),
+            LocalResult::Ambiguous(_, _) => {
+                Err(TimeError::Ambiguous).attach("ambiguous time for the date")
+            }
+            LocalResult::None => Err(TimeError::NonExistent).attach("invalid date/time"),
         }
     }
 
     pub fn with_time(&self, time: &Time) -> TimeResult<Timestamp> {
         match self.0.with_time(time.0) {
             LocalResult::Single(new_ts) => Ok(Self(new_ts)),
-            LocalResult::Ambiguous(_, _) => Err(TimeError).attach("ambiguous time for today"),
-            LocalResult::None => Err(TimeError).attach("invalid time for today"),
+            LocalResult::Ambiguous(_, _) => {
+                Err(TimeError::Ambiguous).attach("ambiguous time for today")
+            }
+            LocalResult::None => Err(TimeError::NonExistent).attach("invalid time for today"),
         }
     }
 
+    /// Time of the day, e.g. `20:00`.
+    pub fn to_hh_mm(&self) -> String {
+        self.0.format("%H:%M").to_string()
+    }
+
+    /// Time of the day with the seconds, e.g. `20:00:05`.
+    pub fn to_hh_mm_ss(&self) -> String {
+        self.0.format("%H:%M:%S").to_string()
+    }
+
+    /// The timestamp in RFC 3339 format, e.g. `2025-10-24T17:40:00+02:00`.
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339()
+    }
+
+    /// The local time of `secs` seconds since the Unix epoch.
+    pub fn from_unix(secs: i64) -> TimeResult<Timestamp> {
+        DateTime::from_timestamp(secs, 0)
+            .map(|ts| Self(ts.with_timezone(&Local)))
+            .ok_or(Report::new(TimeError::NonExistent))
+            .attach(format!("{secs} seconds since the epoch are out of range"))
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn to_unix(&self) -> i64 {
+        self.0.timestamp()
+    }
+
+    /// `span` before this timestamp, or `None` when out of range.
+    pub fn checked_sub(&self, span: TimeSpan) -> Option<Timestamp> {
+        let delta = TimeDelta::try_seconds(i64::try_from(span.0).ok()?)?;
+        self.0.checked_sub_signed(delta).map(Self)
+    }
+
     pub fn time_span_from(&self, other: &Timestamp) -> TimeSpan {
         let delta_seconds = (self.0 - other.0).num_seconds();
         if delta_seconds >= 0 {
@@ -236,6 +315,14 @@ mod tests {
         assert_eq!("01:15:22", format!("{}", ts));
     }
 
+    #[test]
+    fn time_span_formatting_past_a_day() {
+        let ts = TimeSpan::new(26, 3, 7);
+
+        assert_eq!("26:03:07", ts.to_string());
+        assert_eq!("100:00:00", TimeSpan::of_hours(100).to_string());
+    }
+
     #[test]
     fn time_span_should_be_buildable_with_seconds() {
         let built = TimeSpan::of_seconds(5);
@@ -373,16 +460,52 @@ mod tests {
         assert!(format!("{ts}").starts_with("2025-10-18 16:00:00"));
     }
 
+    #[test]
+    fn timestamp_parse_should_parse_date_and_time() {
+        assert_eq!(
+            Timestamp::new(2025, 10, 24, 20, 0, 0).unwrap(),
+            Timestamp::parse("2025-10-24 20:00").unwrap()
+        );
+        assert_eq!(
+            Timestamp::new(2025, 10, 24, 20, 0, 30).unwrap(),
+            Timestamp::parse("2025-10-24 20:00:30").unwrap()
+        );
+        let ts = Timestamp::new(2025, 10, 24, 20, 0, 0).unwrap();
+        assert_eq!(ts, Timestamp::parse(&ts.to_rfc3339()).unwrap());
+        assert!(Timestamp::parse("20:00").is_err());
+    }
+
+    #[test]
+    fn timestamp_parse_tells_the_format_from_the_calendar() {
+        let format = Timestamp::parse("24/10/2025 20:00").unwrap_err();
+
+        assert_eq!(&TimeError::InvalidFormat, format.current_context());
+        assert_eq!(
+            &TimeError::NonExistent,
+            Timestamp::new(2025, 2, 30, 20, 0, 0)
+                .unwrap_err()
+                .current_context()
+        );
+    }
+
+    #[test]
+    fn timestamp_to_and_from_unix() {
+        let ts = Timestamp::new(2025, 10, 24, 20, 0, 0).unwrap();
+
+        assert_eq!(ts, Timestamp::from_unix(ts.to_unix()).unwrap());
+        assert!(Timestamp::from_unix(i64::MAX).is_err());
+    }
+
     #[test]
     fn time_should_have_a_readable_debug_impl() {
-        let time = Time::new(11, 02, 15).unwrap();
+        let time = Time::new(11, 2, 15).unwrap();
 
         assert_eq!("11:02:15", format!("{:?}", time));
     }
 
     #[test]
     fn time_should_have_a_readable_display_impl() {
-        let time = Time::new(11, 02, 15).unwrap();
+        let time = Time::new(11, 2, 15).unwrap();
 
         assert_eq!("11:02:15", format!("{}", time));
     }
@@ -398,7 +521,7 @@ mod tests {
     fn time_should_be_parsed_from_correct_string_without_seconds() {
         let parsed = Time::parse("11:06").unwrap();
 
-        assert_eq!(Time::new(11, 06, 00).unwrap(), parsed);
+        assert_eq!(Time::new(11, 6, 0).unwrap(), parsed);
     }
 
     #[test]
@@ -433,6 +556,14 @@ mod tests {
         assert_eq!(expected, updated);
     }
 
+    #[test]
+    fn timestamp_until_next_second_is_within_a_second() {
+        let wait = Timestamp::until_next_second();
+
+        assert!(!wait.is_zero());
+        assert!(wait <= std::time::Duration::from_secs(1));
+    }
+
     #[test]
     fn timestamp_time_span_from_same_timestamp() {
         let original = Timestamp::new(2025, 10, 18, 16, 0, 0).unwrap();